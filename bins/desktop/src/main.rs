@@ -6,7 +6,8 @@ use panic_message::panic_message;
 use structopt::StructOpt;
 
 use lc3_tui::{DynTui, ProgramSource};
-use lc3_tui::layout;
+use lc3_tui::layout::{self, LayoutDescription};
+use lc3_tui::config::Config;
 use lc3_application_support::init::{
     BlackBox, BoardDevice, BoardConfig, SimDevice, SimWithRpcDevice
 };
@@ -134,6 +135,38 @@ struct Args {
     /// Build/run with without the OS
     #[structopt(long, help = "Builds .asm files without the UTP LC-3 OS and does *not* skip past the OS on loads and resets when this is set")]
     without_os: bool,
+
+    /// Config file
+    #[structopt(short, long, help = "Path to a config file (key bindings, widget sizes, etc.); defaults to `$TUI_CONFIG` or `~/.config/utp-tui/config.toml` when they exist")]
+    config: Option<PathBuf>,
+
+    /// Layout
+    #[structopt(short, long, default_value = "full", help = "Name of a built-in layout (`full` or `intro`) or the path to a layout file")]
+    layout: String,
+
+    /// Theme
+    #[structopt(short, long, help = "Colour theme to start with: `dark`, `light`, `high-contrast`, `monochrome`, or the path to a theme file (overrides the config file)")]
+    theme: Option<String>,
+
+    /// No colour
+    #[structopt(long, help = "Use the monochrome theme and show text markers for things that are otherwise only coloured (also enabled by `$NO_COLOR`)")]
+    no_colour: bool,
+
+    /// Input script
+    #[structopt(long, help = "Path to a file whose contents are fed to the program's input as it reads it")]
+    input: Option<PathBuf>,
+
+    /// Input script delay
+    #[structopt(long, help = "Milliseconds to wait between characters from `--input` (overrides the config file)")]
+    input_delay: Option<TimeInMs>,
+
+    /// Refeed input on reset
+    #[structopt(long, help = "Start `--input` over from the beginning when the machine is reset")]
+    refeed_input: bool,
+
+    /// Stimulus script
+    #[structopt(long, help = "Path to a stimulus script: changes to make to the peripherals' inputs as the program runs (i.e. `at 500ms gpio 3 = 1`, `every 100ms toggle gpio 0`)")]
+    stimulus: Option<PathBuf>,
 }
 
 pub fn with_stack_size<R: Send + 'static, F: FnOnce() -> R + Send + 'static>(ss: usize, f: F) -> anyhow::Result<R> {
@@ -176,11 +209,53 @@ fn main() -> anyhow::Result<()> {
 
         tui.set_use_os(!options.without_os);
 
+        let mut config = if let Some(ref path) = options.config {
+            Config::load(path)?
+        } else {
+            Config::load_default()?
+        };
+        if let Some(theme) = options.theme.clone() {
+            config.theme = Some(theme);
+        }
+        if let Some(TimeInMs(delay)) = options.input_delay {
+            config.input_delay_ms = delay.as_millis() as u64;
+        }
+        config.refeed_input_on_reset |= options.refeed_input;
+
+        let input_delay = Duration::from_millis(config.input_delay_ms);
+        let refeed_input = config.refeed_input_on_reset;
+
+        tui.set_config(config)?;
+        if options.no_colour {
+            tui.set_no_colour();
+        }
+
+        if let Some(ref path) = options.input {
+            let script = lc3_tui::InputScript::load(path)?
+                .with_delay(input_delay)
+                .with_refeed_on_reset(refeed_input);
+
+            tui.set_input_script(script);
+        }
+
+        if let Some(ref path) = options.stimulus {
+            tui.set_stimulus_script(lc3_tui::StimulusScript::load(path)?);
+        }
+
+        // Widget sizes get saved back to the config file on exit (nothing else
+        // is, so the flags above don't end up in it):
+        if let Some(path) = options.config.clone().or_else(Config::default_path) {
+            tui.set_config_path(path);
+        }
+
+        let layout_desc = LayoutDescription::load_by_name_or_path(&options.layout)?;
+
         let name = format!("UTP LC-3 Simulator (running {:#})", options.device);
 
         let no_extra_tabs = Vec::new();
-        let layout = layout::layout(
+        let layout = layout::layout_from_description(
             Some(name.as_ref()),
+            &layout_desc,
             no_extra_tabs
         );
 
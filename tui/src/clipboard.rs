@@ -0,0 +1,161 @@
+//! Getting text out of (and back into) the TUI.
+//!
+//! Because we capture the mouse, the terminal's own selection doesn't work so
+//! copied text is sent to the terminal with the OSC 52 escape sequence which
+//! asks the terminal to put it on the system clipboard. This works over SSH
+//! and in xterm.js.
+//!
+//! Terminals that don't support OSC 52 ignore it so when we think it's not
+//! supported (or when [`OSC52_ENV_VAR`] is `0`) we write the text to a file
+//! only the user can read instead (not on wasm); see [`fallback_path`].
+//!
+//! [`OSC52_ENV_VAR`]: crate::env::OSC52_ENV_VAR
+
+use std::io::Write;
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::PathBuf;
+
+/// Where copied text ended up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Copied {
+    Osc52,
+    #[cfg(not(target_arch = "wasm32"))]
+    File(PathBuf),
+}
+
+/// The OSC 52 sequence that sets the clipboard to `text`.
+pub fn osc52(text: &str) -> String {
+    format!("\x1B]52;c;{}\x07", base64(text.as_bytes()))
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut out = String::with_capacity((bytes.len() + 2) / 3 * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+
+    out
+}
+
+/// Copies `text` to the clipboard by writing OSC 52 to `term`, or to the
+/// fallback file when OSC 52 isn't available.
+pub fn copy(term: &mut impl Write, text: &str) -> anyhow::Result<Copied> {
+    // OSC 52 is always used on the web.
+    specialize! { [var: fallback]
+        desktop => { if osc52_supported() { None } else { Some(write_fallback(text)) } }
+        web => { None }
+    }
+
+    if let Some(res) = fallback {
+        return res;
+    }
+
+    term.write_all(osc52(text).as_bytes())?;
+    term.flush()?;
+
+    Ok(Copied::Osc52)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_fallback(text: &str) -> anyhow::Result<Copied> {
+    use std::fs::{self, OpenOptions};
+
+    let path = fallback_path()
+        .ok_or_else(|| anyhow::anyhow!("there's nowhere to put the text (neither `XDG_RUNTIME_DIR` nor `HOME` are set)"))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    // Start from scratch so we never write through a link or into a file
+    // someone else made:
+    match fs::remove_file(&path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+        _ => {},
+    }
+
+    let mut options = OpenOptions::new();
+    let _ = options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        let _ = options.mode(0o600);
+    }
+
+    options.open(&path)?.write_all(text.as_bytes())?;
+
+    Ok(Copied::File(path))
+}
+
+/// Where copied text goes when OSC 52 isn't available: the user's runtime
+/// directory (`$XDG_RUNTIME_DIR`) or, failing that, our config directory.
+/// Never somewhere shared, like the temp directory.
+// No files on wasm.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn fallback_path() -> Option<PathBuf> {
+    let dir = std::env::var_os("XDG_RUNTIME_DIR")
+        .map(|d| PathBuf::from(d).join("utp-tui"))
+        .or_else(crate::config::Config::default_dir)?;
+
+    Some(dir.join("clipboard.txt"))
+}
+
+/// Our best guess at whether the terminal we're in supports OSC 52.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn osc52_supported() -> bool {
+    use crate::env::OSC52_ENV_VAR;
+    use std::env::var;
+
+    if let Ok(v) = var(OSC52_ENV_VAR) {
+        return !matches!(v.as_str(), "0" | "false" | "off" | "no");
+    }
+
+    // The Linux console and Terminal.app don't support it:
+    let term = var("TERM").unwrap_or_default();
+    let term_program = var("TERM_PROGRAM").unwrap_or_default();
+
+    !(term.is_empty() || term == "dumb" || term == "linux" || term_program == "Apple_Terminal")
+}
+
+/// Text to paste when nothing has been copied in this session: the contents of
+/// the fallback file (if it exists, and is a plain file and not a link).
+#[cfg(not(target_arch = "wasm32"))]
+pub fn fallback_contents() -> Option<String> {
+    let path = fallback_path()?;
+    if !std::fs::symlink_metadata(&path).ok()?.file_type().is_file() {
+        return None;
+    }
+
+    std::fs::read_to_string(path).ok()
+}
+
+#[cfg(test)]
+mod clipboard_tests {
+    use super::*;
+    use pretty_assertions::assert_eq as eq;
+
+    #[test]
+    fn base64_padding() {
+        eq!(base64(b""), "");
+        eq!(base64(b"f"), "Zg==");
+        eq!(base64(b"fo"), "Zm8=");
+        eq!(base64(b"foo"), "Zm9v");
+        eq!(base64(b"foobar"), "Zm9vYmFy");
+    }
+
+    #[test]
+    fn sequence() {
+        eq!(osc52("hi"), "\x1B]52;c;aGk=\x07");
+    }
+}
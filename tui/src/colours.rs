@@ -2,8 +2,12 @@
 
 use crate::env::COLOUR_PALETTE_ENV_VAR;
 
+use serde::Deserialize;
 use tui::style::Color as TuiColour;
 
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+
 macro_rules! palette {
     ($($field:tt $(= $default:expr)?),* $(,)?) => {
         pub trait ColourPalette { $(
@@ -12,16 +16,31 @@ macro_rules! palette {
         )*}
 
         #[allow(non_snake_case)]
+        #[derive(Debug, Clone, PartialEq)]
         pub struct CurrentPalette {
             $(pub $field: TuiColour,)*
         }
 
         impl CurrentPalette {
+            /// The names of the palette's fields; these are the keys used in
+            /// theme files.
+            pub const FIELDS: &'static [&'static str] = &[$(stringify!($field),)*];
+
             fn from<P: ColourPalette>(_p: P) -> Self {
                 Self {
                     $($field: P::$field,)*
                 }
             }
+
+            /// Sets a field by name; returns false if there's no such field.
+            pub fn set(&mut self, field: &str, colour: TuiColour) -> bool {
+                match field {
+                    $(stringify!($field) => self.$field = colour,)*
+                    _ => return false,
+                }
+
+                true
+            }
         }
 
         impl<P: ColourPalette> From<P> for CurrentPalette {
@@ -111,7 +130,171 @@ palette! {
 
 declare_palette! { DefaultPalette = { }}
 
-// TODO: a dark palette?
+// For terminals with light backgrounds.
+declare_palette! { LightPalette = {
+    Data: TuiColour::Green,
+    Inst: TuiColour::Blue,
+    Name: TuiColour::DarkGray,
+    Addr: TuiColour::DarkGray,
+    Pc: TuiColour::Rgb(0xC0, 0x50, 0x00),
+    Num: TuiColour::Rgb(0xC0, 0x50, 0x00),
+
+    DataT: TuiColour::Green,
+    AddrT: TuiColour::Gray,
+    NumT: TuiColour::Rgb(0xA0, 0x60, 0x20),
+
+    z_bit: TuiColour::Black,
+    p_bit: TuiColour::Rgb(0x3A, 0x7D, 0x1C),
+    n_bit: TuiColour::Rgb(0xB0, 0x30, 0x80),
+
+    MemRegHighlight: TuiColour::DarkGray,
+
+    ConsoleIn: TuiColour::Rgb(0xC0, 0x50, 0x00),
+    ConsoleOut: TuiColour::Rgb(0xC0, 0x50, 0x00),
+    ConsolePrompt: TuiColour::Blue,
+    ConsoleHelp: TuiColour::DarkGray,
+
+    Error: TuiColour::Red,
+    Halted: TuiColour::Black,
+    Depth: TuiColour::Rgb(0x90, 0x70, 0x00),
+    Pause: TuiColour::Rgb(0x90, 0x70, 0x00),
+
+    Highlight: TuiColour::Blue,
+
+    LoadText: TuiColour::Black,
+    LoadPendingChanges: TuiColour::Red,
+
+    Title: TuiColour::Rgb(0xC0, 0x50, 0x00),
+
+    Modeline: TuiColour::Black,
+    StepButtons: TuiColour::Blue,
+    LoadB: TuiColour::Black,
+    mDefault: TuiColour::Gray,
+
+    Disabled: TuiColour::Gray,
+}}
+
+// Bright colours only; meant for projectors and washed out screens.
+declare_palette! { HighContrastPalette = {
+    Data: TuiColour::LightGreen,
+    Inst: TuiColour::LightCyan,
+    Name: TuiColour::White,
+    Addr: TuiColour::White,
+    Pc: TuiColour::LightYellow,
+    Num: TuiColour::LightYellow,
+
+    DataT: TuiColour::LightGreen,
+    AddrT: TuiColour::White,
+    NumT: TuiColour::LightYellow,
+
+    Breakpoint: TuiColour::LightRed,
+    Watchpoint: TuiColour::LightBlue,
+    BWHighlight: TuiColour::LightMagenta,
+
+    Privilege: TuiColour::LightCyan,
+    Priority: TuiColour::LightRed,
+    n_bit: TuiColour::LightMagenta,
+    z_bit: TuiColour::White,
+    p_bit: TuiColour::LightGreen,
+
+    MemRegHighlight: TuiColour::White,
+    RegHighlight: TuiColour::LightMagenta,
+
+    ConsoleIn: TuiColour::LightYellow,
+    ConsoleOut: TuiColour::White,
+    ConsolePrompt: TuiColour::LightCyan,
+    ConsoleHelp: TuiColour::White,
+    ConsoleRequest: TuiColour::LightRed,
+    InvalidInput: TuiColour::LightRed,
+
+    Run: TuiColour::LightGreen,
+    Pause: TuiColour::LightYellow,
+    Success: TuiColour::LightGreen,
+    Depth: TuiColour::LightYellow,
+
+    Highlight: TuiColour::LightCyan,
+
+    Focus: TuiColour::LightRed,
+    Reset: TuiColour::LightRed,
+
+    Title: TuiColour::LightYellow,
+    Border: TuiColour::White,
+    Help: TuiColour::LightGreen,
+
+    StepButtons: TuiColour::LightCyan,
+    mDefault: TuiColour::White,
+
+    CallStackSupervisorMode: TuiColour::LightRed,
+    CallStackUserMode: TuiColour::LightGreen,
+
+    InProgress: TuiColour::LightMagenta,
+    Disabled: TuiColour::Gray,
+}}
+
+// No colours at all; everything uses the terminal's default foreground
+// colour except for the focused widget's border.
+declare_palette! { MonochromePalette = {
+    Data: TuiColour::Reset,
+    Inst: TuiColour::Reset,
+    Name: TuiColour::Reset,
+    Addr: TuiColour::Reset,
+    Pc: TuiColour::Reset,
+    Num: TuiColour::Reset,
+
+    DataT: TuiColour::Reset,
+    AddrT: TuiColour::Reset,
+    NumT: TuiColour::Reset,
+
+    Breakpoint: TuiColour::Reset,
+    Watchpoint: TuiColour::Reset,
+    BWHighlight: TuiColour::Reset,
+
+    Privilege: TuiColour::Reset,
+    Priority: TuiColour::Reset,
+    n_bit: TuiColour::Reset,
+    z_bit: TuiColour::Reset,
+    p_bit: TuiColour::Reset,
+
+    MemRegHighlight: TuiColour::Reset,
+    RegHighlight: TuiColour::Reset,
+
+    ConsoleIn: TuiColour::Reset,
+    ConsoleOut: TuiColour::Reset,
+    ConsolePrompt: TuiColour::Reset,
+    ConsoleHelp: TuiColour::Reset,
+    ConsoleRequest: TuiColour::Reset,
+    InvalidInput: TuiColour::Reset,
+
+    Run: TuiColour::Reset,
+    Pause: TuiColour::Reset,
+    Error: TuiColour::Reset,
+    Success: TuiColour::Reset,
+    Halted: TuiColour::Reset,
+    Depth: TuiColour::Reset,
+
+    Highlight: TuiColour::Reset,
+
+    Focus: TuiColour::White,
+    Reset: TuiColour::Reset,
+    LoadText: TuiColour::Reset,
+    LoadNoChanges: TuiColour::Reset,
+    LoadPendingChanges: TuiColour::Reset,
+
+    Title: TuiColour::Reset,
+    Border: TuiColour::DarkGray,
+    Help: TuiColour::Reset,
+
+    Modeline: TuiColour::Reset,
+    StepButtons: TuiColour::Reset,
+    LoadB: TuiColour::Reset,
+    mDefault: TuiColour::Reset,
+
+    CallStackSupervisorMode: TuiColour::Reset,
+    CallStackUserMode: TuiColour::Reset,
+
+    InProgress: TuiColour::Reset,
+    Disabled: TuiColour::DarkGray,
+}}
 
 /// Colours from the [nord colour palette](https://www.nordtheme.com/docs/colors-and-palettes).
 pub mod nord {
@@ -447,24 +630,182 @@ pub mod nord {
     }}
 }
 
-lazy_static::lazy_static! {
-    pub static ref PALETTE: CurrentPalette = {
-        if let Some(palette) = std::env::var_os(COLOUR_PALETTE_ENV_VAR) {
-            match palette.to_str().unwrap() {
-                "nord-polar-night" => nord::PolarNight.into(),
-                "nord-snow-storm" => nord::SnowStorm.into(),
-                "nord-frost" => nord::Frost.into(),
-                "nord" | "nord-aurora" => nord::Aurora.into(),
-                _ => DefaultPalette.into(),
+/// Parses a colour name (i.e. `"light-red"`, `"blue"`, `"reset"`) or a hex
+/// colour (i.e. `"#FF9740"`).
+pub fn parse_colour(s: &str) -> Option<TuiColour> {
+    use TuiColour::*;
+
+    if let Some(hex) = s.strip_prefix('#') {
+        if hex.len() != 6 {
+            return None;
+        }
+
+        let v = u32::from_str_radix(hex, 16).ok()?;
+        return Some(Rgb((v >> 16) as u8, (v >> 8) as u8, v as u8));
+    }
+
+    Some(match s.to_lowercase().replace('_', "-").as_str() {
+        "reset" | "default" => Reset,
+        "black" => Black,
+        "red" => Red,
+        "green" => Green,
+        "yellow" => Yellow,
+        "blue" => Blue,
+        "magenta" => Magenta,
+        "cyan" => Cyan,
+        "gray" | "grey" => Gray,
+        "dark-gray" | "dark-grey" => DarkGray,
+        "light-red" => LightRed,
+        "light-green" => LightGreen,
+        "light-yellow" => LightYellow,
+        "light-blue" => LightBlue,
+        "light-magenta" => LightMagenta,
+        "light-cyan" => LightCyan,
+        "white" => White,
+        _ => return None,
+    })
+}
+
+/// A named palette.
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub name: String,
+    pub palette: CurrentPalette,
+}
+
+/// The format of theme files:
+///
+/// ```toml
+/// name = "projector"
+/// # Optional; colours that aren't listed are taken from this theme.
+/// base = "high-contrast"
+///
+/// [colours]
+/// Title = "yellow"
+/// Border = "#FFFFFF"
+/// ```
+///
+/// The keys in `[colours]` are the fields in [`CurrentPalette::FIELDS`];
+/// colours are parsed with [`parse_colour`].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ThemeFile {
+    name: Option<String>,
+    base: Option<String>,
+    #[serde(default)]
+    colours: BTreeMap<String, String>,
+}
+
+impl Theme {
+    pub fn new<P: ColourPalette>(name: &str, palette: P) -> Self {
+        Self { name: name.to_string(), palette: palette.into() }
+    }
+
+    /// The themes that ship with the TUI, in the order they're cycled
+    /// through.
+    pub fn builtin() -> Vec<Theme> {
+        vec![
+            Theme::new("dark", DefaultPalette),
+            Theme::new("light", LightPalette),
+            Theme::new("high-contrast", HighContrastPalette),
+            Theme::new("monochrome", MonochromePalette),
+        ]
+    }
+
+    pub fn builtin_named(name: &str) -> Option<Theme> {
+        Some(match name {
+            "default" | "dark" => Theme::new("dark", DefaultPalette),
+            "light" => Theme::new("light", LightPalette),
+            "high-contrast" => Theme::new("high-contrast", HighContrastPalette),
+            "monochrome" => Theme::new("monochrome", MonochromePalette),
+            "nord-polar-night" => Theme::new(name, nord::PolarNight),
+            "nord-snow-storm" => Theme::new(name, nord::SnowStorm),
+            "nord-frost" => Theme::new(name, nord::Frost),
+            "nord" | "nord-aurora" => Theme::new(name, nord::Aurora),
+            _ => return None,
+        })
+    }
+
+    /// The theme the palette starts out as: the one named by `TUI_PALETTE`
+    /// or, if that isn't set, `monochrome` when `NO_COLOR` is and `dark`
+    /// otherwise.
+    pub fn startup() -> Theme {
+        std::env::var_os(COLOUR_PALETTE_ENV_VAR)
+            .and_then(|p| p.to_str().and_then(Theme::builtin_named))
+            .unwrap_or_else(|| if crate::markers::no_colour_requested() {
+                Theme::new("monochrome", MonochromePalette)
+            } else {
+                Theme::new("dark", DefaultPalette)
+            })
+    }
+
+    /// Where the [startup theme](Theme::startup) is in `themes`; it's added
+    /// to the end if it isn't there.
+    pub fn find_startup(themes: &mut Vec<Theme>) -> usize {
+        let startup = Self::startup();
+        match themes.iter().position(|t| t.name == startup.name) {
+            Some(idx) => idx,
+            None => {
+                themes.push(startup);
+                themes.len() - 1
+            },
+        }
+    }
+
+    /// `default_name` is used if the theme doesn't have a name of its own.
+    pub fn from_toml(src: &str, default_name: &str) -> anyhow::Result<Self> {
+        let file: ThemeFile = toml::from_str(src)?;
+
+        let mut palette = match file.base {
+            Some(ref base) => Self::builtin_named(base)
+                .ok_or_else(|| anyhow::anyhow!("Unknown base theme `{}`", base))?
+                .palette,
+            None => DefaultPalette.into(),
+        };
+
+        for (field, colour) in file.colours.iter() {
+            let colour = parse_colour(colour)
+                .ok_or_else(|| anyhow::anyhow!("Invalid colour `{}` for `{}`", colour, field))?;
+
+            if !palette.set(field, colour) {
+                anyhow::bail!("Unknown palette field `{}`; expected one of: {}", field, CurrentPalette::FIELDS.join(", "));
             }
-        } else {
-            DefaultPalette.into()
         }
-    };
+
+        Ok(Self {
+            name: file.name.unwrap_or_else(|| default_name.to_string()),
+            palette,
+        })
+    }
+}
+
+// No files on wasm.
+#[cfg(not(target_arch = "wasm32"))]
+impl Theme {
+    pub fn load(path: &std::path::Path) -> anyhow::Result<Self> {
+        use anyhow::Context;
+
+        let src = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read the theme file at `{}`", path.display()))?;
+        let name = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+
+        Self::from_toml(&src, &name)
+            .with_context(|| format!("Invalid theme file at `{}`", path.display()))
+    }
+}
+
+lazy_static::lazy_static! {
+    /// The palette currently in use; see [`set_palette`].
+    pub static ref PALETTE: RwLock<CurrentPalette> = RwLock::new(Theme::startup().palette);
+}
+
+/// Switches the palette used by everything drawn from now on.
+pub fn set_palette(palette: CurrentPalette) {
+    *PALETTE.write().unwrap() = palette;
 }
 
 #[macro_export]
 #[doc(hidden)]
-macro_rules! c { ($nom:tt) => { (*$crate::colours::PALETTE).$nom }; }
+macro_rules! c { ($nom:tt) => { $crate::colours::PALETTE.read().unwrap().$nom }; }
 
 pub use crate::c;
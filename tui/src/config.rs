@@ -0,0 +1,318 @@
+//! User configuration for the TUI.
+//!
+//! This is read from a TOML file; every section is optional and anything that
+//! is left out falls back to its default.
+
+use crate::tui::symbols::parse_number;
+
+use lc3_isa::Addr;
+use serde::{Deserialize, Serialize};
+
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::path::PathBuf;
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Config {
+    /// Key binding overrides: action name to a list of key chords.
+    ///
+    /// See [`Keymap`](crate::keymap::Keymap).
+    pub keys: BTreeMap<String, Vec<String>>,
+
+    /// The theme to start with: the name of a built-in theme, the name of a
+    /// theme in `theme-files`, or a path to a theme file.
+    ///
+    /// See [`Theme`](crate::colours::Theme).
+    pub theme: Option<String>,
+
+    /// Use the monochrome theme and mark things that are otherwise only shown
+    /// with colour (i.e. the PC row, changed registers) with text.
+    ///
+    /// This is also turned on by the `NO_COLOR` env var; see
+    /// [`markers`](crate::markers).
+    pub no_colour: bool,
+
+    /// Show the text markers but keep the colours.
+    pub markers: bool,
+
+    /// Start with the console sending input a line at a time (with line
+    /// editing and history) instead of as it's typed.
+    pub line_buffered: bool,
+
+    /// Milliseconds to wait between characters of input scripts.
+    pub input_delay_ms: u64,
+
+    /// Start input scripts over when the machine is reset.
+    pub refeed_input_on_reset: bool,
+
+    /// Where `save-transcript` writes the console to; defaults to
+    /// `transcript.txt` in the working directory. The output with its escape
+    /// codes intact goes next to it, with an `.ansi` extension.
+    pub transcript_path: Option<PathBuf>,
+
+    /// Start every line of saved transcripts with when it was written and the
+    /// PC at the time, and include the input the program took.
+    pub transcript_timestamps: bool,
+
+    /// How many lines of console output to keep (10,000 by default); older
+    /// lines are dropped.
+    pub console_lines: Option<usize>,
+
+    /// How many lines of the log to keep (10,000 by default).
+    pub log_lines: Option<usize>,
+
+    /// How many events the debug event log keeps (100,000 by default).
+    pub event_log_lines: Option<usize>,
+
+    /// A directory to save dropped lines to instead of discarding them; they
+    /// are appended to `console.txt`, `log.txt` and `events.txt` in it.
+    pub spill_dir: Option<PathBuf>,
+
+    /// Extra theme files; these are cycled through along with the built-in
+    /// themes.
+    pub theme_files: Vec<PathBuf>,
+
+    /// Where the program's stack is allowed to be (i.e. `"x4000-xFE00"`);
+    /// the stack inspector warns when R6 leaves it. Defaults to
+    /// [`StackRegion::default`].
+    pub stack_region: Option<StackRegion>,
+
+    /// Sizes (as percentages) of the widgets in resized splits, by split id.
+    ///
+    /// The TUI fills this in when widgets are resized and saves it on exit.
+    pub panes: BTreeMap<String, Vec<u16>>,
+}
+
+/// A range of addresses (both ends included) that the stack has to stay in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct StackRegion {
+    pub low: Addr,
+    pub high: Addr,
+}
+
+impl Default for StackRegion {
+    /// User memory, up to (and including) the first device register: an empty
+    /// stack's pointer is one past its end.
+    fn default() -> Self {
+        Self { low: 0x3000, high: 0xFE00 }
+    }
+}
+
+impl StackRegion {
+    pub fn contains(&self, addr: Addr) -> bool {
+        (self.low..=self.high).contains(&addr)
+    }
+}
+
+impl TryFrom<String> for StackRegion {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, String> {
+        let err = || format!("`{}` isn't a stack region; they look like `x4000-xFE00`", s);
+
+        let (low, high) = s.split_once('-').ok_or_else(err)?;
+        match (parse_number(low), parse_number(high)) {
+            (Some(low), Some(high)) if low <= high => Ok(Self { low, high }),
+            _ => Err(err()),
+        }
+    }
+}
+
+impl From<StackRegion> for String {
+    fn from(region: StackRegion) -> String {
+        format!("x{:04X}-x{:04X}", region.low, region.high)
+    }
+}
+
+impl Config {
+    pub fn from_toml(src: &str) -> anyhow::Result<Self> {
+        Ok(toml::from_str(src)?)
+    }
+
+    pub fn to_toml(&self) -> anyhow::Result<String> {
+        // Going through a `Value` puts the tables (`keys`, `panes`) after the
+        // plain values; serializing `self` directly fails with
+        // `ValueAfterTable` once `keys` has anything in it.
+        Ok(toml::to_string_pretty(&toml::Value::try_from(self)?)?)
+    }
+
+    /// `src` (a config file) with its `[panes]` table swapped for `panes`.
+    /// Everything else (comments included) is left as it is.
+    pub fn with_panes(src: &str, panes: &BTreeMap<String, Vec<u16>>) -> anyhow::Result<String> {
+        let mut out = String::new();
+        let mut in_panes = false;
+        for line in src.lines() {
+            let trimmed = line.trim_start();
+            if trimmed.starts_with('[') {
+                let name = trimmed.trim_start_matches('[').split(']').next().unwrap_or_default();
+                in_panes = name.trim() == "panes";
+            }
+
+            if !in_panes {
+                out.push_str(line);
+                out.push('\n');
+            }
+        }
+
+        if !panes.is_empty() {
+            let mut table = BTreeMap::new();
+            let _ = table.insert("panes", panes);
+
+            let trimmed = out.trim_end().len();
+            out.truncate(trimmed);
+            if !out.is_empty() {
+                out.push_str("\n\n");
+            }
+            out.push_str(&toml::to_string(&table)?);
+        }
+
+        // Make sure we didn't break anything (i.e. `panes` written inline):
+        let config = Self::from_toml(&out)?;
+        if config.panes != *panes {
+            anyhow::bail!("couldn't find where the widget sizes go in the config file");
+        }
+
+        Ok(out)
+    }
+}
+
+// No files on wasm.
+#[cfg(not(target_arch = "wasm32"))]
+impl Config {
+    /// Where we look for the config file when one isn't explicitly given.
+    ///
+    /// In order: the path in the [`CONFIG_ENV_VAR`] env var,
+    /// `$XDG_CONFIG_HOME/utp-tui/config.toml`, and
+    /// `$HOME/.config/utp-tui/config.toml` (or `%APPDATA%\utp-tui\config.toml`
+    /// on Windows).
+    ///
+    /// [`CONFIG_ENV_VAR`]: crate::env::CONFIG_ENV_VAR
+    pub fn default_path() -> Option<PathBuf> {
+        use crate::env::CONFIG_ENV_VAR;
+
+        if let Some(p) = std::env::var_os(CONFIG_ENV_VAR) {
+            return Some(PathBuf::from(p));
+        }
+
+        Some(Self::default_dir()?.join("config.toml"))
+    }
+
+    /// Our directory in the user's config directory (`$XDG_CONFIG_HOME/utp-tui`
+    /// and friends; see [`Config::default_path`]).
+    pub fn default_dir() -> Option<PathBuf> {
+        use std::env::var_os;
+
+        let dir = var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| var_os("APPDATA").map(PathBuf::from))
+            .or_else(|| var_os("HOME").map(|h| PathBuf::from(h).join(".config")))?;
+
+        Some(dir.join("utp-tui"))
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        use anyhow::Context;
+
+        let src = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read the config file at `{}`", path.display()))?;
+
+        Self::from_toml(&src)
+            .with_context(|| format!("Invalid config file at `{}`", path.display()))
+    }
+
+    /// Loads the config file at [`Config::default_path`] if there is one;
+    /// otherwise returns the default config.
+    pub fn load_default() -> anyhow::Result<Self> {
+        match Self::default_path() {
+            Some(p) if p.exists() => Self::load(&p),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        std::fs::write(path, self.to_toml()?)?;
+        Ok(())
+    }
+
+    /// Writes the widget sizes in `panes` to the config file at `path`
+    /// without touching the rest of it (see [`Config::with_panes`]); the file
+    /// is only created if there are sizes to save.
+    ///
+    /// Only `panes` changes while the TUI runs; everything else in the config
+    /// we're running with may have come from the command line.
+    pub fn save_panes(panes: &BTreeMap<String, Vec<u16>>, path: &Path) -> anyhow::Result<()> {
+        use anyhow::Context;
+
+        let src = match std::fs::read_to_string(path) {
+            Ok(src) => src,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                if panes.is_empty() {
+                    return Ok(());
+                }
+
+                String::new()
+            },
+            Err(e) => return Err(e).with_context(|| format!("Failed to read the config file at `{}`", path.display())),
+        };
+
+        let updated = Self::with_panes(&src, panes)
+            .with_context(|| format!("Failed to update the config file at `{}`", path.display()))?;
+        if updated == src {
+            return Ok(());
+        }
+
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        std::fs::write(path, updated)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod config_tests {
+    use super::*;
+    use pretty_assertions::assert_eq as eq;
+
+    fn panes() -> BTreeMap<String, Vec<u16>> {
+        let mut panes = BTreeMap::new();
+        let _ = panes.insert("memory".to_string(), vec![60, 40]);
+        panes
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut config = Config::default();
+        let _ = config.keys.insert("quit".to_string(), vec!["ctrl+q".to_string()]);
+        config.panes = panes();
+        config.theme = Some("light".to_string());
+        config.stack_region = Some(StackRegion { low: 0x4000, high: 0x5000 });
+
+        eq!(Config::from_toml(&config.to_toml().unwrap()).unwrap(), config);
+    }
+
+    #[test]
+    fn only_panes_are_replaced() {
+        let src = "# My config\ntheme = \"light\"\n\n[panes]\nold = [10, 90]\n\n[keys]\n# Muscle memory:\nquit = [\"ctrl+q\"]\n";
+
+        eq!(
+            Config::with_panes(src, &panes()).unwrap(),
+            "# My config\ntheme = \"light\"\n\n[keys]\n# Muscle memory:\nquit = [\"ctrl+q\"]\n\n[panes]\nmemory = [60, 40]\n",
+        );
+        eq!(Config::with_panes("theme = \"light\"\n", &BTreeMap::new()).unwrap(), "theme = \"light\"\n");
+        eq!(Config::with_panes("", &panes()).unwrap(), "[panes]\nmemory = [60, 40]\n");
+
+        // Inline tables aren't something we can swap out:
+        assert!(Config::with_panes("panes = { old = [10, 90] }\n", &panes()).is_err());
+    }
+}
@@ -6,3 +6,14 @@ pub const UNICODE_ENABLE_ENV_VAR: &'static str = "TUI_UNICODE_ENABLE";
 pub const UNICODE_DISABLE_ENV_VAR: &'static str = "TUI_UNICODE_DISABLE";
 
 pub const COLOUR_PALETTE_ENV_VAR: &'static str = "TUI_PALETTE";
+
+pub const CONFIG_ENV_VAR: &'static str = "TUI_CONFIG";
+
+/// <https://no-color.org/>; when set (and not empty) we use the monochrome
+/// theme and turn on [markers](crate::markers).
+pub const NO_COLOUR_ENV_VAR: &'static str = "NO_COLOR";
+pub const MARKERS_ENV_VAR: &'static str = "TUI_MARKERS";
+/// Set to `0` to stop copying through OSC 52 (and always use the fallback
+/// file) or to `1` to use OSC 52 even when we think the terminal doesn't
+/// support it; see [`clipboard`](crate::clipboard).
+pub const OSC52_ENV_VAR: &'static str = "TUI_OSC52";
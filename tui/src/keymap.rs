@@ -0,0 +1,631 @@
+//! Key bindings for the TUI.
+//!
+//! Every key binding the TUI responds to maps a [`KeyChord`] (i.e. `ctrl+r`)
+//! to a named [`Action`]. The defaults live in the `actions!` invocation
+//! below and can be overridden by users from the `[keys]` table of their
+//! config file:
+//!
+//! ```toml
+//! [keys]
+//! quit = ["ctrl+w", "alt+f4"]
+//! next-tab = ["tab", "f12"]
+//! step = ["ctrl+s"]
+//! ```
+//!
+//! Actions are grouped into [`Scope`]s; a chord may be bound to actions in
+//! different scopes (i.e. `tab` switches to the next tab but completes
+//! commands in the peripheral console) but must be unique within a scope.
+
+use crate::config::Config;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+/// Where an [`Action`] is handled.
+///
+/// Actions in the same scope are dispatched by the same widget and so must
+/// not share key chords.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Scope {
+    /// Handled by the event loop itself.
+    Global,
+    /// Handled by the top level [`Tabs`](crate::widgets::Tabs) widget.
+    Tabs,
+    /// Moving focus between widgets.
+    Focus,
+    /// Execution control; handled by the [`Modeline`](crate::widgets::Modeline).
+    Modeline,
+    /// Handled by the [`Mem`](crate::widgets::Mem) widget.
+    Memory,
+    /// Handled by the [`Regs`](crate::widgets::Regs) widget.
+    Registers,
+    /// Handled by the [`Console`](crate::widgets::Console) widget.
+    Console,
+    /// Handled by the [`LogView`](crate::widgets::LogView) widget.
+    Log,
+    /// Handled by the [`Gpio`](crate::widgets::Gpio) widget.
+    Gpio,
+    /// Handled by the [`Adc`](crate::widgets::Adc) widget.
+    Adc,
+    /// Handled by the [`Analyser`](crate::widgets::Analyser) widget.
+    Analyser,
+    /// Handled by the [`Interrupts`](crate::widgets::Interrupts) widget.
+    Interrupts,
+    /// Handled by the [`DataViews`](crate::widgets::DataViews) widget.
+    DataViews,
+    /// Handled by the [`ConsolePeripherals`](crate::widgets::ConsolePeripherals)
+    /// widget.
+    Peripherals,
+    /// Handled by the [`Help`](crate::widgets::Help) widget.
+    Help,
+    /// Resizing, hiding, and maximising the focused widget.
+    Panes,
+}
+
+impl Scope {
+    pub const ALL: &'static [Scope] = &[
+        Scope::Global,
+        Scope::Tabs,
+        Scope::Focus,
+        Scope::Modeline,
+        Scope::Memory,
+        Scope::Registers,
+        Scope::Console,
+        Scope::Log,
+        Scope::Gpio,
+        Scope::Adc,
+        Scope::Analyser,
+        Scope::Interrupts,
+        Scope::DataViews,
+        Scope::Peripherals,
+        Scope::Help,
+        Scope::Panes,
+    ];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Scope::Global => "General",
+            Scope::Tabs => "Tabs",
+            Scope::Focus => "Moving Between Widgets",
+            Scope::Modeline => "Execution Control",
+            Scope::Memory => "Memory View",
+            Scope::Registers => "Register View",
+            Scope::Console => "Console",
+            Scope::Log => "Program Log",
+            Scope::Gpio => "GPIO Panel",
+            Scope::Adc => "ADC Panel",
+            Scope::Analyser => "Logic Analyser",
+            Scope::Interrupts => "Interrupts View",
+            Scope::DataViews => "Data Views",
+            Scope::Peripherals => "Peripheral Console",
+            Scope::Help => "Help",
+            Scope::Panes => "Resizing Widgets",
+        }
+    }
+}
+
+macro_rules! actions {
+    ($(
+        $scope:ident {$(
+            $(#[$meta:meta])*
+            $act:ident = $name:literal: $desc:literal [$($default:literal),* $(,)?]
+        ),* $(,)?}
+    )*) => {
+        /// Everything that can be bound to a key chord.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub enum Action { $($(
+            $(#[$meta])*
+            $act,
+        )*)* }
+
+        impl Action {
+            /// All the actions, in the order they should be documented.
+            pub const ALL: &'static [Action] = &[$($(Action::$act,)*)*];
+
+            /// The name users use to refer to this action in config files.
+            pub fn name(&self) -> &'static str {
+                match self { $($(Action::$act => $name,)*)* }
+            }
+
+            pub fn description(&self) -> &'static str {
+                match self { $($(Action::$act => $desc,)*)* }
+            }
+
+            pub fn scope(&self) -> Scope {
+                match self { $($(Action::$act => Scope::$scope,)*)* }
+            }
+
+            fn default_chords(&self) -> &'static [&'static str] {
+                match self { $($(Action::$act => &[$($default),*],)*)* }
+            }
+        }
+    };
+}
+
+actions! {
+    Global {
+        Quit = "quit": "Quit the TUI" ["ctrl+w", "alt+f4"],
+        CycleTheme = "cycle-theme": "Switch to the next colour theme" ["alt+t"],
+        CopyConsole = "copy-console": "Copy the console history" ["alt+y"],
+        /// See `transcript-path` and `transcript-timestamps` in the config.
+        SaveTranscript = "save-transcript": "Save the console history to a file" ["alt+w"],
+        CopyLog = "copy-log": "Copy the program log" ["alt+l"],
+        CopyRegisters = "copy-registers": "Copy the register values" ["alt+r"],
+        CopyDiagnostics = "copy-diagnostics": "Copy the errors from the last failed load" ["alt+d"],
+        /// Uses the last thing that was copied (or the clipboard fallback file).
+        Paste = "paste": "Type the copied text into the console" ["alt+v"],
+    }
+
+    Tabs {
+        NextTab = "next-tab": "Switch to the next tab" ["tab", "ctrl+tab"],
+        PrevTab = "prev-tab": "Switch to the previous tab" ["backtab", "ctrl+backtab"],
+        Tab1 = "tab-1": "Jump to tab 1" ["ctrl+1", "alt+1", "f1"],
+        Tab2 = "tab-2": "Jump to tab 2" ["ctrl+2", "alt+2", "f2"],
+        Tab3 = "tab-3": "Jump to tab 3" ["ctrl+3", "alt+3", "f3"],
+        Tab4 = "tab-4": "Jump to tab 4" ["ctrl+4", "alt+4", "f4"],
+        Tab5 = "tab-5": "Jump to tab 5" ["ctrl+5", "alt+5", "f5"],
+        Tab6 = "tab-6": "Jump to tab 6" ["ctrl+6", "alt+6", "f6"],
+        Tab7 = "tab-7": "Jump to tab 7" ["ctrl+7", "alt+7", "f7"],
+        Tab8 = "tab-8": "Jump to tab 8" ["ctrl+8", "alt+8", "f8"],
+        Tab9 = "tab-9": "Jump to tab 9" ["ctrl+9", "alt+9", "f9"],
+    }
+
+    Focus {
+        FocusUp = "focus-up": "Focus the widget above" ["ctrl+up"],
+        FocusDown = "focus-down": "Focus the widget below" ["ctrl+down"],
+        FocusLeft = "focus-left": "Focus the widget to the left" ["ctrl+left"],
+        FocusRight = "focus-right": "Focus the widget to the right" ["ctrl+right"],
+    }
+
+    Modeline {
+        Load = "load": "Load the program" ["ctrl+l"],
+        Run = "run": "Run until an event occurs" ["ctrl+r"],
+        Pause = "pause": "Pause execution" ["ctrl+p"],
+        Step = "step": "Execute one instruction" ["ctrl+s"],
+        StepOver = "step-over": "Step over subroutine calls" ["ctrl+u", "alt+u"],
+        StepIn = "step-in": "Step into subroutine calls (same as step)" ["ctrl+i", "alt+i"],
+        StepOut = "step-out": "Step out of the current subroutine" ["ctrl+o", "alt+o"],
+        /// Must be pressed twice; the first press asks for confirmation.
+        Reset = "reset": "Reset the machine (press twice)" ["ctrl+t"],
+    }
+
+    Memory {
+        MemUp = "mem-up": "Scroll up one word" ["up"],
+        MemDown = "mem-down": "Scroll down one word" ["down"],
+        MemUpFast = "mem-up-fast": "Scroll up ten words" ["shift+up"],
+        MemDownFast = "mem-down-fast": "Scroll down ten words" ["shift+down"],
+        MemPageUp = "mem-page-up": "Scroll up one page" ["pageup"],
+        MemPageDown = "mem-page-down": "Scroll down one page" ["pagedown"],
+        MemHome = "mem-home": "Jump back to the PC" ["home", "h"],
+        MemGoTo = "mem-go-to": "Jump to an address, register or label" ["g"],
+        /// See `tui::mem_search` for what can be searched for.
+        MemSearch = "mem-search": "Search memory for words, text, bytes or an instruction" ["/", "ctrl+f"],
+        MemSearchNext = "mem-search-next": "Jump to the next match" ["n"],
+        MemSearchPrev = "mem-search-prev": "Jump to the previous match" ["shift+N"],
+        /// Takes numbers, character literals, labels and instructions.
+        MemEdit = "mem-edit": "Change the selected word" ["enter"],
+        ToggleBreakpoint = "toggle-breakpoint": "Toggle a breakpoint on the selected word" ["b"],
+        ToggleWatchpoint = "toggle-watchpoint": "Toggle a watchpoint on the selected word" ["w"],
+        CopyMemoryRow = "copy-memory-row": "Copy the selected word (and its instruction)" ["y"],
+    }
+
+    Registers {
+        RegsUp = "regs-up": "Select the register above" ["up"],
+        RegsDown = "regs-down": "Select the register below" ["down"],
+        RegsLeft = "regs-left": "Select the register to the left" ["left"],
+        RegsRight = "regs-right": "Select the register to the right" ["right"],
+        /// Takes the same values as `mem-edit`.
+        RegsEdit = "regs-edit": "Change the selected register (or the PC)" ["enter"],
+    }
+
+    Console {
+        ConsoleScrollUp = "console-scroll-up": "Scroll the console back a page" ["pageup"],
+        ConsoleScrollDown = "console-scroll-down": "Scroll the console forward a page" ["pagedown"],
+        ConsoleTop = "console-top": "Scroll to the start of the console history" ["ctrl+home"],
+        ConsoleBottom = "console-bottom": "Scroll back to the newest console output" ["ctrl+end"],
+        /// `/` also starts a search when the console is scrolled back.
+        ConsoleSearch = "console-search": "Search the console history" ["ctrl+f"],
+        /// `n` also works when the console is scrolled back.
+        ConsoleSearchNext = "console-search-next": "Find the next (older) match" ["ctrl+g"],
+        ConsoleInputScript = "console-input-script": "Feed text or a file to the program's input" ["alt+s"],
+        ConsoleLineMode = "console-line-mode": "Switch between sending input as it's typed and a line at a time" ["alt+b"],
+        // These three are only used in line-buffered mode; while a line-buffered
+        // console has focus they take priority over execution control bindings.
+        ConsoleKillLine = "console-kill-line": "Delete everything before the cursor" ["ctrl+u"],
+        ConsoleHistoryPrev = "console-history-prev": "Recall the previous line" ["up"],
+        ConsoleHistoryNext = "console-history-next": "Recall the next line" ["down"],
+    }
+
+    Log {
+        LogScrollUp = "log-scroll-up": "Scroll the log up a line" ["up"],
+        LogScrollDown = "log-scroll-down": "Scroll the log down a line" ["down"],
+        LogPageUp = "log-page-up": "Scroll the log up a page" ["pageup"],
+        LogPageDown = "log-page-down": "Scroll the log down a page" ["pagedown"],
+        LogTop = "log-top": "Scroll to the oldest log entry" ["home"],
+        LogBottom = "log-bottom": "Scroll back to the newest log entry" ["end"],
+        LogLevel = "log-level": "Cycle the lowest level of entry shown" ["l"],
+        LogSource = "log-source": "Cycle which source's entries are shown" ["s"],
+        LogSearch = "log-search": "Only show entries containing some text" ["/", "ctrl+f"],
+        LogResetFilters = "log-reset-filters": "Show entries from every source and clear the search" ["esc"],
+        /// Writes to `program-log.txt` on desktop and copies on the web.
+        LogExport = "log-export": "Save the entries being shown" ["e"],
+    }
+
+    Gpio {
+        GpioPrev = "gpio-prev": "Select the previous pin" ["up", "left"],
+        GpioNext = "gpio-next": "Select the next pin" ["down", "right"],
+        /// `0` through `7` also toggle pins directly.
+        GpioToggle = "gpio-toggle": "Toggle the selected input pin" ["space", "enter"],
+        /// Interrupt pins fire on rising edges.
+        GpioPulse = "gpio-pulse": "Give the selected input pin a rising edge" ["p"],
+    }
+
+    Adc {
+        AdcPrev = "adc-prev": "Select the previous pin" ["up"],
+        AdcNext = "adc-next": "Select the next pin" ["down"],
+        AdcDecrease = "adc-decrease": "Lower the selected pin's value" ["left", "-"],
+        AdcIncrease = "adc-increase": "Raise the selected pin's value" ["right", "+", "="],
+        AdcDecreaseFast = "adc-decrease-fast": "Lower the selected pin's value by 16" ["shift+left", "pagedown"],
+        AdcIncreaseFast = "adc-increase-fast": "Raise the selected pin's value by 16" ["shift+right", "pageup"],
+        AdcMin = "adc-min": "Set the selected pin to 0" ["home"],
+        AdcMax = "adc-max": "Set the selected pin to 255" ["end"],
+        /// Goes through ramp, sine, square, and noise (and then back to none).
+        AdcSignal = "adc-signal": "Change the signal driving the selected pin" ["g"],
+        AdcSlower = "adc-slower": "Double the period of the selected pin's signal" ["["],
+        AdcFaster = "adc-faster": "Halve the period of the selected pin's signal" ["]"],
+        /// Desktop only.
+        AdcLoadSamples = "adc-load-samples": "Replay samples from a CSV file on the selected pin" ["o"],
+    }
+
+    Analyser {
+        AnalyserCursorLeft = "analyser-cursor-left": "Move the cursor back in time" ["left"],
+        AnalyserCursorRight = "analyser-cursor-right": "Move the cursor forward in time" ["right"],
+        AnalyserScrollBack = "analyser-scroll-back": "Scroll back half a screen" ["shift+left", "pageup"],
+        AnalyserScrollForward = "analyser-scroll-forward": "Scroll forward half a screen" ["shift+right", "pagedown"],
+        /// Scrolling with the mouse wheel zooms too.
+        AnalyserZoomIn = "analyser-zoom-in": "Show less time (in more detail)" ["+", "="],
+        AnalyserZoomOut = "analyser-zoom-out": "Show more time" ["-"],
+        AnalyserChannelsUp = "analyser-channels-up": "Scroll the channels up" ["up"],
+        AnalyserChannelsDown = "analyser-channels-down": "Scroll the channels down" ["down"],
+        AnalyserLive = "analyser-live": "Go back to following the newest samples" ["end", "f"],
+        AnalyserClear = "analyser-clear": "Throw away the samples taken so far" ["c"],
+    }
+
+    Interrupts {
+        InterruptsUp = "interrupts-up": "Scroll the vector tables up a line" ["up"],
+        InterruptsDown = "interrupts-down": "Scroll the vector tables down a line" ["down"],
+        InterruptsPageUp = "interrupts-page-up": "Scroll the vector tables up a page" ["pageup"],
+        InterruptsPageDown = "interrupts-page-down": "Scroll the vector tables down a page" ["pagedown"],
+        InterruptsTraps = "interrupts-traps": "Jump to the trap vector table" ["t", "home"],
+        InterruptsVectors = "interrupts-vectors": "Jump to the interrupt vector table" ["i", "end"],
+        /// Unused entries are the ones sharing the table's most common handler.
+        InterruptsHideUnused = "interrupts-hide-unused": "Show or hide unused vector table entries" ["u"],
+        InterruptsClear = "interrupts-clear": "Forget the traps and interrupts taken so far" ["c"],
+    }
+
+    DataViews {
+        DataViewsPrev = "data-views-prev": "Select the previous view" ["up"],
+        DataViewsNext = "data-views-next": "Select the next view" ["down"],
+        /// Views are typed in; i.e. `list *HEAD next=1` (see the panel for more).
+        DataViewsAdd = "data-views-add": "Add a view of memory" ["a", "insert"],
+        DataViewsEdit = "data-views-edit": "Change the selected view" ["e", "enter"],
+        DataViewsRemove = "data-views-remove": "Remove the selected view" ["d", "delete"],
+    }
+
+    // While the peripheral console has focus these take priority over the
+    // tab bindings (so `tab` completes rather than switching tabs).
+    Peripherals {
+        PeripheralsComplete = "peripherals-complete": "Complete the command being typed" ["tab"],
+        PeripheralsHistoryPrev = "peripherals-history-prev": "Recall the previous command" ["up"],
+        PeripheralsHistoryNext = "peripherals-history-next": "Recall the next command" ["down"],
+    }
+
+    Help {
+        HelpScrollUp = "help-scroll-up": "Scroll the help up a line" ["up"],
+        HelpScrollDown = "help-scroll-down": "Scroll the help down a line" ["down"],
+        HelpPageUp = "help-page-up": "Scroll the help up a page" ["pageup"],
+        HelpPageDown = "help-page-down": "Scroll the help down a page" ["pagedown"],
+        HelpTop = "help-top": "Scroll back to the top of the help" ["home"],
+    }
+
+    Panes {
+        GrowPane = "grow-pane": "Make the focused widget bigger" ["alt+=", "alt++"],
+        ShrinkPane = "shrink-pane": "Make the focused widget smaller" ["alt+-"],
+        /// Toggles; pressing it again restores the other widgets.
+        ZoomPane = "zoom-pane": "Maximise the focused widget (press again to undo)" ["alt+z"],
+        CollapsePane = "collapse-pane": "Hide the focused widget" ["alt+c"],
+        ResetPanes = "reset-panes": "Unhide widgets and restore their default sizes" ["alt+0"],
+    }
+}
+
+impl Display for Action {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "{}", self.name())
+    }
+}
+
+impl FromStr for Action {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Action::ALL
+            .iter()
+            .find(|a| a.name() == s)
+            .copied()
+            .ok_or_else(|| format!("Unknown action: `{}`", s))
+    }
+}
+
+/// A key and the modifiers held down with it (i.e. `ctrl+shift+a`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    pub const fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        Self { code, modifiers }
+    }
+
+    pub fn matches(&self, event: &KeyEvent) -> bool {
+        self.code == event.code && self.modifiers == event.modifiers
+    }
+}
+
+impl From<KeyEvent> for KeyChord {
+    fn from(KeyEvent { code, modifiers }: KeyEvent) -> Self {
+        Self { code, modifiers }
+    }
+}
+
+impl FromStr for KeyChord {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err("Empty key chord!".to_string());
+        }
+
+        // `+` on its own (or at the end, i.e. `ctrl++`) is the plus key.
+        let (mods, key) = if s.ends_with('+') && s.len() > 1 && s[..s.len() - 1].ends_with('+') {
+            (&s[..s.len() - 2], "+")
+        } else if s == "+" {
+            ("", "+")
+        } else {
+            match s.rfind('+') {
+                Some(idx) => (&s[..idx], &s[idx + 1..]),
+                None => ("", s),
+            }
+        };
+
+        let mut modifiers = KeyModifiers::empty();
+        for m in mods.split('+').filter(|m| !m.is_empty()) {
+            modifiers |= match m.to_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" | "meta" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                other => return Err(format!("Unknown modifier `{}` in `{}`", other, s)),
+            };
+        }
+
+        let code = match key.to_lowercase().as_str() {
+            "backspace" => KeyCode::Backspace,
+            "enter" | "return" => KeyCode::Enter,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" | "pgup" => KeyCode::PageUp,
+            "pagedown" | "pgdn" => KeyCode::PageDown,
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "delete" | "del" => KeyCode::Delete,
+            "insert" | "ins" => KeyCode::Insert,
+            "esc" | "escape" => KeyCode::Esc,
+            "space" => KeyCode::Char(' '),
+            f if f.starts_with('f') && f.len() > 1 && f[1..].chars().all(|c| c.is_ascii_digit()) => {
+                match f[1..].parse::<u8>() {
+                    Ok(n @ 1..=12) => KeyCode::F(n),
+                    _ => return Err(format!("Invalid function key `{}`", key)),
+                }
+            }
+            _ => {
+                // Single characters are case sensitive; use the original.
+                let mut chars = key.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => KeyCode::Char(c),
+                    _ => return Err(format!("Unknown key `{}` in `{}`", key, s)),
+                }
+            }
+        };
+
+        Ok(Self { code, modifiers })
+    }
+}
+
+impl Display for KeyChord {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) { write!(fmt, "Ctrl+")?; }
+        if self.modifiers.contains(KeyModifiers::ALT) { write!(fmt, "Alt+")?; }
+        if self.modifiers.contains(KeyModifiers::SHIFT) { write!(fmt, "Shift+")?; }
+
+        match self.code {
+            KeyCode::Backspace => write!(fmt, "Backspace"),
+            KeyCode::Enter => write!(fmt, "Enter"),
+            KeyCode::Left => write!(fmt, "Left"),
+            KeyCode::Right => write!(fmt, "Right"),
+            KeyCode::Up => write!(fmt, "Up"),
+            KeyCode::Down => write!(fmt, "Down"),
+            KeyCode::Home => write!(fmt, "Home"),
+            KeyCode::End => write!(fmt, "End"),
+            KeyCode::PageUp => write!(fmt, "PageUp"),
+            KeyCode::PageDown => write!(fmt, "PageDown"),
+            KeyCode::Tab => write!(fmt, "Tab"),
+            KeyCode::BackTab => write!(fmt, "BackTab"),
+            KeyCode::Delete => write!(fmt, "Delete"),
+            KeyCode::Insert => write!(fmt, "Insert"),
+            KeyCode::Esc => write!(fmt, "Esc"),
+            KeyCode::F(n) => write!(fmt, "F{}", n),
+            KeyCode::Char(' ') => write!(fmt, "Space"),
+            KeyCode::Char(c) => write!(fmt, "{}", c),
+            KeyCode::Null => write!(fmt, "Null"),
+        }
+    }
+}
+
+/// Maps [`Action`]s to the [`KeyChord`]s that trigger them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keymap {
+    bindings: HashMap<Action, Vec<KeyChord>>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let bindings = Action::ALL
+            .iter()
+            .map(|a| (*a, a.default_chords()
+                .iter()
+                .map(|c| c.parse().expect("default key bindings are valid"))
+                .collect()
+            ))
+            .collect();
+
+        Self { bindings }
+    }
+}
+
+impl Keymap {
+    /// Starts from the default bindings and applies the overrides in the
+    /// `[keys]` table of the given [`Config`].
+    ///
+    /// Actions that are overridden lose *all* of their default chords. Fails
+    /// on unknown actions, malformed chords and on chords that are bound to
+    /// more than one action in the same [`Scope`].
+    pub fn from_config(config: &Config) -> Result<Self, String> {
+        let mut keymap = Self::default();
+
+        for (action, chords) in config.keys.iter() {
+            let action: Action = action.parse()?;
+            let chords = chords
+                .iter()
+                .map(|c| c.parse::<KeyChord>())
+                .collect::<Result<Vec<_>, _>>()?;
+
+            keymap.bind(action, chords);
+        }
+
+        keymap.check_for_conflicts()?;
+        Ok(keymap)
+    }
+
+    /// Replaces the chords bound to an action.
+    pub fn bind(&mut self, action: Action, chords: Vec<KeyChord>) -> &mut Self {
+        let _ = self.bindings.insert(action, chords);
+        self
+    }
+
+    pub fn chords(&self, action: Action) -> &[KeyChord] {
+        self.bindings.get(&action).map(|c| c.as_slice()).unwrap_or(&[])
+    }
+
+    /// Is `event` bound to `action`?
+    pub fn is(&self, action: Action, event: &KeyEvent) -> bool {
+        self.chords(action).iter().any(|c| c.matches(event))
+    }
+
+    /// Finds the action in `scope` that `event` is bound to, if there is one.
+    pub fn lookup(&self, event: &KeyEvent, scope: Scope) -> Option<Action> {
+        Action::ALL
+            .iter()
+            .filter(|a| a.scope() == scope)
+            .find(|a| self.is(**a, event))
+            .copied()
+    }
+
+    /// Lists the chords for an action in a human readable way (i.e.
+    /// `Ctrl+r / Alt+r`).
+    pub fn describe(&self, action: Action) -> String {
+        let chords = self.chords(action);
+
+        if chords.is_empty() {
+            String::from("(unbound)")
+        } else {
+            chords.iter()
+                .map(|c| c.to_string())
+                .collect::<Vec<_>>()
+                .join(" / ")
+        }
+    }
+
+    fn check_for_conflicts(&self) -> Result<(), String> {
+        for scope in Scope::ALL {
+            let mut seen: HashMap<KeyChord, Action> = HashMap::new();
+
+            for action in Action::ALL.iter().filter(|a| a.scope() == *scope) {
+                for chord in self.chords(*action) {
+                    if let Some(other) = seen.insert(*chord, *action) {
+                        return Err(format!(
+                            "`{}` is bound to both `{}` and `{}`",
+                            chord, other, action,
+                        ));
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod keymap_tests {
+    use super::*;
+    use pretty_assertions::assert_eq as eq;
+
+    #[test]
+    fn parse_chords() {
+        eq!("ctrl+w".parse(), Ok(KeyChord::new(KeyCode::Char('w'), KeyModifiers::CONTROL)));
+        eq!("Alt+F4".parse(), Ok(KeyChord::new(KeyCode::F(4), KeyModifiers::ALT)));
+        eq!("ctrl+shift+up".parse(), Ok(KeyChord::new(KeyCode::Up, KeyModifiers::CONTROL | KeyModifiers::SHIFT)));
+        eq!("ctrl++".parse(), Ok(KeyChord::new(KeyCode::Char('+'), KeyModifiers::CONTROL)));
+        eq!("B".parse(), Ok(KeyChord::new(KeyCode::Char('B'), KeyModifiers::empty())));
+
+        assert!("hyper+a".parse::<KeyChord>().is_err());
+        assert!("s+x".parse::<KeyChord>().is_err());
+        assert!("f13".parse::<KeyChord>().is_err());
+        assert!("".parse::<KeyChord>().is_err());
+    }
+
+    #[test]
+    fn defaults_have_no_conflicts() {
+        Keymap::default().check_for_conflicts().unwrap();
+    }
+
+    #[test]
+    fn overrides_replace_defaults() {
+        let mut config = Config::default();
+        let _ = config.keys.insert("quit".to_string(), vec!["ctrl+q".to_string()]);
+
+        let keymap = Keymap::from_config(&config).unwrap();
+        let ctrl_w = KeyEvent { code: KeyCode::Char('w'), modifiers: KeyModifiers::CONTROL };
+        let ctrl_q = KeyEvent { code: KeyCode::Char('q'), modifiers: KeyModifiers::CONTROL };
+
+        assert!(!keymap.is(Action::Quit, &ctrl_w));
+        eq!(keymap.lookup(&ctrl_q, Scope::Global), Some(Action::Quit));
+    }
+
+    #[test]
+    fn conflicts_are_rejected() {
+        let mut config = Config::default();
+        let _ = config.keys.insert("run".to_string(), vec!["ctrl+p".to_string()]);
+
+        assert!(Keymap::from_config(&config).is_err());
+    }
+}
@@ -0,0 +1,418 @@
+//! Declarative descriptions of the TUI's layout.
+//!
+//! Layouts are written in TOML. A layout is a list of tabs; each tab is a tree
+//! of nodes where every node is either a `split` (that divides its area between
+//! its `children`) or a `widget`. For example:
+//!
+//! ```toml
+//! [[tab]]
+//! title = "Week 1"
+//! split = "horizontal"
+//!
+//!     [[tab.children]]
+//!     size = "50%"
+//!     widget = "mem"
+//!     title = "Memory"
+//!
+//!     [[tab.children]]
+//!     size = "50%"
+//!     widget = "console"
+//!     title = "Console"
+//! ```
+//!
+//! Sizes are either percentages (`"40%"`), fixed lengths (`"3"`), or minimums
+//! and maximums (`"min:3"`, `"max:10"`). Titles on tabs that start with `@`
+//! refer to the TUI's built-in tab names (i.e. `"@root"`).
+//!
+//! See the built-in layouts (in `layouts/`) for larger examples.
+
+use crate::colours::parse_colour;
+
+use serde::Deserialize;
+use tui::layout::{Constraint, Direction};
+use tui::style::{Color as Colour, Style};
+use tui::widgets::{Block, Borders};
+
+use std::convert::TryFrom;
+use std::fmt::{self, Display};
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LayoutDescription {
+    #[serde(rename = "tab")]
+    pub tabs: Vec<Node>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Node {
+    /// How much of the parent split this node gets. Ignored for tabs.
+    pub size: Option<Size>,
+    /// Title of the tab for top level nodes and of the node's block otherwise.
+    pub title: Option<String>,
+    /// Defaults to `all` when the node has a title and `none` otherwise.
+    pub borders: Option<BorderKind>,
+    pub border_colour: Option<NamedColour>,
+
+    pub split: Option<Split>,
+    /// Identifies the split in the user's config when its widgets are
+    /// resized; defaults to the tab's title followed by the path to the split
+    /// (i.e. `@root/1`).
+    pub id: Option<String>,
+    pub margin: u16,
+    pub children: Vec<Node>,
+
+    pub widget: Option<WidgetKind>,
+    /// Overrides whether the widget can be focused (only some widgets support
+    /// this).
+    pub focusable: Option<bool>,
+    /// Show extra debugging information (`mem` and `regs` only).
+    pub debug: bool,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Split {
+    Horizontal,
+    Vertical,
+}
+
+impl From<Split> for Direction {
+    fn from(split: Split) -> Direction {
+        match split {
+            Split::Horizontal => Direction::Horizontal,
+            Split::Vertical => Direction::Vertical,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BorderKind {
+    All,
+    NoBottom,
+    None,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WidgetKind {
+    Mem,
+    Regs,
+    Console,
+    Gpio,
+    Adc,
+    Pwm,
+    Timers,
+    Clock,
+    PeripheralConsole,
+    Stimulus,
+    LogicAnalyser,
+    Interrupts,
+    StackInspector,
+    DataViews,
+    Breakpoints,
+    Watchpoints,
+    CallStack,
+    MemoryInterface,
+    Help,
+    Log,
+    EventLog,
+    Empty,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Size(pub Constraint);
+
+impl Default for Size {
+    fn default() -> Self {
+        Size(Constraint::Percentage(100))
+    }
+}
+
+impl TryFrom<String> for Size {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, String> {
+        let s = s.trim();
+        let err = || format!("invalid size `{}`; expected something like `40%`, `3`, `min:3`, or `max:10`", s);
+
+        let c = if let Some(p) = s.strip_suffix('%') {
+            match p.trim().parse() {
+                Ok(p) if p <= 100 => Constraint::Percentage(p),
+                _ => return Err(err()),
+            }
+        } else if let Some(m) = s.strip_prefix("min:") {
+            Constraint::Min(m.trim().parse().map_err(|_| err())?)
+        } else if let Some(m) = s.strip_prefix("max:") {
+            Constraint::Max(m.trim().parse().map_err(|_| err())?)
+        } else {
+            Constraint::Length(s.parse().map_err(|_| err())?)
+        };
+
+        Ok(Size(c))
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct NamedColour(pub Colour);
+
+impl TryFrom<String> for NamedColour {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, String> {
+        parse_colour(&s)
+            .map(NamedColour)
+            .ok_or_else(|| format!("unknown colour `{}`", s))
+    }
+}
+
+/// Problems with a layout that parsed but doesn't make sense.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayoutError {
+    /// Titles (or widget kinds) of the nodes leading to the problem.
+    pub path: Vec<String>,
+    pub msg: String,
+}
+
+impl Display for LayoutError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(fmt, "{}", self.msg)
+        } else {
+            write!(fmt, "in `{}`: {}", self.path.join(" > "), self.msg)
+        }
+    }
+}
+
+impl std::error::Error for LayoutError { }
+
+static FULL: &str = include_str!("../../layouts/full.toml");
+static INTRO: &str = include_str!("../../layouts/intro.toml");
+
+lazy_static::lazy_static! {
+    static ref BUILTIN_FULL: LayoutDescription = LayoutDescription::from_toml(FULL)
+        .expect("the built-in full layout is valid");
+    static ref BUILTIN_INTRO: LayoutDescription = LayoutDescription::from_toml(INTRO)
+        .expect("the built-in intro layout is valid");
+}
+
+impl LayoutDescription {
+    /// Names of the layouts that ship with the TUI.
+    pub const BUILTIN: &'static [&'static str] = &["full", "intro"];
+
+    /// The layout with every tab (this is what's used by default).
+    pub fn full() -> &'static Self {
+        &*BUILTIN_FULL
+    }
+
+    /// A pared down layout with just memory, registers, and the console.
+    pub fn intro() -> &'static Self {
+        &*BUILTIN_INTRO
+    }
+
+    pub fn builtin(name: &str) -> Option<&'static Self> {
+        match name {
+            "full" => Some(Self::full()),
+            "intro" => Some(Self::intro()),
+            _ => None,
+        }
+    }
+
+    pub fn from_toml(src: &str) -> anyhow::Result<Self> {
+        let desc: Self = toml::from_str(src)?;
+        desc.validate()?;
+
+        Ok(desc)
+    }
+
+    pub fn validate(&self) -> Result<(), LayoutError> {
+        if self.tabs.is_empty() {
+            return Err(LayoutError { path: Vec::new(), msg: "layouts need at least one tab".into() });
+        }
+
+        let mut path = Vec::new();
+        for tab in self.tabs.iter() {
+            if tab.title.is_none() {
+                return Err(LayoutError { path, msg: "every tab needs a title".into() });
+            }
+
+            tab.validate(&mut path)?;
+        }
+
+        Ok(())
+    }
+}
+
+// No files on wasm.
+#[cfg(not(target_arch = "wasm32"))]
+impl LayoutDescription {
+    pub fn load(path: &std::path::Path) -> anyhow::Result<Self> {
+        use anyhow::Context;
+
+        let src = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read the layout file at `{}`", path.display()))?;
+
+        Self::from_toml(&src)
+            .with_context(|| format!("Invalid layout file at `{}`", path.display()))
+    }
+
+    /// Accepts either the name of a built-in layout or a path to a layout
+    /// file.
+    pub fn load_by_name_or_path(layout: &str) -> anyhow::Result<Self> {
+        match Self::builtin(layout) {
+            Some(l) => Ok(l.clone()),
+            None => Self::load(layout.as_ref()),
+        }
+    }
+}
+
+impl Node {
+    fn name(&self) -> String {
+        match (&self.title, self.widget, self.split) {
+            (Some(t), _, _) => t.clone(),
+            (None, Some(w), _) => format!("{:?}", w),
+            (None, None, Some(s)) => format!("{:?} split", s),
+            (None, None, None) => "<empty>".into(),
+        }
+    }
+
+    fn validate(&self, path: &mut Vec<String>) -> Result<(), LayoutError> {
+        path.push(self.name());
+        let err = |path: &Vec<String>, msg: &str| Err(LayoutError { path: path.clone(), msg: msg.into() });
+
+        match (self.split, self.widget) {
+            (Some(_), Some(_)) => return err(path, "nodes must be a `split` or a `widget`, not both"),
+            (None, None) => return err(path, "nodes must have either a `split` or a `widget`"),
+            (Some(_), None) if self.children.is_empty() => return err(path, "splits need at least one child"),
+            (None, Some(_)) if !self.children.is_empty() => return err(path, "widgets can't have children"),
+            (None, Some(_)) if self.id.is_some() => return err(path, "only splits can have an `id`"),
+            (None, Some(w)) if self.debug && !matches!(w, WidgetKind::Mem | WidgetKind::Regs) => {
+                return err(path, "only `mem` and `regs` support `debug`")
+            },
+            (None, Some(w)) if self.focusable.is_some() && !w.supports_focusable() => {
+                return err(path, "this widget doesn't support `focusable`")
+            },
+            _ => {},
+        }
+
+        for child in self.children.iter() {
+            child.validate(path)?;
+        }
+
+        let _ = path.pop();
+        Ok(())
+    }
+
+    pub fn constraint(&self) -> Constraint {
+        self.size.unwrap_or_default().0
+    }
+
+    /// Resolves `@` references to the built-in tab names.
+    pub fn tab_title(&self) -> String {
+        use crate::strings::*;
+
+        let title = self.title.as_deref().unwrap_or("");
+        let builtin = match title {
+            "@root" => RootTab,
+            "@peripherals" => PeripheralsTab,
+            "@analyser" => AnalyserTab,
+            "@memory" => MemTab,
+            "@console" => ConsoleTab,
+            "@debug" => DebugTab,
+            "@system" => SystemTab,
+            "@help" => HelpTab,
+            "@log" => LogTab,
+            t => return t.to_string(),
+        };
+
+        get_string(builtin).to_string()
+    }
+
+    pub fn block(&self) -> Option<Block<'_>> {
+        let default = if self.title.is_some() { BorderKind::All } else { BorderKind::None };
+        let borders = match self.borders.unwrap_or(default) {
+            BorderKind::All => Borders::ALL,
+            BorderKind::NoBottom => Borders::ALL & !Borders::BOTTOM,
+            BorderKind::None => return None,
+        };
+
+        // Border and title colours are filled in when the block is drawn (so
+        // that they follow the current theme).
+        let block = Block::default()
+            .borders(borders)
+            .style(Style::default().bg(Colour::Reset));
+
+        Some(match self.title {
+            Some(ref t) => block.title(t),
+            None => block,
+        })
+    }
+}
+
+impl WidgetKind {
+    fn supports_focusable(self) -> bool {
+        use WidgetKind::*;
+        matches!(self, Gpio | Adc | Pwm | Timers | Clock | Help | Empty)
+    }
+}
+
+#[cfg(test)]
+mod layout_description_tests {
+    use super::*;
+
+    #[test]
+    fn builtins_are_valid() {
+        for name in LayoutDescription::BUILTIN {
+            let _ = LayoutDescription::builtin(name).unwrap();
+        }
+    }
+
+    #[test]
+    fn sizes() {
+        let s = |s: &str| Size::try_from(s.to_string()).map(|s| s.0);
+
+        assert_eq!(s("40%"), Ok(Constraint::Percentage(40)));
+        assert_eq!(s("3"), Ok(Constraint::Length(3)));
+        assert_eq!(s("min:3"), Ok(Constraint::Min(3)));
+        assert_eq!(s("max: 10"), Ok(Constraint::Max(10)));
+        assert!(s("101%").is_err());
+        assert!(s("big").is_err());
+    }
+
+    #[test]
+    fn nodes_must_be_splits_or_widgets() {
+        let both = r#"
+            [[tab]]
+            title = "A"
+            split = "vertical"
+            widget = "mem"
+        "#;
+        let neither = r#"
+            [[tab]]
+            title = "A"
+        "#;
+        let childless = r#"
+            [[tab]]
+            title = "A"
+            split = "vertical"
+        "#;
+
+        assert!(LayoutDescription::from_toml(both).is_err());
+        assert!(LayoutDescription::from_toml(neither).is_err());
+        assert!(LayoutDescription::from_toml(childless).is_err());
+    }
+
+    #[test]
+    fn unknown_widgets_are_rejected() {
+        let src = r#"
+            [[tab]]
+            title = "A"
+            widget = "spreadsheet"
+        "#;
+
+        assert!(LayoutDescription::from_toml(src).is_err());
+    }
+}
@@ -0,0 +1,202 @@
+//! Module defining the layout of the widgets used by the TUI.
+//!
+//! Layouts are built from [`LayoutDescription`]s; see the [`description`]
+//! module for the format.
+
+use crate::tui::widget::{Widgets, Widget};
+use crate::tui::widget::util::ConditionalSendBound;
+use crate::widgets::*;
+use crate::colours::c;
+
+use lc3_application_support::io_peripherals::InputSink;
+use lc3_application_support::io_peripherals::OutputSource;
+use lc3_traits::control::Control;
+
+use tui::backend::Backend;
+use tui::layout::{Layout, Direction, Constraint};
+use tui::widgets::{Block, Borders};
+use tui::terminal::Terminal;
+use tui::style::{Style, Color as Colour};
+
+pub mod description;
+pub use description::LayoutDescription;
+use description::{Node, WidgetKind};
+
+// Returns the root widget for our layout (the full layout).
+pub fn layout<'a, 'int: 'a, C, I, O, B: 'a>(
+    name: Option<&'a str>,
+    extra_tabs: Vec<(Box<dyn Widget<'a, 'int, C, I, O, B> + 'a>, String)>,
+) -> impl Widget<'a, 'int, C, I, O, B>
+where
+    C: Control + ?Sized + 'a,
+    I: InputSink + ?Sized + 'a,
+    O: OutputSource + ?Sized + 'a,
+    B: Backend,
+    Terminal<B>: ConditionalSendBound,
+{
+    layout_from_description(name, LayoutDescription::full(), extra_tabs)
+}
+
+// Returns the root widget for the layout in the given description.
+pub fn layout_from_description<'a, 'int: 'a, C, I, O, B: 'a>(
+    name: Option<&'a str>,
+    desc: &'a LayoutDescription,
+    extra_tabs: Vec<(Box<dyn Widget<'a, 'int, C, I, O, B> + 'a>, String)>,
+) -> impl Widget<'a, 'int, C, I, O, B>
+where
+    C: Control + ?Sized + 'a,
+    I: InputSink + ?Sized + 'a,
+    O: OutputSource + ?Sized + 'a,
+    B: Backend,
+    Terminal<B>: ConditionalSendBound,
+{
+    RootWidget::new(layout_tabs_from_description(name, desc, extra_tabs))
+        .add(Modeline::new(LoadButton::new()))
+}
+
+pub fn layout_tabs<'a, 'int: 'a, C, I, O, B: 'a>(
+    name: Option<&'a str>,
+    extra_tabs: Vec<(Box<dyn Widget<'a, 'int, C, I, O, B> + 'a>, String)>,
+) -> Tabs<'a, 'int, C, I, O, B, impl Fn() -> TabsBar<'a, String>>
+where
+    C: Control + ?Sized + 'a,
+    I: InputSink + ?Sized + 'a,
+    O: OutputSource + ?Sized + 'a,
+    B: Backend,
+    Terminal<B>: ConditionalSendBound,
+{
+    layout_tabs_from_description(name, LayoutDescription::full(), extra_tabs)
+}
+
+/// Builds the tabs in a [`LayoutDescription`], followed by the `extra_tabs`
+/// (and the event log when in debug mode).
+///
+/// The description is expected to be valid (this is checked when
+/// descriptions are parsed; see [`LayoutDescription::validate`]).
+pub fn layout_tabs_from_description<'a, 'int: 'a, C, I, O, B: 'a>(
+    name: Option<&'a str>,
+    desc: &'a LayoutDescription,
+    extra_tabs: Vec<(Box<dyn Widget<'a, 'int, C, I, O, B> + 'a>, String)>,
+) -> Tabs<'a, 'int, C, I, O, B, impl Fn() -> TabsBar<'a, String>>
+where
+    C: Control + ?Sized + 'a,
+    I: InputSink + ?Sized + 'a,
+    O: OutputSource + ?Sized + 'a,
+    B: Backend,
+    Terminal<B>: ConditionalSendBound,
+{
+    use crate::strings::*;
+
+    let mut desc_tabs = desc.tabs.iter();
+    let first = desc_tabs.next().expect("layouts have at least one tab");
+
+    let mut tabs = Tabs::new(build_node(first, first.title.clone().unwrap_or_default()), first.tab_title())
+        .with_tabs_bar(move || {
+            TabsBar::default()
+                .block(Block::default().title(name.unwrap_or(s!(TabBarName))).borders(Borders::ALL).border_style(Style::default().fg(c!(Border))))
+                .style(Style::default().fg(Colour::White))
+                .highlight_style(Style::default().fg(Colour::LightCyan))
+                // .divider(tui::symbols::DOT)
+        });
+
+    for tab in desc_tabs {
+        tabs = tabs.add_dyn(build_node(tab, tab.title.clone().unwrap_or_default()), tab.tab_title());
+    }
+
+    for (w, t) in extra_tabs {
+        tabs = tabs.add_dyn(w, t);
+    }
+
+    if crate::debug::in_debug_mode() {
+        let events = Text::new(|t| &t.debug_log.as_ref().unwrap()[..]);
+        let b = Block::default()
+            .borders(Borders::ALL)
+            .style(Style::default().bg(Colour::Reset));
+
+        let mut event_log = Widgets::new(Layout::default().direction(Direction::Vertical));
+        let _ = event_log
+            .add_widget_with_border_colour(Constraint::Percentage(100), events, b.title("Event Log"), Colour::Green);
+
+        tabs = tabs
+            .add(event_log, s!(EventLogTab));
+    }
+
+    tabs
+}
+
+// `id` is the id the node gets if it doesn't specify one.
+fn build_node<'a, 'int: 'a, C, I, O, B: 'a>(
+    node: &'a Node,
+    id: String,
+) -> Box<dyn Widget<'a, 'int, C, I, O, B> + 'a>
+where
+    C: Control + ?Sized + 'a,
+    I: InputSink + ?Sized + 'a,
+    O: OutputSource + ?Sized + 'a,
+    B: Backend,
+    Terminal<B>: ConditionalSendBound,
+{
+    if let Some(kind) = node.widget {
+        return build_widget(kind, node);
+    }
+
+    let split = node.split.expect("nodes are either widgets or splits");
+    let layout = Layout::default()
+        .direction(split.into())
+        .margin(node.margin);
+
+    let id = node.id.clone().unwrap_or(id);
+
+    let mut widgets = Widgets::new(layout);
+    for (idx, child) in node.children.iter().enumerate() {
+        let child_id = format!("{}/{}", id, idx);
+        let widget = build_node(child, child_id);
+
+        let _ = match (child.block(), child.border_colour) {
+            (Some(block), Some(colour)) => widgets.add_widget_with_border_colour(child.constraint(), widget, block, colour.0),
+            (block, _) => widgets.add_widget(child.constraint(), widget, block),
+        };
+    }
+    let _ = widgets.set_id(id);
+
+    Box::new(widgets)
+}
+
+fn build_widget<'a, 'int: 'a, C, I, O, B: 'a>(
+    kind: WidgetKind,
+    node: &'a Node,
+) -> Box<dyn Widget<'a, 'int, C, I, O, B> + 'a>
+where
+    C: Control + ?Sized + 'a,
+    I: InputSink + ?Sized + 'a,
+    O: OutputSource + ?Sized + 'a,
+    B: Backend,
+    Terminal<B>: ConditionalSendBound,
+{
+    let focusable = |default: bool| node.focusable.unwrap_or(default);
+
+    match kind {
+        WidgetKind::Mem => Box::new(Mem::new_with_debug(node.debug)),
+        WidgetKind::Regs => Box::new(Regs::new_with_debug(node.debug)),
+        WidgetKind::Console => Box::new(Console::default()),
+        WidgetKind::Gpio => Box::new(Gpio::default().focusable(focusable(true))),
+        WidgetKind::Adc => Box::new(Adc::default().focusable(focusable(true))),
+        WidgetKind::Pwm => Box::new(Pwm::default().focusable(focusable(false))),
+        WidgetKind::Timers => Box::new(Timers::default().focusable(focusable(false))),
+        WidgetKind::Clock => Box::new(Clock::default().focusable(focusable(false))),
+        WidgetKind::PeripheralConsole => Box::new(ConsolePeripherals::default()),
+        WidgetKind::Stimulus => Box::new(StimulusWindow::default()),
+        WidgetKind::LogicAnalyser => Box::new(Analyser::default()),
+        WidgetKind::Interrupts => Box::new(Interrupts::default()),
+        WidgetKind::StackInspector => Box::new(StackInspector::default()),
+        WidgetKind::DataViews => Box::new(DataViews::default()),
+        WidgetKind::Breakpoints => Box::new(BreakWindow::default()),
+        WidgetKind::Watchpoints => Box::new(WatchWindow::default()),
+        WidgetKind::CallStack => Box::new(StackWindow::default()),
+        WidgetKind::MemoryInterface => Box::new(MemRegInterface::default()),
+        WidgetKind::Help => Box::new(Help::default().focusable(focusable(true))),
+        WidgetKind::Log => Box::new(LogView::default()),
+        WidgetKind::EventLog => Box::new(Text::new(|t| t.debug_log.as_ref().map(|l| &l[..]).unwrap_or(&[]))),
+        WidgetKind::Empty => Box::new(Empty::default().focusable(focusable(false))),
+    }
+}
@@ -66,13 +66,17 @@ macro_rules! specialize {
     }
 }
 
+pub mod clipboard;
 pub mod colours;
+pub mod config;
 pub mod debug;
+pub mod keymap;
 pub mod layout;
+pub mod markers;
 pub mod widgets;
 
 pub mod env;
 pub mod strings;
 
 mod tui;
-pub use crate::tui::{DynTui, Tui, ProgramSource};
+pub use crate::tui::{DynTui, Tui, ProgramSource, InputScript, StimulusScript};
@@ -0,0 +1,54 @@
+//! Text and glyph markers for state that is otherwise only shown with colour
+//! (i.e. which row the PC is on or which registers just changed).
+//!
+//! Markers are off by default; they're turned on when [`NO_COLOUR_ENV_VAR`] or
+//! [`MARKERS_ENV_VAR`] are set or with [`set_enabled`].
+//!
+//! [`NO_COLOUR_ENV_VAR`]: crate::env::NO_COLOUR_ENV_VAR
+//! [`MARKERS_ENV_VAR`]: crate::env::MARKERS_ENV_VAR
+
+use crate::env::{MARKERS_ENV_VAR, NO_COLOUR_ENV_VAR};
+
+use lc3_traits::control::Event;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// The row the PC is on (in the memory view).
+pub const PC: &str = "> PC";
+/// The selected row (in the memory view).
+pub const SELECTED: &str = "-->";
+/// Registers and bits that changed in the last step.
+pub const CHANGED: &str = "*";
+
+lazy_static::lazy_static! {
+    static ref ENABLED: AtomicBool = AtomicBool::new(
+        no_colour_requested() || std::env::var_os(MARKERS_ENV_VAR).is_some()
+    );
+}
+
+/// Whether the user has asked for no colours (see <https://no-color.org/>).
+pub fn no_colour_requested() -> bool {
+    matches!(std::env::var_os(NO_COLOUR_ENV_VAR), Some(v) if !v.is_empty())
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed)
+}
+
+/// A short tag for an event (i.e. `[B]` for a breakpoint).
+pub fn event(event: Event) -> &'static str {
+    match event {
+        Event::Breakpoint { .. } => "[B]",
+        Event::MemoryWatch { .. } => "[W]",
+        Event::DepthReached { .. } => "[D]",
+        Event::Error { .. } => "[E]",
+        Event::Interrupted => "[I]",
+        Event::Halted => "[H]",
+        #[allow(unreachable_patterns)]
+        _ => "[?]",
+    }
+}
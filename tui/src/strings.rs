@@ -62,9 +62,11 @@ register_strings! { STR_TABLE <- {
     TabBarName => ("UTP LC-3 Simulator"),
     RootTab => ("Root", "🌴 Root"),
     PeripheralsTab => ("Peripherals", "🕹️  Peripherals"),
+    AnalyserTab => ("Analyser", "📈 Analyser"),
     MemTab => ("Memory", "💽 Memory"),
     ConsoleTab => ("Console", "🖥️  Console"),
     DebugTab => ("Debug", "🐛 Debug"),
+    SystemTab => ("System", "⚙️  System"),
     HelpTab => ("Help", "❔ Help"),
     LogTab => ("Log", "📜 Log"),
     EventLogTab => ("Internal Event Log", "🦠 Internal Event Log"),
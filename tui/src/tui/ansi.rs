@@ -1,16 +1,61 @@
 //! Utilities for dealing with strings containing ANSI escape codes.
 
+use super::bounded::{dropped_marker, slack};
+
 use tui::widgets::Text as TuiText;
 use tui::style::{Color, Modifier, Style};
 
-use std::ops::{Bound, Deref, RangeBounds};
+use std::ops::{Bound, Deref, Range, RangeBounds};
 use std::borrow::Cow;
 
+const ESC: char = 0x1B as char;
+
+/// Tab stops are every this many columns.
+const TAB_WIDTH: usize = 8;
+
+/// How many rows the screen has until we're told otherwise (see
+/// [`AnsiTextContainer::set_screen_rows`]); this is what a VT100 has.
+const DEFAULT_SCREEN_ROWS: usize = 24;
+
+/// The console history, modelled as a small virtual terminal.
+///
+/// Output is written at a cursor, which the usual VT100 escape sequences
+/// (cursor movement, erasing the screen or a line, saving/restoring the cursor)
+/// and carriage returns, backspaces and tabs move around; SGR sequences set the
+/// style of what's written.
+///
+/// Unlike a real terminal, lines don't have a width (they're wrapped when
+/// they're drawn) and nothing is ever scrolled away: the "screen" is just the
+/// last `screen_rows` lines, and clearing it starts a new screen below
+/// everything that's been written so that it all stays in the history.
+///
+/// The history is bounded though (see [`AnsiTextContainer::set_limit`]); once
+/// lines have been dropped the first line is a marker saying how many.
 #[derive(Debug, Clone)]
 pub struct AnsiTextContainer<'a> {
+    /// The lines, without their newlines.
     lines: Vec<Vec<TuiText<'a>>>,
+    /// Input that ended partway through an escape sequence.
     pending: String,
     style: Style,
+    /// Where the next character goes, as a line and a column (in characters).
+    ///
+    /// The line can be past the end of `lines`; lines are added when
+    /// something is written to them.
+    cursor: (usize, usize),
+    saved_cursor: (usize, usize),
+    /// The screen doesn't start before this line; clearing the screen moves
+    /// this past everything that's been written.
+    screen_base: usize,
+    screen_rows: usize,
+
+    limit: usize,
+    dropped: usize,
+    /// How far lines have moved up (i.e. line 10 is now line 0) from dropping
+    /// lines, in total.
+    shifted: usize,
+    /// Dropped lines that haven't been taken yet, if we're holding on to them.
+    spilled: Option<Vec<String>>,
 }
 
 impl<'a> AnsiTextContainer<'a> {
@@ -26,39 +71,117 @@ impl<'a> AnsiTextContainer<'a> {
             lines,
             pending: String::new(),
             style: Style::default(),
+            cursor: (0, 0),
+            saved_cursor: (0, 0),
+            screen_base: 0,
+            screen_rows: DEFAULT_SCREEN_ROWS,
+
+            limit: usize::MAX,
+            dropped: 0,
+            shifted: 0,
+            spilled: None,
         }
     }
 
     pub fn push_string(&mut self, new: String) {
         if new.is_empty() { return; }
 
-        if self.pending.is_empty() {
-            self.pending = new;
+        let input = if self.pending.is_empty() {
+            new
         } else {
-            self.pending.push_str(new.as_str());
+            let mut input = std::mem::take(&mut self.pending);
+            input.push_str(&new);
+            input
+        };
+
+        let mut rest = input.as_str();
+        while let Some(c) = rest.chars().next() {
+            rest = &rest[c.len_utf8()..];
+
+            match c {
+                ESC => match parse_escape(rest) {
+                    Some((esc, len)) => {
+                        self.escape(esc);
+                        rest = &rest[len..];
+                    },
+                    // Try again once we've got the rest of the sequence:
+                    None => {
+                        self.pending.push(ESC);
+                        self.pending.push_str(rest);
+                        return;
+                    },
+                },
+                '\n' => self.newline(),
+                '\r' => self.cursor.1 = 0,
+                '\x08' => self.cursor.1 = self.cursor.1.saturating_sub(1),
+                '\t' => self.cursor.1 = (self.cursor.1 / TAB_WIDTH + 1) * TAB_WIDTH,
+                c if c.is_control() => { /* Unsupported (i.e. bells) */ },
+                c => self.put(c),
+            }
         }
 
-        match ansi_string_to_tui_text(
-            &self.pending.as_str(),
-            &mut self.style,
-            &mut self.lines
-        ) {
-            Ok(()) => self.pending.clear(),
-            Err(remaining) => self.pending = String::from(remaining),
+        let lines = self.lines.len() - self.marker_lines();
+        if lines > self.limit.saturating_add(slack(self.limit)) {
+            self.trim();
         }
     }
 
+    /// Empties the container (but keeps its limit).
     pub fn clear(&mut self) {
-        self.style.reset();
-        self.lines.clear()
+        let (limit, spilling) = (self.limit, self.spilled.is_some());
+
+        *self = Self::with_capacity(self.lines.capacity());
+        self.limit = limit;
+        self.set_spilling(spilling);
+    }
+
+    /// Sets how many lines we keep; past this the oldest lines are dropped.
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit.max(1);
+
+        if self.lines.len() - self.marker_lines() > self.limit {
+            self.trim();
+        }
+    }
+
+    /// Sets whether dropped lines are held on to (until they're taken with
+    /// [`AnsiTextContainer::take_spilled`]).
+    pub fn set_spilling(&mut self, spilling: bool) {
+        self.spilled = if spilling { self.spilled.take().or_else(|| Some(Vec::new())) } else { None };
+    }
+
+    /// The lines that have been dropped since this was last called (without
+    /// display attributes).
+    pub fn take_spilled(&mut self) -> Vec<String> {
+        self.spilled.as_mut().map(std::mem::take).unwrap_or_default()
+    }
+
+    /// How many lines have been dropped.
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    /// How far lines have moved up from dropping lines, in total; line
+    /// numbers from before this last changed should be adjusted by the
+    /// difference.
+    pub fn shifted(&self) -> usize {
+        self.shifted
     }
 
     pub fn num_lines(&self) -> usize {
         self.lines.len()
     }
 
+    /// Sets how many rows the screen has (i.e. how tall the console is); this
+    /// is where the cursor goes for escapes like `CSI H` (home).
+    pub fn set_screen_rows(&mut self, rows: usize) {
+        self.screen_rows = rows.max(1);
+    }
+
     /// Get an iterator over [`Text`] instances for a given range of lines.
     ///
+    /// Lines are separated by `Text` instances containing just a newline.
+    ///
     /// [`Text`]: tui::widgets::Text
     pub fn get_lines(&self, lines: impl RangeBounds<usize>) -> impl Iterator<Item = &TuiText<'a>> {
         use Bound::*;
@@ -78,14 +201,19 @@ impl<'a> AnsiTextContainer<'a> {
         };
 
         let lower = lower.min(self.lines.len().saturating_sub(1));
-        let upper = upper.max(self.lines.len());
+        let upper = upper.min(self.lines.len());
 
         let num_to_take = upper - lower;
+        let last = self.lines.len() - 1;
 
         self.lines.iter()
+            .enumerate()
             .skip(lower)
             .take(num_to_take)
-            .flatten()
+            .flat_map(move |(idx, line)| {
+                let newline: Option<&TuiText<'a>> = if idx == last { None } else { Some(&NEWLINE) };
+                line.iter().chain(newline)
+            })
     }
 
     /// Gives you an iterator over [`Text`] instances for the last `n` lines.
@@ -101,12 +229,477 @@ impl<'a> AnsiTextContainer<'a> {
     /// attributes).
     pub fn as_string(&self) -> String {
         self.get_lines(..)
-            .map(|t| match t {
-                TuiText::Raw(s) => s,
-                TuiText::Styled(s, _) => s,
-            }.deref())
+            .map(text_str)
             .collect()
     }
+
+    /// The [`Text`] instances that make up a line (without the newline).
+    ///
+    /// [`Text`]: tui::widgets::Text
+    pub fn line(&self, idx: usize) -> &[TuiText<'a>] {
+        self.lines.get(idx).map(|l| l.as_slice()).unwrap_or(&[])
+    }
+
+    /// Gets a line as a `String`, without display attributes.
+    pub fn line_string(&self, idx: usize) -> String {
+        self.line(idx).iter().map(text_str).collect()
+    }
+
+    /// Finds the last line before line `before` that contains `needle`
+    /// (ignoring case).
+    pub fn find_before(&self, needle: &str, before: usize) -> Option<usize> {
+        let needle = needle.to_lowercase();
+
+        (0..before.min(self.num_lines()))
+            .rev()
+            .find(|idx| self.line_string(*idx).to_lowercase().contains(&needle))
+    }
+
+    fn marker_lines(&self) -> usize {
+        if self.dropped > 0 { 1 } else { 0 }
+    }
+
+    // Drops the oldest lines (the ones over the limit) and updates the marker.
+    fn trim(&mut self) {
+        let start = self.marker_lines();
+        let remove = (self.lines.len() - start).saturating_sub(self.limit);
+        if remove == 0 { return; }
+
+        let removed = self.lines.drain(start..(start + remove));
+        match self.spilled.as_mut() {
+            Some(spilled) => spilled.extend(removed.map(|l| l.iter().map(text_str).collect::<String>())),
+            None => drop(removed),
+        }
+
+        self.dropped += remove;
+
+        let marker = vec![TuiText::styled(dropped_marker(self.dropped), Style::default().modifier(Modifier::DIM))];
+        if start == 0 {
+            self.lines.insert(0, marker);
+        } else {
+            self.lines[0] = marker;
+        }
+
+        // Everything after the marker moved up:
+        let shift = remove - (1 - start);
+        self.shifted += shift;
+        self.cursor.0 = self.cursor.0.saturating_sub(shift);
+        self.saved_cursor.0 = self.saved_cursor.0.saturating_sub(shift);
+        self.screen_base = self.screen_base.saturating_sub(shift);
+    }
+
+    /// The first line of the screen.
+    fn screen_top(&self) -> usize {
+        let end = self.lines.len().max(self.cursor.0 + 1);
+        self.screen_base.max(end.saturating_sub(self.screen_rows))
+    }
+
+    fn screen_bottom(&self) -> usize {
+        self.screen_top() + self.screen_rows - 1
+    }
+
+    fn line_mut(&mut self, idx: usize) -> &mut Vec<TuiText<'a>> {
+        while self.lines.len() <= idx {
+            self.lines.push(vec![]);
+        }
+
+        &mut self.lines[idx]
+    }
+
+    // Edits a line as individual characters (with their styles).
+    fn edit_cells(&mut self, idx: usize, f: impl FnOnce(&mut Vec<(char, Style)>)) {
+        let line = self.line_mut(idx);
+
+        let mut cells = to_cells(line);
+        f(&mut cells);
+        *line = from_cells(&cells);
+    }
+
+    fn newline(&mut self) {
+        self.cursor = (self.cursor.0 + 1, 0);
+        let _ = self.line_mut(self.cursor.0);
+    }
+
+    // Writes a character at the cursor (over whatever's there) and moves the
+    // cursor along.
+    fn put(&mut self, c: char) {
+        let (row, col) = self.cursor;
+        let style = self.style;
+        let line = self.line_mut(row);
+        let len: usize = line.iter().map(|t| text_str(t).chars().count()).sum();
+
+        // Writing at the end of the line is the common case, so it gets to
+        // skip splitting the line up:
+        if col >= len {
+            if col > len {
+                line.push(TuiText::styled(" ".repeat(col - len), Style::default()));
+            }
+
+            match line.last_mut() {
+                Some(TuiText::Styled(s, last)) if *last == style => s.to_mut().push(c),
+                _ => line.push(TuiText::styled(c.to_string(), style)),
+            }
+        } else {
+            self.edit_cells(row, |cells| cells[col] = (c, style));
+        }
+
+        self.cursor.1 += 1;
+    }
+
+    fn escape(&mut self, esc: Escape) {
+        match esc {
+            Escape::Csi { private: false, params, action } => self.csi(&params, action),
+            Escape::Simple('7') => self.saved_cursor = self.cursor,
+            Escape::Simple('8') => self.cursor = self.saved_cursor,
+            Escape::Simple('c') => self.style = Style::default(),
+            // Private sequences (i.e. hiding the cursor), character sets, and
+            // everything else:
+            _ => { /* Unsupported */ },
+        }
+    }
+
+    fn csi(&mut self, params: &[u16], action: char) {
+        let arg = |idx: usize| params.get(idx).copied().unwrap_or(0) as usize;
+        let count = |idx: usize| arg(idx).max(1);
+
+        let (row, col) = self.cursor;
+        let (top, bottom) = (self.screen_top(), self.screen_bottom());
+
+        match action {
+            // Cursor up, down, forward, back:
+            'A' => self.cursor.0 = row.saturating_sub(count(0)).max(top),
+            'B' => self.cursor.0 = (row + count(0)).min(bottom),
+            'C' => self.cursor.1 = col + count(0),
+            'D' => self.cursor.1 = col.saturating_sub(count(0)),
+
+            // Start of the next line, previous line:
+            'E' => self.cursor = ((row + count(0)).min(bottom), 0),
+            'F' => self.cursor = (row.saturating_sub(count(0)).max(top), 0),
+
+            // Column, row, and both (rows and columns start at 1):
+            'G' => self.cursor.1 = count(0) - 1,
+            'd' => self.cursor.0 = (top + count(0) - 1).min(bottom),
+            'H' | 'f' => self.cursor = ((top + count(0) - 1).min(bottom), count(1) - 1),
+
+            'J' => self.erase_screen(arg(0)),
+            'K' => self.erase_line(arg(0)),
+
+            'm' => if let Some(style) = sgr(self.style, params) {
+                self.style = style;
+            },
+
+            's' => self.saved_cursor = self.cursor,
+            'u' => self.cursor = self.saved_cursor,
+
+            _ => { /* Unsupported */ },
+        }
+    }
+
+    fn erase_screen(&mut self, mode: usize) {
+        let (row, col) = self.cursor;
+        let top = self.screen_top();
+
+        match mode {
+            // To the end of the screen:
+            0 => {
+                self.erase_line(0);
+                self.lines.truncate(row + 1);
+            },
+
+            // To the start of the screen:
+            1 => {
+                for idx in top..row.min(self.lines.len()) {
+                    self.lines[idx].clear();
+                }
+
+                self.edit_cells(row, |cells| {
+                    for cell in cells.iter_mut().take(col + 1) { *cell = (' ', Style::default()); }
+                });
+            },
+
+            // The whole screen (and, for `3`, the scrollback; we hold on to
+            // it anyways). The new screen starts after the last thing that
+            // was written and the cursor stays where it was on the screen:
+            _ => {
+                let written = match self.lines.last() {
+                    Some(l) if l.is_empty() => self.lines.len() - 1,
+                    _ => self.lines.len(),
+                };
+
+                self.screen_base = written.max(top);
+                self.cursor.0 = self.screen_base + row.saturating_sub(top);
+            },
+        }
+    }
+
+    fn erase_line(&mut self, mode: usize) {
+        let (row, col) = self.cursor;
+
+        self.edit_cells(row, |cells| match mode {
+            0 => cells.truncate(col),
+            1 => for cell in cells.iter_mut().take(col + 1) { *cell = (' ', Style::default()); },
+            _ => cells.clear(),
+        });
+    }
+}
+
+static NEWLINE: TuiText<'static> = TuiText::Raw(Cow::Borrowed("\n"));
+
+// Escape sequences (minus the leading `ESC`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Escape {
+    /// `ESC [ <params> <action>`; `private` is for sequences whose parameters
+    /// start with one of `<=>?` (i.e. `ESC [ ? 25 l`).
+    Csi { private: bool, params: Vec<u16>, action: char },
+    /// `ESC <c>`.
+    Simple(char),
+    /// `ESC ( <c>` and `ESC ) <c>`.
+    Charset,
+    /// Not actually an escape sequence; only the `ESC` is dropped.
+    Invalid,
+}
+
+// Parses an escape sequence from the characters after an `ESC`, returning the
+// sequence and how many bytes it took up, or `None` if the sequence isn't
+// finished yet.
+fn parse_escape(s: &str) -> Option<(Escape, usize)> {
+    let mut chars = s.char_indices();
+
+    match chars.next()? {
+        (_, '[') => {},
+        (_, '(') | (_, ')') => return chars.next().map(|(idx, c)| (Escape::Charset, idx + c.len_utf8())),
+        (_, c @ '0'..='~') => return Some((Escape::Simple(c), 1)),
+        _ => return Some((Escape::Invalid, 0)),
+    }
+
+    let mut params = String::new();
+    for (idx, c) in chars {
+        match c {
+            // Parameters and intermediate bytes (we don't support any
+            // sequences with the latter):
+            ' '..='?' => params.push(c),
+            '@'..='~' => {
+                let private = params.starts_with(|c| matches!(c, '<'..='?'));
+                let params = if private || params.contains(|c| matches!(c, ' '..='/' | ':')) {
+                    Vec::new()
+                } else {
+                    params.split(';').map(|p| p.parse().unwrap_or(0)).collect()
+                };
+
+                return Some((Escape::Csi { private, params, action: c }, idx + 1));
+            },
+            _ => return Some((Escape::Invalid, 0)),
+        }
+    }
+
+    None
+}
+
+fn to_cells(line: &[TuiText<'_>]) -> Vec<(char, Style)> {
+    line.iter()
+        .flat_map(|t| {
+            let style = match t {
+                TuiText::Raw(_) => Style::default(),
+                TuiText::Styled(_, style) => *style,
+            };
+
+            text_str(t).chars().map(move |c| (c, style))
+        })
+        .collect()
+}
+
+fn from_cells<'t>(cells: &[(char, Style)]) -> Vec<TuiText<'t>> {
+    let mut line: Vec<TuiText<'t>> = Vec::new();
+
+    for (c, style) in cells {
+        match line.last_mut() {
+            Some(TuiText::Styled(s, last)) if last == style => s.to_mut().push(*c),
+            _ => line.push(TuiText::styled(c.to_string(), *style)),
+        }
+    }
+
+    line
+}
+
+/// Applies the parameters of an SGR (Set Graphics Rendition) sequence to a
+/// style, returning `None` if any of them aren't valid.
+///
+/// Details for this are taken from
+/// [here](https://en.wikipedia.org/wiki/ANSI_escape_code#Colors).
+fn sgr(style: Style, params: &[u16]) -> Option<Style> {
+    use Color::*;
+
+    let to_colour = |num: u16| match num {
+        0 => Black,
+        1 => Red,
+        2 => Green,
+        3 => Yellow,
+        4 => Blue,
+        5 => Magenta,
+        6 => Cyan,
+        7 => Gray,
+
+        60 => DarkGray,
+        61 => LightRed,
+        62 => LightGreen,
+        63 => LightYellow,
+        64 => LightBlue,
+        65 => LightMagenta,
+        66 => LightCyan,
+        67 => White,
+
+        _ => unreachable!(),
+    };
+
+    // The 8-bit (`5;n`) and 24-bit (`2;r;g;b`) forms of colours:
+    let extended = |iter: &mut std::slice::Iter<'_, u16>| -> Option<Color> {
+        let byte = |n: Option<&u16>| n.and_then(|n| if *n <= 255 { Some(*n as u8) } else { None });
+
+        match iter.next()? {
+            5 => Some(Indexed(byte(iter.next())?)),
+            2 => Some(Rgb(byte(iter.next())?, byte(iter.next())?, byte(iter.next())?)),
+            _ => None,
+        }
+    };
+
+    let mut s = style;
+    let mut iter = params.iter();
+
+    while let Some(attr) = iter.next() {
+        macro_rules! at {
+            ($a:expr) => {s = s.modifier(s.modifier | $a)};
+        }
+
+        macro_rules! atc {
+            ($a:expr) => {s = s.modifier(s.modifier & !($a))};
+        }
+
+        match attr {
+            0 => s = Style::default(),
+            1 => at!(Modifier::BOLD),
+            2 => at!(Modifier::DIM),
+            3 => at!(Modifier::ITALIC),
+            4 => at!(Modifier::UNDERLINED),
+            5 => at!(Modifier::SLOW_BLINK),
+            6 => at!(Modifier::RAPID_BLINK),
+            7 => at!(Modifier::REVERSED),
+            8 => {},
+            9 => at!(Modifier::CROSSED_OUT),
+
+            21 => atc!(Modifier::BOLD),
+            22 => atc!(Modifier::BOLD | Modifier::DIM),
+            23 => atc!(Modifier::ITALIC),
+            24 => atc!(Modifier::UNDERLINED),
+            25 => atc!(Modifier::SLOW_BLINK | Modifier::RAPID_BLINK),
+            26 => {},
+            27 => atc!(Modifier::REVERSED),
+            28 => {},
+            29 => atc!(Modifier::CROSSED_OUT),
+
+            30..=37 |  90..=97 => s = s.fg(to_colour(*attr - 30)),
+            40..=47 | 100..=107 => s = s.bg(to_colour(*attr - 40)),
+
+            38 => s = s.fg(extended(&mut iter)?),
+            48 => s = s.bg(extended(&mut iter)?),
+
+            39 => s = s.fg(Reset),
+            49 => s = s.bg(Reset),
+
+            _ => return None,
+        }
+    }
+
+    Some(s)
+}
+
+fn text_str<'t>(text: &'t TuiText<'_>) -> &'t str {
+    match text {
+        TuiText::Raw(s) => s,
+        TuiText::Styled(s, _) => s,
+    }.deref()
+}
+
+// Makes a `Text` of the same kind (raw or styled) as `like` with new contents.
+fn text_like<'t>(like: &TuiText<'t>, s: &str) -> TuiText<'t> {
+    match like {
+        TuiText::Raw(_) => TuiText::raw(s.to_string()),
+        TuiText::Styled(_, style) => TuiText::styled(s.to_string(), *style),
+    }
+}
+
+// Splits a string at a character (rather than byte) index.
+fn split_at_char(s: &str, idx: usize) -> (&str, &str) {
+    s.split_at(s.char_indices().nth(idx).map(|(i, _)| i).unwrap_or(s.len()))
+}
+
+/// Splits a line (i.e. from [`AnsiTextContainer::line`]) into rows that are at
+/// most `width` characters wide, dropping the trailing newline. Empty lines
+/// still get one (empty) row.
+///
+/// Note that this counts characters and not columns so wide characters can
+/// still make rows spill over.
+pub fn wrap_line<'t>(line: &[TuiText<'t>], width: usize) -> Vec<Vec<TuiText<'t>>> {
+    let width = width.max(1);
+    let mut rows = vec![vec![]];
+    let mut col = 0;
+
+    for text in line {
+        let s = text_str(text);
+        let mut rest = s.strip_suffix('\n').unwrap_or(s);
+
+        while !rest.is_empty() {
+            if col == width {
+                rows.push(vec![]);
+                col = 0;
+            }
+
+            let take = (width - col).min(rest.chars().count());
+            let (head, tail) = split_at_char(rest, take);
+
+            rows.last_mut().unwrap().push(text_like(text, head));
+            col += take;
+            rest = tail;
+        }
+    }
+
+    rows
+}
+
+/// Applies `f` to the style of the characters in `range` (character indices
+/// into the row) and leaves the rest of the row as is.
+///
+/// Raw [`Text`] instances that get restyled start from the default style.
+///
+/// [`Text`]: tui::widgets::Text
+pub fn restyle<'t>(row: Vec<TuiText<'t>>, range: Range<usize>, f: impl Fn(Style) -> Style) -> Vec<TuiText<'t>> {
+    let mut out = Vec::with_capacity(row.len() + 2);
+    let mut start = 0;
+
+    for text in row {
+        let s = text_str(&text);
+        let len = s.chars().count();
+        let end = start + len;
+
+        if range.end <= start || end <= range.start {
+            out.push(text);
+        } else {
+            let (before, rest) = split_at_char(s, range.start.saturating_sub(start));
+            let (inside, after) = split_at_char(rest, range.end.min(end) - start.max(range.start));
+
+            let style = match &text {
+                TuiText::Raw(_) => Style::default(),
+                TuiText::Styled(_, style) => *style,
+            };
+
+            if !before.is_empty() { out.push(text_like(&text, before)); }
+            out.push(TuiText::styled(inside.to_string(), f(style)));
+            if !after.is_empty() { out.push(text_like(&text, after)); }
+        }
+
+        start = end;
+    }
+
+    out
 }
 
 /// Takes an string with ANSI escape sequences and appends them to a `Vec` of
@@ -429,123 +1022,14 @@ pub fn ansi_string_to_tui_text<'s, 't>(
                                         // Details for this section are taken
                                         // from [here](https://en.wikipedia.org/wiki/ANSI_escape_code#Colors)
                                         'm' if s.parse::<u16>().is_ok() => {
-                                            use Color::*;
-                                            use tui::style::Modifier;
                                             nums.push(s.parse::<u16>().unwrap());
 
-                                            let to_color = |num: u16, sub: u16| {
-                                                match num - sub {
-                                                    0 => Black,
-                                                    1 => Red,
-                                                    2 => Green,
-                                                    3 => Yellow,
-                                                    4 => Blue,
-                                                    5 => Magenta,
-                                                    6 => Cyan,
-                                                    7 => Gray,
-
-                                                    60 => DarkGray,
-                                                    61 => LightRed,
-                                                    62 => LightGreen,
-                                                    63 => LightYellow,
-                                                    64 => LightBlue,
-                                                    65 => LightMagenta,
-                                                    66 => LightCyan,
-                                                    67 => White,
-
-                                                    _ => unreachable!(),
-                                                    // _ => Indexed(num as u8), // TODO: don't as cast
-                                                }
-                                            };
-
-                                            let mut iter = nums.iter().peekable();
-                                            let mut s = current_style.clone();
-                                            loop {
-                                                if let Some(attr) = iter.next() {
-                                                    macro_rules! at {
-                                                        ($a:expr) => {s = s.modifier(s.modifier | $a);};
-                                                    }
-
-                                                    macro_rules! atc {
-                                                        ($a:expr) => {s = s.modifier(s.modifier & !($a));};
-                                                    }
-
-                                                    match attr {
-                                                        0 => s = s.modifier(Modifier::empty()),
-                                                        1 => at!(Modifier::BOLD),
-                                                        2 => at!(Modifier::DIM),
-                                                        3 => at!(Modifier::ITALIC),
-                                                        4 => at!(Modifier::UNDERLINED),
-                                                        5 => at!(Modifier::SLOW_BLINK),
-                                                        6 => at!(Modifier::RAPID_BLINK),
-                                                        7 => at!(Modifier::REVERSED),
-                                                        8 => {},
-                                                        9 => at!(Modifier::CROSSED_OUT),
-
-                                                        21 => atc!(!Modifier::BOLD),
-                                                        22 => atc!(!Modifier::DIM),
-                                                        23 => atc!(!Modifier::ITALIC),
-                                                        24 => atc!(!Modifier::UNDERLINED),
-                                                        25 => {
-                                                            atc!(!Modifier::SLOW_BLINK);
-                                                            atc!(!Modifier::RAPID_BLINK);
-                                                        },
-                                                        26 => {},
-                                                        27 => atc!(Modifier::REVERSED),
-                                                        28 => {},
-                                                        29 => atc!(Modifier::CROSSED_OUT),
-
-                                                        30..=37 |   90..=97 => s = s.fg(to_color(*attr, 30)),
-                                                        40..=47 | 100..=107 => s = s.bg(to_color(*attr, 40)),
-
-                                                        // 8-bit lookup:
-                                                        38 if Some(&&5) == iter.peek() && {
-                                                            assert_eq!(Some(&5), iter.next());
-                                                            iter.peek().is_some()
-                                                        } => {
-                                                            // TODO: don't as cast here.
-                                                            s = s.fg(Indexed(*iter.next().unwrap() as u8));
-                                                        },
-
-                                                        48 if Some(&&5) == iter.peek() && {
-                                                            assert_eq!(Some(&5), iter.next());
-                                                            iter.peek().is_some()
-                                                        } => {
-                                                            // TODO: don't as cast here.
-                                                            s = s.bg(Indexed(*iter.next().unwrap() as u8));
-                                                        },
-
-                                                        // 24-bit lookup:
-                                                        38 if Some(&&2) == iter.peek() && {
-                                                            assert_eq!(Some(&2), iter.next());
-                                                            iter.size_hint().0 >= 3
-                                                        } => {
-                                                            let (r, g, b) = (*iter.next().unwrap(), *iter.next().unwrap(), *iter.next().unwrap());
-                                                            // TODO: don't as cast here.
-                                                            s = s.fg(Rgb(r as u8, g as u8, b as u8));
-                                                        },
-
-                                                        48 if Some(&&2) == iter.peek() && {
-                                                            assert_eq!(Some(&2), iter.next());
-                                                            iter.size_hint().0 >= 3
-                                                        } => {
-                                                            let (r, g, b) = (*iter.next().unwrap(), *iter.next().unwrap(), *iter.next().unwrap());
-                                                            // TODO: don't as cast here.
-                                                            s = s.bg(Rgb(r as u8, g as u8, b as u8));
-                                                        },
-
-                                                        _ => {
-                                                            // Assume this was all a mistake.
-                                                            consumed_characters = 1;
-                                                            break 'c;
-                                                        }
-                                                    }
-                                                } else {
-                                                    break;
-                                                }
+                                            match sgr(*current_style, &nums) {
+                                                Some(s) => *current_style = s,
+                                                // Assume this was all a mistake.
+                                                None => consumed_characters = 1,
                                             }
 
-                                            *current_style = s;
                                             break;
                                         }
 
@@ -672,6 +1156,120 @@ mod ansi_tests {
         }
     }
 
+    mod wrapping {
+        use super::*;
+
+        #[test]
+        fn wrap() {
+            let s = Style::default();
+            let red = Style::default().fg(Color::Red);
+            let line = l![("hello ": s, "world\n": red)].remove(0);
+
+            eq!(wrap_line(&line, 4), l![
+                ("hell": s),
+                ("o ": s, "wo": red),
+                ("rld": red),
+            ]);
+            eq!(wrap_line(&line, 80), l![("hello ": s, "world": red)]);
+            eq!(wrap_line(&[], 10), l![()]);
+        }
+
+        #[test]
+        fn restyling() {
+            let s = Style::default();
+            let rev = s.modifier(Modifier::REVERSED);
+            let row = l![("hello ": s, "world": s)].remove(0);
+
+            eq!(restyle(row.clone(), 3..8, |s| s.modifier(Modifier::REVERSED)), l![
+                ("hel": s, "lo ": rev, "wo": rev, "rld": s),
+            ].remove(0));
+            eq!(restyle(row.clone(), 20..30, |s| s.modifier(Modifier::REVERSED)), row);
+        }
+
+        #[test]
+        fn search() {
+            let mut c = AnsiTextContainer::new();
+            c.push_string("one\nTwo\nthree\ntwo\n".to_string());
+
+            eq!(c.find_before("two", c.num_lines()), Some(3));
+            eq!(c.find_before("two", 3), Some(1));
+            eq!(c.find_before("two", 1), None);
+            eq!(c.line_string(2), "three");
+        }
+    }
+
+    mod screen {
+        use super::*;
+
+        fn screen(inp: &str) -> Vec<String> {
+            let mut c = AnsiTextContainer::new();
+            c.set_screen_rows(4);
+            c.push_string(inp.to_string());
+
+            (0..c.num_lines()).map(|l| c.line_string(l)).collect()
+        }
+
+        #[test]
+        fn control_characters() {
+            eq!(screen("hello\rJ\x08\x08y\tz\x07"), vec!["yello   z"]);
+        }
+
+        #[test]
+        fn cursor_movement() {
+            eq!(screen("ab\ncd\x1b[Ax\x1b[2Cy\x1b[Bz"), vec!["abx  y", "cd    z"]);
+            eq!(screen("\x1b[2;3H#\x1b[H@\x1b[5G!"), vec!["@   !", "  #"]);
+            eq!(screen("\x1b[sone\x1b[u1"), vec!["1ne"]);
+        }
+
+        #[test]
+        fn erasing() {
+            eq!(screen("hello\x1b[3D\x1b[K!"), vec!["he!"]);
+            eq!(screen("hello\x1b[3D\x1b[1K"), vec!["   lo"]);
+            eq!(screen("a\nbc\nd\x1b[A\x1b[J"), vec!["a", "b"]);
+        }
+
+        #[test]
+        fn clearing_keeps_history() {
+            eq!(screen("old\x1b[2J\x1b[Hnew\x1b[2;1Hrow 2"), vec!["old", "new", "row 2"]);
+            eq!(screen("1\n2\n3\n4\n5\x1b[H!"), vec!["1", "!", "3", "4", "5"]);
+        }
+
+        #[test]
+        fn split_escapes() {
+            let mut c = AnsiTextContainer::new();
+            c.push_string("a\x1b[3".to_string());
+            c.push_string("8;5;9mb\x1b[38;2;1;2;3mc".to_string());
+
+            eq!(c.line(0).to_vec(), vec![
+                TuiText::styled("a", Style::default()),
+                TuiText::styled("b", Style::default().fg(Color::Indexed(9))),
+                TuiText::styled("c", Style::default().fg(Color::Rgb(1, 2, 3))),
+            ]);
+        }
+
+        #[test]
+        fn trimming() {
+            let mut c = AnsiTextContainer::new();
+            c.set_screen_rows(2);
+            c.set_limit(4);
+            c.set_spilling(true);
+            c.push_string("1\n2\n3\n4\n5\n6".to_string());
+
+            eq!((0..c.num_lines()).map(|l| c.line_string(l)).collect::<Vec<_>>(), vec!["… 2 lines dropped", "3", "4", "5", "6"]);
+            eq!(c.take_spilled(), vec!["1", "2"]);
+            eq!(c.shifted(), 1);
+
+            // The cursor moved up with everything else:
+            c.push_string("\x1b[H!".to_string());
+            eq!(c.line_string(3), "!");
+        }
+
+        #[test]
+        fn private_and_invalid_escapes() {
+            eq!(screen("\x1b[?25lhi\x1b(B!\x1b[\n"), vec!["hi![", ""]);
+        }
+    }
+
     mod colors {
         use super::*;
 
@@ -685,5 +1283,15 @@ mod ansi_tests {
             eq!(out, l![("hello": s)]);
             eq!(s, Style::default().fg(Color::Red).modifier(Modifier::BOLD));
         }
+
+        #[test]
+        fn resets() {
+            let bold_red = Style::default().fg(Color::Red).modifier(Modifier::BOLD);
+
+            eq!(sgr(bold_red, &[22]), Some(Style::default().fg(Color::Red)));
+            eq!(sgr(bold_red, &[39]), Some(bold_red.fg(Color::Reset)));
+            eq!(sgr(bold_red, &[0]), Some(Style::default()));
+            eq!(sgr(bold_red, &[38, 5, 256]), None);
+        }
     }
 }
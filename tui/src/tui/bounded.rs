@@ -0,0 +1,198 @@
+//! Limits on how much history (the logs and the console) we hold on to.
+//!
+//! Once a history is over its limit its oldest lines are dropped, leaving a
+//! marker line saying how many have been dropped. Dropped lines can also be
+//! kept around until they're spilled to disk (see
+//! [`TuiData::spill`](super::TuiData::spill)).
+
+use crate::colours::c;
+
+use tui::widgets::Text as TuiText;
+use tui::style::Style;
+
+use std::ops::Deref;
+
+/// How many lines of console output we keep by default.
+pub const DEFAULT_CONSOLE_LINES: usize = 10_000;
+/// How many lines of the log we keep by default.
+pub const DEFAULT_LOG_LINES: usize = 10_000;
+/// How many events the debug event log keeps by default.
+pub const DEFAULT_EVENT_LOG_LINES: usize = 100_000;
+
+/// How far over its limit a history can go before lines are dropped.
+///
+/// Dropping lines means shifting everything that's left over so we do it in
+/// batches rather than on every new line.
+pub(in crate) fn slack(limit: usize) -> usize {
+    (limit / 8).max(1)
+}
+
+/// The text of the marker line that stands in for dropped lines, i.e.
+/// "… 10,342 lines dropped".
+pub(in crate) fn dropped_marker(dropped: usize) -> String {
+    let digits = dropped.to_string();
+    let mut num = String::with_capacity(digits.len() + digits.len() / 3);
+
+    for (idx, d) in digits.chars().enumerate() {
+        if idx > 0 && (digits.len() - idx) % 3 == 0 {
+            num.push(',');
+        }
+
+        num.push(d);
+    }
+
+    let s = if dropped == 1 { "" } else { "s" };
+    format!("… {} line{} dropped", num, s)
+}
+
+/// Something that can be a line in a [`BoundedLog`].
+pub trait LogLine {
+    /// The line that stands in for the `dropped` lines that have been
+    /// dropped.
+    fn dropped_marker(dropped: usize) -> Self;
+
+    /// The line as text, for spilling to disk (without a trailing newline).
+    fn to_spilled(&self) -> String;
+}
+
+impl<'a> LogLine for TuiText<'a> {
+    fn dropped_marker(dropped: usize) -> Self {
+        TuiText::styled(format!("{}\n", dropped_marker(dropped)), Style::default().fg(c!(Pause)))
+    }
+
+    fn to_spilled(&self) -> String {
+        let s = match self { TuiText::Raw(s) | TuiText::Styled(s, _) => s };
+        s.trim_end_matches('\n').to_string()
+    }
+}
+
+/// A log (a list of lines) that only keeps its newest `limit` lines.
+///
+/// Derefs to the lines, starting with the marker once lines have been
+/// dropped.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoundedLog<L: LogLine> {
+    lines: Vec<L>,
+    limit: usize,
+    dropped: usize,
+    /// Dropped lines that haven't been taken yet, if we're holding on to them.
+    spilled: Option<Vec<String>>,
+}
+
+impl<L: LogLine> BoundedLog<L> {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            lines: Vec::new(),
+            limit: limit.max(1),
+            dropped: 0,
+            spilled: None,
+        }
+    }
+
+    pub fn push(&mut self, line: L) {
+        self.lines.push(line);
+
+        if self.len_without_marker() > self.limit + slack(self.limit) {
+            self.trim();
+        }
+    }
+
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit.max(1);
+
+        if self.len_without_marker() > self.limit {
+            self.trim();
+        }
+    }
+
+    /// Sets whether dropped lines are held on to (until they're taken with
+    /// [`BoundedLog::take_spilled`]).
+    pub fn set_spilling(&mut self, spilling: bool) {
+        self.spilled = if spilling { self.spilled.take().or_else(|| Some(Vec::new())) } else { None };
+    }
+
+    /// The lines that have been dropped since this was last called (without
+    /// their trailing newlines).
+    pub fn take_spilled(&mut self) -> Vec<String> {
+        self.spilled.as_mut().map(std::mem::take).unwrap_or_default()
+    }
+
+    /// How many lines have been dropped.
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    fn len_without_marker(&self) -> usize {
+        self.lines.len() - if self.dropped > 0 { 1 } else { 0 }
+    }
+
+    fn trim(&mut self) {
+        let start = if self.dropped > 0 { 1 } else { 0 };
+        let remove = self.len_without_marker().saturating_sub(self.limit);
+        if remove == 0 { return; }
+
+        let removed = self.lines.drain(start..(start + remove));
+        match self.spilled.as_mut() {
+            Some(spilled) => spilled.extend(removed.map(|l| l.to_spilled())),
+            None => drop(removed),
+        }
+
+        self.dropped += remove;
+
+        let marker = L::dropped_marker(self.dropped);
+        if start == 0 {
+            self.lines.insert(0, marker);
+        } else {
+            self.lines[0] = marker;
+        }
+    }
+}
+
+impl<L: LogLine> Deref for BoundedLog<L> {
+    type Target = [L];
+
+    fn deref(&self) -> &Self::Target {
+        &self.lines
+    }
+}
+
+#[cfg(test)]
+mod bounded_tests {
+    use super::*;
+    use pretty_assertions::assert_eq as eq;
+
+    fn text(t: &TuiText<'_>) -> String {
+        match t { TuiText::Raw(s) | TuiText::Styled(s, _) => s.to_string() }
+    }
+
+    #[test]
+    fn markers() {
+        eq!(dropped_marker(1), "… 1 line dropped");
+        eq!(dropped_marker(999), "… 999 lines dropped");
+        eq!(dropped_marker(10_342), "… 10,342 lines dropped");
+        eq!(dropped_marker(1_000_000), "… 1,000,000 lines dropped");
+    }
+
+    #[test]
+    fn trimming() {
+        let mut log = BoundedLog::<TuiText<'_>>::new(8);
+        log.set_spilling(true);
+
+        for i in 0..9 {
+            log.push(TuiText::raw(format!("{}\n", i)));
+        }
+        eq!(log.dropped(), 0); // Still within the slack.
+
+        log.push(TuiText::raw("9\n"));
+        eq!(log.dropped(), 2);
+        eq!(log.len(), 9);
+        eq!(text(&log[0]), "… 2 lines dropped\n");
+        eq!(text(&log[1]), "2\n");
+        eq!(log.take_spilled(), vec!["0", "1"]);
+        eq!(log.take_spilled(), Vec::<String>::new());
+
+        log.set_limit(2);
+        eq!(text(&log[0]), "… 8 lines dropped\n");
+        eq!(log.iter().skip(1).map(text).collect::<Vec<_>>(), vec!["8\n", "9\n"]);
+    }
+}
@@ -0,0 +1,372 @@
+//! Typed views of memory, for the [`DataViews`] panel: strings, arrays,
+//! linked lists and binary trees.
+//!
+//! Views are written like this:
+//! ```text
+//! string MSG                        # a null-terminated string
+//! array x4000 10 [hex|dec|char]     # 10 words
+//! list *HEAD [next=1] [value=0]     # follows the pointer at HEAD
+//! tree R0 [left=1] [right=2] [value=0]
+//! ```
+//!
+//! Views start at an address, a label or a register (the address in it); a
+//! `*` in front follows the pointer stored there instead. Offsets are in
+//! words from the start of each node.
+//!
+//! [`DataViews`]: crate::widgets::DataViews
+
+use super::symbols::{parse_number, Symbols};
+
+use lc3_isa::{Addr, Word};
+
+use std::collections::HashSet;
+use std::fmt::{self, Display};
+
+/// Strings longer than this are cut off.
+pub const MAX_STRING: usize = 256;
+/// Lists and trees with more nodes than this are cut off.
+pub const MAX_NODES: usize = 64;
+pub const MAX_ARRAY: Word = 1024;
+
+const USAGE: &str = "views look like `string MSG`, `array x4000 10`, `list *HEAD next=1` or `tree R0 left=1 right=2`";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Format {
+    Hex,
+    Dec,
+    Char,
+}
+
+impl Format {
+    fn show(self, word: Word) -> String {
+        match self {
+            Format::Hex => format!("x{:04X}", word),
+            Format::Dec => format!("{:>6}", word as i16),
+            Format::Char => format!("{:>4}", char_literal(word)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Kind {
+    String,
+    Array { len: Word, format: Format },
+    List { next: Word, value: Word },
+    Tree { left: Word, right: Word, value: Word },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DataView {
+    pub kind: Kind,
+    /// What the view starts at (see the module docs).
+    pub target: String,
+}
+
+/// A piece of a line of a view; `addr` is the word it shows, if it shows
+/// one (so changes can be highlighted).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub text: String,
+    pub addr: Option<Addr>,
+}
+
+impl Span {
+    fn text(text: impl Into<String>) -> Self {
+        Self { text: text.into(), addr: None }
+    }
+
+    fn word(text: String, addr: Addr) -> Self {
+        Self { text, addr: Some(addr) }
+    }
+}
+
+pub type Line = Vec<Span>;
+
+// Characters as they'd be written in a string (i.e. `a` or `\n`).
+fn escaped(word: Word) -> String {
+    match word {
+        0x0A => "\\n".to_string(),
+        0x09 => "\\t".to_string(),
+        0x22 => "\\\"".to_string(),
+        0x5C => "\\\\".to_string(),
+        0x20..=0x7E => (word as u8 as char).to_string(),
+        _ => format!("\\x{:02X}", word),
+    }
+}
+
+fn char_literal(word: Word) -> String {
+    format!("'{}'", escaped(word))
+}
+
+fn value(word: Word) -> String {
+    format!("x{:04X} ({})", word, word as i16)
+}
+
+fn pointer(word: Word) -> String {
+    if word == 0 { "null".to_string() } else { format!("x{:04X}", word) }
+}
+
+impl DataView {
+    pub fn parse(src: &str) -> Result<Self, String> {
+        let words: Vec<&str> = src.split_whitespace().collect();
+        let (kind, target, rest) = match words.as_slice() {
+            [kind, target, rest @ ..] => (kind.to_lowercase(), target.to_string(), rest),
+            _ => return Err(format!("Expected a kind of view and where it starts; {}", USAGE)),
+        };
+
+        // `name=offset` options, with defaults:
+        let offsets = |names: &[(&str, Word)], rest: &[&str]| -> Result<Vec<Word>, String> {
+            let mut values: Vec<Word> = names.iter().map(|(_, default)| *default).collect();
+            for option in rest {
+                let (name, off) = option.split_once('=').ok_or_else(|| format!("Expected `name=offset`, not `{}`", option))?;
+                let idx = names.iter().position(|(n, _)| *n == name.to_lowercase()).ok_or_else(|| format!(
+                    "`{}` isn't an option here (expected {})",
+                    name, names.iter().map(|(n, _)| format!("`{}`", n)).collect::<Vec<_>>().join(", "),
+                ))?;
+                values[idx] = parse_number(off).ok_or_else(|| format!("`{}` isn't an offset", off))?;
+            }
+
+            Ok(values)
+        };
+
+        let kind = match (kind.as_str(), rest) {
+            ("string" | "str", []) => Kind::String,
+            ("array" | "arr", [len, format @ ..]) => {
+                let len = parse_number(len)
+                    .filter(|l| (1..=MAX_ARRAY).contains(l))
+                    .ok_or_else(|| format!("`{}` isn't a length (arrays have 1 to {} words)", len, MAX_ARRAY))?;
+
+                let format = match format {
+                    [] => Format::Hex,
+                    [f] => match f.to_lowercase().as_str() {
+                        "hex" => Format::Hex,
+                        "dec" => Format::Dec,
+                        "char" => Format::Char,
+                        _ => return Err(format!("`{}` isn't a format (expected `hex`, `dec` or `char`)", f)),
+                    },
+                    _ => return Err(format!("Expected `array <start> <length> [hex|dec|char]`; {}", USAGE)),
+                };
+
+                Kind::Array { len, format }
+            },
+            ("list", rest) => match offsets(&[("next", 1), ("value", 0)], rest)?.as_slice() {
+                [next, value] => Kind::List { next: *next, value: *value },
+                _ => unreachable!(),
+            },
+            ("tree", rest) => match offsets(&[("left", 1), ("right", 2), ("value", 0)], rest)?.as_slice() {
+                [left, right, value] => Kind::Tree { left: *left, right: *right, value: *value },
+                _ => unreachable!(),
+            },
+            ("string" | "str", _) => return Err(format!("Expected `string <start>`; {}", USAGE)),
+            ("array" | "arr", _) => return Err(format!("Expected `array <start> <length>`; {}", USAGE)),
+            (other, _) => return Err(format!("`{}` isn't a kind of view; {}", other, USAGE)),
+        };
+
+        Ok(Self { kind, target })
+    }
+
+    /// Works out where the view starts (see [`Symbols::resolve`]).
+    pub fn resolve(&self, symbols: &Symbols, regs: &[Word; 8], pc: Addr, read: impl Fn(Addr) -> Word) -> Result<Addr, String> {
+        symbols.resolve(&self.target, regs, pc, read)
+    }
+
+    /// The view's lines, for the view starting at `start`.
+    pub fn render(&self, start: Addr, read: impl Fn(Addr) -> Word) -> Vec<Line> {
+        match self.kind {
+            Kind::String => {
+                let mut line = vec![Span::text("\"")];
+                let mut len = 0;
+
+                loop {
+                    let addr = start.wrapping_add(len as Word);
+                    let word = read(addr);
+                    if word == 0 {
+                        line.push(Span::text(format!("\" ({} characters)", len)));
+                        break;
+                    }
+                    if len == MAX_STRING {
+                        line.push(Span::text(format!("…\" (no terminator in the first {} words)", MAX_STRING)));
+                        break;
+                    }
+
+                    line.push(Span::word(escaped(word), addr));
+                    len += 1;
+                }
+
+                vec![line]
+            },
+
+            Kind::Array { len, format } => (0..len).step_by(8).map(|row| {
+                let mut line = vec![Span::text(format!("[{:>3}]", row))];
+                for idx in row..(row + 8).min(len) {
+                    let addr = start.wrapping_add(idx);
+                    line.push(Span::text(" "));
+                    line.push(Span::word(format.show(read(addr)), addr));
+                }
+                line
+            }).collect(),
+
+            Kind::List { next, value } => {
+                let mut lines = Vec::new();
+                let mut seen = HashSet::new();
+                let mut node = start;
+
+                loop {
+                    if node == 0 {
+                        lines.push(vec![Span::text("null")]);
+                        break;
+                    }
+                    if !seen.insert(node) {
+                        lines.push(vec![Span::text(format!("↺ back to x{:04X} (the list has a cycle)", node))]);
+                        break;
+                    }
+                    if seen.len() > MAX_NODES {
+                        lines.push(vec![Span::text(format!("… (stopped after {} nodes)", MAX_NODES))]);
+                        break;
+                    }
+
+                    let (value_addr, next_addr) = (node.wrapping_add(value), node.wrapping_add(next));
+                    let next_node = read(next_addr);
+                    lines.push(vec![
+                        Span::text(format!("x{:04X}: ", node)),
+                        Span::word(self::value(read(value_addr)), value_addr),
+                        Span::text(" → "),
+                        Span::word(pointer(next_node), next_addr),
+                    ]);
+                    node = next_node;
+                }
+
+                lines
+            },
+
+            Kind::Tree { left, right, value } => {
+                let mut lines = Vec::new();
+                if start == 0 {
+                    lines.push(vec![Span::text("null (the tree is empty)")]);
+                } else {
+                    let offsets = TreeOffsets { left, right, value };
+                    offsets.node(start, "", "", &read, &mut HashSet::new(), &mut lines);
+                }
+
+                lines
+            },
+        }
+    }
+}
+
+struct TreeOffsets {
+    left: Word,
+    right: Word,
+    value: Word,
+}
+
+impl TreeOffsets {
+    // `node`'s line (after `prefix` and `branch`) and then its children's.
+    fn node(&self, node: Addr, prefix: &str, branch: &str, read: &impl Fn(Addr) -> Word, seen: &mut HashSet<Addr>, lines: &mut Vec<Line>) {
+        if !seen.insert(node) {
+            lines.push(vec![Span::text(format!("{}{}↺ x{:04X} (already shown; the tree has a cycle)", prefix, branch, node))]);
+            return;
+        }
+        if seen.len() > MAX_NODES {
+            lines.push(vec![Span::text(format!("{}{}… (stopped after {} nodes)", prefix, branch, MAX_NODES))]);
+            return;
+        }
+
+        let (value_addr, left_addr, right_addr) = (node.wrapping_add(self.value), node.wrapping_add(self.left), node.wrapping_add(self.right));
+        let (l, r) = (read(left_addr), read(right_addr));
+
+        lines.push(vec![
+            Span::text(format!("{}{}x{:04X}: ", prefix, branch, node)),
+            Span::word(value(read(value_addr)), value_addr),
+            Span::text("  L→"),
+            Span::word(pointer(l), left_addr),
+            Span::text(" R→"),
+            Span::word(pointer(r), right_addr),
+        ]);
+
+        // Children go under their parent's branch:
+        let prefix = match branch {
+            "" => prefix.to_string(),
+            b if b.starts_with('└') => format!("{}    ", prefix),
+            _ => format!("{}│   ", prefix),
+        };
+
+        let children: Vec<(&str, Addr)> = [("L ", l), ("R ", r)].iter().copied().filter(|(_, c)| *c != 0).collect();
+        for (idx, (side, child)) in children.iter().enumerate() {
+            let branch = if idx + 1 == children.len() { format!("└─{}", side) } else { format!("├─{}", side) };
+            self.node(*child, &prefix, &branch, read, seen, lines);
+        }
+    }
+}
+
+impl Display for DataView {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            Kind::String => write!(fmt, "string {}", self.target),
+            Kind::Array { len, format } => {
+                let format = match format { Format::Hex => "", Format::Dec => " dec", Format::Char => " char" };
+                write!(fmt, "array {} {}{}", self.target, len, format)
+            },
+            Kind::List { next, value } => write!(fmt, "list {} next={} value={}", self.target, next, value),
+            Kind::Tree { left, right, value } => write!(fmt, "tree {} left={} right={} value={}", self.target, left, right, value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod data_views_tests {
+    use super::*;
+    use pretty_assertions::assert_eq as eq;
+
+    fn text(lines: Vec<Line>) -> Vec<String> {
+        lines.into_iter().map(|l| l.into_iter().map(|s| s.text).collect()).collect()
+    }
+
+    #[test]
+    fn parsing() {
+        eq!(DataView::parse("string MSG").unwrap().kind, Kind::String);
+        eq!(DataView::parse("array x4000 #10 dec").unwrap().kind, Kind::Array { len: 10, format: Format::Dec });
+        eq!(DataView::parse("list *HEAD next=2").unwrap().kind, Kind::List { next: 2, value: 0 });
+        eq!(DataView::parse("tree R0 value=2 left=0 right=1").unwrap().to_string(), "tree R0 left=0 right=1 value=2");
+
+        assert!(DataView::parse("array x4000").unwrap_err().starts_with("Expected `array"));
+        assert!(DataView::parse("list HEAD prev=1").unwrap_err().contains("`prev` isn't an option"));
+        assert!(DataView::parse("queue HEAD").unwrap_err().starts_with("`queue` isn't"));
+    }
+
+    #[test]
+    fn rendering() {
+        let mut memory = vec![0; 0x10000];
+        for &(addr, word) in &[
+            (0x4000, 'H' as Word), (0x4001, '\n' as Word),
+            // A list with a cycle: x5000 → x5002 → x5000:
+            (0x5000, 1), (0x5001, 0x5002), (0x5002, 2), (0x5003, 0x5000),
+            // A tree: x6000 with a right child at x6003:
+            (0x6000, 5), (0x6002, 0x6003), (0x6003, 7),
+        ] {
+            memory[addr] = word;
+        }
+        let read = |a: Addr| memory[a as usize];
+
+        let string = DataView::parse("string x4000").unwrap().render(0x4000, &read);
+        eq!(text(string.clone()), vec!["\"H\\n\" (2 characters)"]);
+        eq!(string[0][1].addr, Some(0x4000));
+
+        let array = DataView::parse("array x4000 9 char").unwrap().render(0x4000, &read);
+        eq!(array.len(), 2);
+        eq!(array[1][2].text, "'\\x00'");
+
+        let list = DataView::parse("list x5000").unwrap().render(0x5000, &read);
+        eq!(text(list), vec![
+            "x5000: x0001 (1) → x5002",
+            "x5002: x0002 (2) → x5000",
+            "↺ back to x5000 (the list has a cycle)",
+        ]);
+
+        let tree = DataView::parse("tree x6000").unwrap().render(0x6000, &read);
+        eq!(text(tree), vec![
+            "x6000: x0005 (5)  L→null R→x6003",
+            "└─R x6003: x0007 (7)  L→null R→null",
+        ]);
+    }
+}
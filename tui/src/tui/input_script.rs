@@ -0,0 +1,160 @@
+//! Input scripts: text that's fed to the program's input as it reads it (so
+//! the same input doesn't have to be typed in by hand on every run).
+
+use super::time::now;
+
+use std::time::Duration;
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
+
+/// Text that's sent to the program (through the console's `InputSink`) as the
+/// program takes it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputScript {
+    input: Vec<char>,
+    pos: usize,
+    /// How long to wait between characters.
+    ///
+    /// Note that input is only sent when the TUI updates so delays shorter
+    /// than the update period are effectively rounded up to it.
+    pub delay: Duration,
+    /// Whether to start over from the beginning when the machine is reset.
+    pub refeed_on_reset: bool,
+    /// When the last character was sent (see `now`).
+    last_sent: Option<u64>,
+}
+
+impl InputScript {
+    pub fn new(input: &str) -> Self {
+        Self {
+            input: input.chars().collect(),
+            pos: 0,
+            delay: Duration::from_millis(0),
+            refeed_on_reset: false,
+            last_sent: None,
+        }
+    }
+
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    pub fn with_refeed_on_reset(mut self, refeed: bool) -> Self {
+        self.refeed_on_reset = refeed;
+        self
+    }
+
+    /// Makes a script from something typed into a prompt, where `\n`, `\t`,
+    /// `\\` and `\xNN` are escapes.
+    pub fn from_escaped(s: &str) -> Result<Self, String> {
+        let mut out = String::with_capacity(s.len());
+        let mut chars = s.chars();
+
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                out.push(c);
+                continue;
+            }
+
+            match chars.next() {
+                Some('n') => out.push('\n'),
+                Some('t') => out.push('\t'),
+                Some('\\') => out.push('\\'),
+                Some('x') => {
+                    let hex: String = chars.by_ref().take(2).collect();
+                    match u8::from_str_radix(&hex, 16) {
+                        Ok(b) if hex.len() == 2 => out.push(b as char),
+                        _ => return Err(format!("Invalid escape: `\\x{}`", hex)),
+                    }
+                },
+                Some(other) => return Err(format!("Unknown escape: `\\{}`", other)),
+                None => return Err("Trailing `\\`".to_string()),
+            }
+        }
+
+        Ok(Self::new(&out))
+    }
+
+    /// The characters that haven't been sent yet.
+    pub fn remaining(&self) -> &[char] {
+        &self.input[self.pos..]
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.pos == self.input.len()
+    }
+
+    pub fn rewind(&mut self) {
+        self.pos = 0;
+        self.last_sent = None;
+    }
+
+    /// The next character to send, if it's time to send it.
+    pub(in crate) fn peek(&self) -> Option<char> {
+        let due = match self.last_sent {
+            Some(last) => now().saturating_sub(last) >= self.delay.as_millis() as u64,
+            None => true,
+        };
+
+        if due { self.input.get(self.pos).copied() } else { None }
+    }
+
+    /// Call once the character from [`peek`](InputScript::peek) has been sent.
+    pub(in crate) fn advance(&mut self) {
+        self.pos = (self.pos + 1).min(self.input.len());
+
+        if self.delay > Duration::from_millis(0) {
+            self.last_sent = Some(now());
+        }
+    }
+}
+
+// No files on wasm.
+#[cfg(not(target_arch = "wasm32"))]
+impl InputScript {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        use anyhow::Context;
+
+        let input = std::fs::read_to_string(path)
+            .with_context(|| format!("Couldn't read the input file at `{}`", path.display()))?;
+
+        Ok(Self::new(&input))
+    }
+}
+
+#[cfg(test)]
+mod input_script_tests {
+    use super::*;
+    use pretty_assertions::assert_eq as eq;
+
+    #[test]
+    fn escapes() {
+        eq!(InputScript::from_escaped("a\\nb\\t\\\\\\x41").unwrap().remaining(), &['a', '\n', 'b', '\t', '\\', 'A']);
+        assert!(InputScript::from_escaped("\\q").is_err());
+        assert!(InputScript::from_escaped("\\x4").is_err());
+        assert!(InputScript::from_escaped("oops\\").is_err());
+    }
+
+    #[test]
+    fn feeding() {
+        let mut s = InputScript::new("hi");
+        eq!(s.peek(), Some('h'));
+        s.advance();
+        eq!(s.peek(), Some('i'));
+        s.advance();
+        eq!(s.peek(), None);
+        assert!(s.is_done());
+
+        s.rewind();
+        eq!(s.remaining(), &['h', 'i']);
+    }
+
+    #[test]
+    fn delays() {
+        let mut s = InputScript::new("hi").with_delay(Duration::from_secs(60));
+        s.advance();
+        eq!(s.peek(), None);
+    }
+}
@@ -0,0 +1,226 @@
+//! Keeps track of when the processor enters supervisor mode (and why).
+//!
+//! The simulator doesn't tell us when a trap or an interrupt is taken, so we
+//! look for the switch from user to supervisor mode on every update and
+//! every step. When we catch it right as it happens (always true for steps)
+//! the PC is at a handler from one of the vector tables and the supervisor
+//! stack has the PC and PSR that were pushed; anything taken and finished
+//! between two updates while running is missed, and anything we only catch
+//! up with mid-handler is recorded without a cause.
+//!
+//! Traps and interrupts taken from supervisor mode (i.e. a handler using a
+//! trap) don't change the mode, so we only see those when we catch the PC
+//! arriving at a handler with a matching frame on the stack: reliably when
+//! stepping, rarely while running.
+
+use lc3_isa::{Addr, Bits, Word};
+
+use std::collections::VecDeque;
+use std::fmt::{self, Display};
+
+use super::symbols::trap_name;
+
+/// Where the trap vector table starts (it's x100 words long).
+pub const TRAP_VECTOR_TABLE: Addr = 0x0000;
+/// Where the interrupt vector table starts (it's x100 words long).
+pub const INTERRUPT_VECTOR_TABLE: Addr = 0x0100;
+pub const VECTOR_TABLE_LEN: Addr = 0x0100;
+
+/// How many traps and interrupts we remember.
+pub const HISTORY_LEN: usize = 64;
+
+/// What the interrupt vectors that every LC-3 has are for.
+pub fn interrupt_name(vector: u8) -> Option<&'static str> {
+    match vector {
+        0x00 => Some("privilege mode violation"),
+        0x01 => Some("illegal opcode"),
+        0x02 => Some("access control violation"),
+        0x80 => Some("keyboard"),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Cause {
+    Trap(u8),
+    /// Interrupts and exceptions (which share the interrupt vector table).
+    Interrupt(u8),
+    /// We only caught up with it once the handler was underway.
+    Unknown,
+}
+
+impl Display for Cause {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Cause::Trap(v) => match trap_name(*v) {
+                Some(name) => write!(fmt, "TRAP x{:02X} ({})", v, name),
+                None => write!(fmt, "TRAP x{:02X}", v),
+            },
+            Cause::Interrupt(v) => match interrupt_name(*v) {
+                Some(name) => write!(fmt, "INT x{:02X} ({})", v, name),
+                None => write!(fmt, "INT x{:02X}", v),
+            },
+            Cause::Unknown => write!(fmt, "trap or interrupt"),
+        }
+    }
+}
+
+/// A trap or interrupt that was taken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Taken {
+    /// When (see `now`).
+    pub at: u64,
+    pub cause: Cause,
+    /// Where we first saw the processor in supervisor mode (the handler, if
+    /// we caught it on the way in).
+    pub pc: Addr,
+    /// The PC and PSR pushed onto the supervisor stack; `None` when we caught
+    /// up with it mid-handler.
+    pub saved: Option<(Addr, Word)>,
+}
+
+/// A peripheral that can raise interrupts.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Source {
+    pub name: String,
+    pub enabled: bool,
+    /// `None` when we can't tell (i.e. without the simulator's peripherals).
+    pub pending: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct InterruptHistory {
+    /// Whether we were in supervisor mode as of the last update.
+    supervisor: Option<bool>,
+    /// The PC as of the last update.
+    pc: Option<Addr>,
+    /// The most recent, oldest first.
+    pub taken: VecDeque<Taken>,
+    /// How many have been taken in all.
+    pub count: u64,
+}
+
+impl InterruptHistory {
+    /// Catches up with the processor; `r6` is the stack pointer and `read`
+    /// reads memory.
+    pub fn observe(&mut self, psr: Word, pc: Addr, r6: Word, now: u64, read: impl Fn(Addr) -> Word) {
+        let supervisor = !psr.bit(15);
+        let was_supervisor = self.supervisor.replace(supervisor);
+        let moved = self.pc.replace(pc) != Some(pc);
+
+        let (cause, saved) = match (was_supervisor, supervisor) {
+            (Some(false), true) => Self::cause(pc, r6, true, &read),
+            // Without a change of mode, only what we catch on the way in:
+            (Some(true), true) if moved => match Self::cause(pc, r6, false, &read) {
+                (Cause::Unknown, _) => return,
+                found => found,
+            },
+            _ => return,
+        };
+
+        if self.taken.len() == HISTORY_LEN {
+            let _ = self.taken.pop_front();
+        }
+        self.taken.push_back(Taken { at: now, cause, pc, saved });
+        self.count += 1;
+    }
+
+    // Works out why we're at `pc` from the frame on the stack; `from_user` is
+    // whether the PSR that was pushed should be a user mode one.
+    fn cause(pc: Addr, r6: Word, from_user: bool, read: impl Fn(Addr) -> Word) -> (Cause, Option<(Addr, Word)>) {
+        let (saved_pc, saved_psr) = (read(r6), read(r6.wrapping_add(1)));
+        let handler = |table: Addr| (0..VECTOR_TABLE_LEN).find(|v| read(table + v) == pc);
+
+        if saved_psr.bit(15) != from_user {
+            return (Cause::Unknown, None);
+        }
+
+        let prev = read(saved_pc.wrapping_sub(1));
+        let trap = prev >> 12 == 0xF && read(prev & 0xFF) == pc;
+
+        match (trap, handler(INTERRUPT_VECTOR_TABLE)) {
+            (true, _) => (Cause::Trap(prev as u8), Some((saved_pc, saved_psr))),
+            (false, Some(v)) => (Cause::Interrupt(v as u8), Some((saved_pc, saved_psr))),
+            (false, None) => (Cause::Unknown, None),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod interrupts_tests {
+    use super::*;
+    use pretty_assertions::assert_eq as eq;
+
+    use std::collections::HashMap;
+
+    const USER: Word = 0x8002;
+    const SUPERVISOR: Word = 0x0002;
+
+    fn memory(words: &[(Addr, Word)]) -> impl Fn(Addr) -> Word {
+        let mem: HashMap<Addr, Word> = words.iter().copied().collect();
+        move |addr| mem.get(&addr).copied().unwrap_or(0)
+    }
+
+    #[test]
+    fn traps_and_interrupts() {
+        let mut history = InterruptHistory::default();
+        let read = memory(&[
+            (0x0025, 0x0500),                       // HALT's handler
+            (0x0180, 0x0600),                       // keyboard's handler
+            (0x3004, 0xF025),                       // TRAP x25
+            (0x2FFE, 0x3005), (0x2FFF, USER),       // pushed by the trap
+            (0x2FFC, 0x3010), (0x2FFD, USER | 0x4), // pushed by the interrupt
+        ]);
+
+        // Nothing happens until we've seen user mode:
+        history.observe(SUPERVISOR, 0x0200, 0x3000, 0, &read);
+        history.observe(USER, 0x3000, 0xFE00, 1, &read);
+        eq!(history.count, 0);
+
+        history.observe(SUPERVISOR, 0x0500, 0x2FFE, 2, &read);
+        history.observe(SUPERVISOR, 0x0501, 0x2FFE, 3, &read);
+        eq!(history.count, 1);
+
+        history.observe(USER, 0x3005, 0xFE00, 4, &read);
+        history.observe(SUPERVISOR, 0x0600, 0x2FFC, 5, &read);
+
+        // Caught mid-handler:
+        history.observe(USER, 0x3011, 0xFE00, 6, &read);
+        history.observe(SUPERVISOR, 0x0603, 0x2FF0, 7, &read);
+
+        let taken: Vec<_> = history.taken.iter().map(|t| (t.at, t.cause, t.saved)).collect();
+        eq!(taken, vec![
+            (2, Cause::Trap(0x25), Some((0x3005, USER))),
+            (5, Cause::Interrupt(0x80), Some((0x3010, USER | 0x4))),
+            (7, Cause::Unknown, None),
+        ]);
+
+        eq!(Cause::Trap(0x25).to_string(), "TRAP x25 (HALT)");
+        eq!(Cause::Interrupt(0x81).to_string(), "INT x81");
+    }
+
+    #[test]
+    fn from_supervisor_mode() {
+        let mut history = InterruptHistory::default();
+        let read = memory(&[
+            (0x0021, 0x0700),                         // OUT's handler
+            (0x0025, 0x0500),                         // HALT's handler
+            (0x0510, 0xF021),                         // TRAP x21 (in HALT's handler)
+            (0x2FFC, 0x0511), (0x2FFD, SUPERVISOR),   // pushed by the trap
+        ]);
+
+        history.observe(SUPERVISOR, 0x0510, 0x2FFE, 0, &read);
+        history.observe(SUPERVISOR, 0x0700, 0x2FFC, 1, &read);
+
+        // Staying put isn't taking it again:
+        history.observe(SUPERVISOR, 0x0700, 0x2FFC, 2, &read);
+        history.observe(SUPERVISOR, 0x0701, 0x2FFC, 3, &read);
+
+        let taken: Vec<_> = history.taken.iter().map(|t| (t.at, t.cause, t.saved)).collect();
+        eq!(taken, vec![(1, Cause::Trap(0x21), Some((0x0511, SUPERVISOR)))]);
+    }
+}
@@ -0,0 +1,203 @@
+//! The console's input line.
+
+/// How many past lines we hold on to.
+const HISTORY_LEN: usize = 100;
+
+/// The line being typed into the console: the line itself, a cursor and (for
+/// the line-buffered mode) a history of lines that have been entered.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LineEditor {
+    line: Vec<char>,
+    cursor: usize,
+    history: Vec<String>,
+    /// When going through the history: the entry we're on and what had been
+    /// typed before we started.
+    browsing: Option<(usize, Vec<char>)>,
+}
+
+impl LineEditor {
+    pub fn line(&self) -> String {
+        self.line.iter().collect()
+    }
+
+    /// Where the cursor is, in characters.
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.line.is_empty()
+    }
+
+    pub fn history(&self) -> &[String] {
+        &self.history
+    }
+
+    pub fn insert(&mut self, c: char) {
+        self.line.insert(self.cursor, c);
+        self.cursor += 1;
+    }
+
+    /// Adds a character to the end of the line, regardless of where the cursor
+    /// is.
+    pub fn push(&mut self, c: char) {
+        self.line.push(c);
+    }
+
+    pub fn backspace(&mut self) {
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            let _ = self.line.remove(self.cursor);
+        }
+    }
+
+    pub fn delete(&mut self) {
+        if self.cursor < self.line.len() {
+            let _ = self.line.remove(self.cursor);
+        }
+    }
+
+    pub fn left(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    pub fn right(&mut self) {
+        self.cursor = (self.cursor + 1).min(self.line.len());
+    }
+
+    pub fn home(&mut self) {
+        self.cursor = 0;
+    }
+
+    pub fn end(&mut self) {
+        self.cursor = self.line.len();
+    }
+
+    /// Deletes everything before the cursor (i.e. `ctrl+u`).
+    pub fn kill_to_start(&mut self) {
+        let _ = self.line.drain(..self.cursor);
+        self.cursor = 0;
+    }
+
+    /// Replaces the line, putting the cursor at its end.
+    pub fn set_line(&mut self, line: &str) {
+        self.line = line.chars().collect();
+        self.cursor = self.line.len();
+        self.browsing = None;
+    }
+
+    /// Empties the line (but not the history).
+    pub fn clear(&mut self) {
+        self.line.clear();
+        self.cursor = 0;
+        self.browsing = None;
+    }
+
+    /// Empties the line, adding it to the history, and returns it.
+    pub fn submit(&mut self) -> String {
+        let line = self.line();
+        self.clear();
+
+        if !line.is_empty() && self.history.last() != Some(&line) {
+            if self.history.len() == HISTORY_LEN {
+                let _ = self.history.remove(0);
+            }
+
+            self.history.push(line.clone());
+        }
+
+        line
+    }
+
+    /// Replaces the line with the previous line in the history.
+    pub fn history_prev(&mut self) {
+        let idx = match self.browsing {
+            Some((0, _)) => return,
+            Some((idx, _)) => idx - 1,
+            None if self.history.is_empty() => return,
+            None => {
+                self.browsing = Some((self.history.len(), self.line.clone()));
+                self.history.len() - 1
+            },
+        };
+
+        self.set_browsing(idx);
+    }
+
+    /// Replaces the line with the next line in the history (or with what was
+    /// being typed, once we're past the end of the history).
+    pub fn history_next(&mut self) {
+        match self.browsing {
+            Some((idx, _)) if idx + 1 < self.history.len() => self.set_browsing(idx + 1),
+            Some(_) => {
+                let (_, draft) = self.browsing.take().unwrap();
+                self.line = draft;
+                self.cursor = self.line.len();
+            },
+            None => {},
+        }
+    }
+
+    fn set_browsing(&mut self, idx: usize) {
+        if let Some((ref mut current, _)) = self.browsing {
+            *current = idx;
+        }
+
+        self.line = self.history[idx].chars().collect();
+        self.cursor = self.line.len();
+    }
+}
+
+#[cfg(test)]
+mod line_editor_tests {
+    use super::*;
+    use pretty_assertions::assert_eq as eq;
+
+    fn typed(s: &str) -> LineEditor {
+        let mut l = LineEditor::default();
+        s.chars().for_each(|c| l.insert(c));
+        l
+    }
+
+    #[test]
+    fn editing() {
+        let mut l = typed("helo");
+        l.left();
+        l.insert('l');
+        eq!(l.line(), "hello");
+        eq!(l.cursor(), 4);
+
+        l.end();
+        l.backspace();
+        l.home();
+        l.delete();
+        eq!(l.line(), "ell");
+
+        l.right();
+        l.right();
+        l.kill_to_start();
+        eq!(l.line(), "l");
+        eq!(l.cursor(), 0);
+    }
+
+    #[test]
+    fn history() {
+        let mut l = typed("one");
+        eq!(l.submit(), "one");
+        "two".chars().for_each(|c| l.insert(c));
+        let _ = l.submit();
+        let _ = l.submit(); // Empty lines aren't recorded.
+
+        "dra".chars().for_each(|c| l.insert(c));
+        l.history_prev();
+        eq!(l.line(), "two");
+        l.history_prev();
+        l.history_prev();
+        eq!(l.line(), "one");
+        l.history_next();
+        eq!(l.line(), "two");
+        l.history_next();
+        eq!(l.line(), "dra");
+        eq!(l.history(), &["one".to_string(), "two".to_string()]);
+    }
+}
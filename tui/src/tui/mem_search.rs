@@ -0,0 +1,267 @@
+//! Searches for the [`Mem`] widget.
+//!
+//! Searches look like this:
+//! ```text
+//! x1234            # a word
+//! x48 ? x6C        # consecutive words (`?` matches any word)
+//! "Hello"          # a string, a word per character (like `.STRINGZ`)
+//! bytes xF0 x25    # bytes, wherever they are (the high byte of a word comes first)
+//! TRAP x25         # instructions, by mnemonic and (the first few) operands
+//! ```
+//!
+//! Instructions are decoded from the words themselves so they match however
+//! they're written: `HALT` finds `TRAP x25`, `ADD R1` finds every `ADD` into
+//! R1, and `BR` on its own finds branches with any condition.
+//!
+//! [`Mem`]: crate::widgets::Mem
+
+use super::symbols::{parse_number, TRAP_NAMES};
+
+use lc3_isa::{Addr, Word};
+
+const MNEMONICS: &[&str] = &[
+    "add", "and", "not", "ld", "ldi", "ldr", "lea", "st", "sti", "str", "jmp", "ret", "jsr", "jsrr",
+    "rti", "trap", "br", "brn", "brz", "brp", "brnz", "brnp", "brzp", "brnzp",
+];
+
+/// Whether `word` is an instruction's mnemonic (or one of the trap routines').
+pub fn is_mnemonic(word: &str) -> bool {
+    let word = word.to_lowercase();
+    MNEMONICS.contains(&word.as_str()) || TRAP_NAMES.iter().any(|(_, n)| n.to_lowercase() == word)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operand {
+    Reg(u8),
+    /// Immediates and offsets (sign extended) and trap vectors.
+    Num(Word),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Query {
+    /// Consecutive words; `None` matches any word.
+    Words(Vec<Option<Word>>),
+    /// Consecutive bytes; `None` matches any byte.
+    Bytes(Vec<Option<u8>>),
+    /// Instructions with this (uppercase) mnemonic whose operands start with
+    /// these.
+    Instruction { mnemonic: String, operands: Vec<Operand> },
+}
+
+fn sext(word: Word, bits: u32) -> Word {
+    let shift = 16 - bits;
+    (((word << shift) as i16) >> shift) as Word
+}
+
+fn reg(word: Word, lsb: u32) -> Operand {
+    Operand::Reg(((word >> lsb) & 0b111) as u8)
+}
+
+/// The mnemonic and operands of the instruction in `word`; `None` for the
+/// reserved opcode and for branches that are never taken (i.e. `x0000`).
+pub fn decode(word: Word) -> Option<(String, Vec<Operand>)> {
+    use Operand::Num;
+
+    let (mnemonic, operands) = match word >> 12 {
+        0x0 => {
+            let nzp = (word >> 9) & 0b111;
+            if nzp == 0 {
+                return None;
+            }
+
+            let flags: String = [(0b100, 'n'), (0b010, 'z'), (0b001, 'p')].iter()
+                .filter(|(bit, _)| nzp & bit != 0)
+                .map(|(_, flag)| *flag)
+                .collect();
+            return Some((format!("BR{}", flags), vec![Num(sext(word, 9))]));
+        },
+        op @ (0x1 | 0x5) => {
+            let src2 = if word & (1 << 5) != 0 { Num(sext(word, 5)) } else { reg(word, 0) };
+            (if op == 0x1 { "ADD" } else { "AND" }, vec![reg(word, 9), reg(word, 6), src2])
+        },
+        0x2 => ("LD", vec![reg(word, 9), Num(sext(word, 9))]),
+        0xA => ("LDI", vec![reg(word, 9), Num(sext(word, 9))]),
+        0xE => ("LEA", vec![reg(word, 9), Num(sext(word, 9))]),
+        0x3 => ("ST", vec![reg(word, 9), Num(sext(word, 9))]),
+        0xB => ("STI", vec![reg(word, 9), Num(sext(word, 9))]),
+        0x6 => ("LDR", vec![reg(word, 9), reg(word, 6), Num(sext(word, 6))]),
+        0x7 => ("STR", vec![reg(word, 9), reg(word, 6), Num(sext(word, 6))]),
+        0x4 if word & (1 << 11) != 0 => ("JSR", vec![Num(sext(word, 11))]),
+        0x4 => ("JSRR", vec![reg(word, 6)]),
+        0x9 => ("NOT", vec![reg(word, 9), reg(word, 6)]),
+        0xC if (word >> 6) & 0b111 == 7 => ("RET", vec![]),
+        0xC => ("JMP", vec![reg(word, 6)]),
+        0x8 => ("RTI", vec![]),
+        0xF => ("TRAP", vec![Num(word & 0xFF)]),
+        _ => return None,
+    };
+
+    Some((mnemonic.to_string(), operands))
+}
+
+fn parse_operand(src: &str) -> Result<Operand, String> {
+    let lower = src.to_lowercase();
+    match lower.strip_prefix('r').and_then(|n| n.parse::<u8>().ok()) {
+        Some(r) if r < 8 => Ok(Operand::Reg(r)),
+        _ => parse_number(src).map(Operand::Num).ok_or_else(|| format!("`{}` isn't a register or a number", src)),
+    }
+}
+
+// The characters in a quoted string, as words.
+fn parse_string(src: &str) -> Result<Vec<Option<Word>>, String> {
+    let inner = src.strip_prefix('"').and_then(|s| s.strip_suffix('"')).filter(|s| !s.is_empty())
+        .ok_or_else(|| "Strings need a closing `\"` (and something in them)".to_string())?;
+
+    let mut words = Vec::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        let c = if c != '\\' { c } else {
+            match chars.next() {
+                Some('n') => '\n',
+                Some('t') => '\t',
+                Some('0') => '\0',
+                Some(c @ ('"' | '\\')) => c,
+                Some(other) => return Err(format!("`\\{}` isn't an escape (try `\\n`, `\\t`, `\\0`, `\\\"` or `\\\\`)", other)),
+                None => return Err("Strings can't end with a `\\`".to_string()),
+            }
+        };
+
+        words.push(Some(c as u32 as Word));
+    }
+
+    Ok(words)
+}
+
+impl Query {
+    pub fn parse(src: &str) -> Result<Self, String> {
+        let src = src.trim();
+        if src.starts_with('"') {
+            return parse_string(src).map(Query::Words);
+        }
+
+        let tokens: Vec<&str> = src.split(|c: char| c.is_whitespace() || c == ',').filter(|t| !t.is_empty()).collect();
+        let (first, rest) = tokens.split_first().ok_or_else(|| "Search for what?".to_string())?;
+        let first = first.to_lowercase();
+
+        if first == "bytes" {
+            if rest.is_empty() {
+                return Err("Expected some bytes after `bytes`".to_string());
+            }
+
+            return rest.iter().map(|b| match *b {
+                "?" => Ok(None),
+                b => parse_number(b).filter(|v| *v <= 0xFF).map(|v| Some(v as u8))
+                    .ok_or_else(|| format!("`{}` isn't a byte", b)),
+            }).collect::<Result<_, _>>().map(Query::Bytes);
+        }
+
+        if let Some((vector, name)) = TRAP_NAMES.iter().find(|(_, n)| n.to_lowercase() == first) {
+            return match rest {
+                [] => Ok(Query::Instruction { mnemonic: "TRAP".to_string(), operands: vec![Operand::Num(*vector as Word)] }),
+                _ => Err(format!("`{}` doesn't take operands", name)),
+            };
+        }
+        if is_mnemonic(&first) {
+            let operands = rest.iter().map(|o| parse_operand(o)).collect::<Result<_, _>>()?;
+            return Ok(Query::Instruction { mnemonic: first.to_uppercase(), operands });
+        }
+
+        tokens.iter().map(|w| match *w {
+            "?" => Ok(None),
+            w => parse_number(w).map(Some).ok_or_else(|| format!(
+                "`{}` isn't a number, a string, `bytes …` or an instruction", w,
+            )),
+        }).collect::<Result<_, _>>().map(Query::Words)
+    }
+
+    fn matches_instruction(mnemonic: &str, operands: &[Operand], word: Word) -> bool {
+        let (actual, actual_operands) = match decode(word) {
+            Some(inst) => inst,
+            None => return false,
+        };
+
+        let name_matches = actual.eq_ignore_ascii_case(mnemonic) || (mnemonic == "BR" && actual.starts_with("BR"));
+        name_matches && actual_operands.starts_with(operands)
+    }
+
+    /// Where the matches in `mem` (all of memory, usually) start; matches can
+    /// wrap around the end.
+    pub fn matches(&self, mem: &[Word]) -> Vec<Addr> {
+        let len = mem.len();
+        match self {
+            Query::Words(pattern) => (0..len)
+                .filter(|start| pattern.iter().enumerate().all(|(i, w)| w.map_or(true, |w| mem[(start + i) % len] == w)))
+                .map(|start| start as Addr)
+                .collect(),
+
+            Query::Bytes(pattern) => {
+                let byte = |idx: usize| {
+                    let word = mem[(idx / 2) % len];
+                    if idx % 2 == 0 { (word >> 8) as u8 } else { word as u8 }
+                };
+
+                let mut found: Vec<Addr> = Vec::new();
+                for start in 0..(2 * len) {
+                    if pattern.iter().enumerate().all(|(i, b)| b.map_or(true, |b| byte(start + i) == b))
+                        && found.last() != Some(&((start / 2) as Addr))
+                    {
+                        found.push((start / 2) as Addr);
+                    }
+                }
+                found
+            },
+
+            Query::Instruction { mnemonic, operands } => (0..len)
+                .filter(|addr| Self::matches_instruction(mnemonic, operands, mem[*addr]))
+                .map(|addr| addr as Addr)
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod mem_search_tests {
+    use super::*;
+    use pretty_assertions::assert_eq as eq;
+
+    #[test]
+    fn parsing() {
+        eq!(Query::parse("x1234"), Ok(Query::Words(vec![Some(0x1234)])));
+        eq!(Query::parse("1 ? #-1"), Ok(Query::Words(vec![Some(1), None, Some(0xFFFF)])));
+        eq!(Query::parse("\"Hi\\n\""), Ok(Query::Words(vec![Some(0x48), Some(0x69), Some(0x0A)])));
+        eq!(Query::parse("bytes xF0 ?"), Ok(Query::Bytes(vec![Some(0xF0), None])));
+        eq!(Query::parse("halt"), Ok(Query::Instruction { mnemonic: "TRAP".to_string(), operands: vec![Operand::Num(0x25)] }));
+        eq!(Query::parse("add r1, R2"), Ok(Query::Instruction { mnemonic: "ADD".to_string(), operands: vec![Operand::Reg(1), Operand::Reg(2)] }));
+
+        assert!(Query::parse("bytes x100").is_err());
+        assert!(Query::parse("\"unfinished").is_err());
+        assert!(Query::parse("loop").unwrap_err().starts_with("`loop` isn't"));
+    }
+
+    #[test]
+    fn searching() {
+        let mem = [
+            0xF025, // TRAP x25
+            0x1261, // ADD R1, R1, #1
+            0x0BFD, // BRnp #-3
+            0x0048, 0x0069, 0x0000,
+            0x1242, // ADD R1, R1, R2
+        ];
+        let find = |src: &str| Query::parse(src).unwrap().matches(&mem);
+
+        eq!(find("HALT"), vec![0]);
+        eq!(find("TRAP"), vec![0]);
+        eq!(find("add R1 R1"), vec![1, 6]);
+        eq!(find("ADD r1, r1, #1"), vec![1]);
+        eq!(find("br"), vec![2]);
+        eq!(find("BRz"), Vec::<Addr>::new());
+        eq!(find("\"Hi\""), vec![3]);
+        eq!(find("x0048 ? 0"), vec![3]);
+        eq!(find("bytes x25 x12"), vec![0]);
+        // Wraps around:
+        eq!(find("x1242 xF025"), vec![6]);
+
+        eq!(decode(0x0BFD), Some(("BRnp".to_string(), vec![Operand::Num(0xFFFD)])));
+        eq!(decode(0xC1C0), Some(("RET".to_string(), vec![])));
+    }
+}
@@ -8,14 +8,22 @@ use lc3_shims::peripherals::SourceShim;
 use lc3_traits::control::rpc::{EventFuture, SyncEventFutureSharedState};
 use lc3_traits::control::control::{Control, Event};
 
-use lc3_isa::Addr;
+use lc3_isa::{Addr, Bits};
+
+use crate::colours::{self, Theme};
+use crate::config::Config;
+use crate::keymap::Keymap;
+use crate::markers;
 
 use std::sync::Mutex;
 use std::time::Duration;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::string::ToString;
 use std::cell::RefCell;
 
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::PathBuf;
+
 
 use tui::widgets::Text as TuiText;
 use tui::style::{Style, Color};
@@ -23,6 +31,55 @@ use tui::style::{Style, Color};
 pub mod ansi;
 use ansi::AnsiTextContainer;
 
+pub mod line_editor;
+use line_editor::LineEditor;
+
+pub mod bounded;
+use bounded::BoundedLog;
+
+pub mod program_log;
+use program_log::{Level, LogEntry};
+
+pub(in crate) mod time;
+
+pub mod input_script;
+pub use input_script::InputScript;
+
+pub mod transcript;
+use transcript::Transcript;
+
+pub mod peripheral_commands;
+mod peripherals;
+
+pub mod signal;
+use signal::SignalGenerator;
+
+pub mod timer_history;
+use timer_history::TimerHistory;
+
+pub mod stimulus;
+pub use stimulus::StimulusScript;
+
+pub mod peripheral_trace;
+use peripheral_trace::PeripheralTrace;
+
+pub mod symbols;
+use symbols::Symbols;
+
+pub mod interrupts;
+use interrupts::InterruptHistory;
+
+pub mod stack;
+use stack::StackTracker;
+
+pub mod data_views;
+
+pub mod mem_search;
+
+pub mod word_input;
+
+pub mod prompt;
+
 pub mod run;
 pub mod events;
 pub mod widget;
@@ -32,6 +89,14 @@ pub use program_source::ProgramSource;
 
 pub use anyhow::Result as Res;
 
+/// Where [`TuiData::save_transcript`] writes to when the config doesn't say.
+#[cfg(not(target_arch = "wasm32"))]
+pub const DEFAULT_TRANSCRIPT_PATH: &str = "transcript.txt";
+
+/// Where [`TuiData::export_log`] writes to.
+#[cfg(not(target_arch = "wasm32"))]
+pub const DEFAULT_LOG_EXPORT_PATH: &str = "program-log.txt";
+
 #[allow(explicit_outlives_requirements)]
 pub struct TuiData<'a, 'int, C, I = SourceShim, O = Mutex<Vec<u8>>>
 where
@@ -51,14 +116,48 @@ where
 
     pub(in crate) reset_flag: u8,
     pub(in crate) load_flag: u8,
+    /// Goes up whenever memory or the registers might have changed: when the
+    /// machine steps or runs and when they're edited. Widgets that keep a
+    /// copy of memory use this to tell when it's out of date.
+    pub(in crate) writes: u64,
     pub(in crate) jump: (u8, Addr),
     pub(in crate) mem_reg_inter: (u8, Addr),
 
-    pub(in crate) console_input_string: RefCell<String>, // TODO: Give this a better name, maybe.
+    pub(in crate) console_input: RefCell<LineEditor>,
+    /// Input for the program that it hasn't taken yet; see
+    /// [`TuiData::send_input`].
+    pub(in crate) input_queue: VecDeque<char>,
+    /// Fed to the program once it has taken everything in `input_queue`.
+    pub(in crate) input_script: Option<InputScript>,
+    /// Whether console input is sent a line at a time (instead of as it's
+    /// typed).
+    pub(in crate) line_buffered: bool,
     pub(in crate) console_hist: RefCell<AnsiTextContainer<'a>>,
-
-    pub(in crate) debug_log: Option<Vec<TuiText<'a>>>,
-    pub(in crate) log: Vec<TuiText<'a>>,
+    /// Everything that's gone through the console, for
+    /// [`TuiData::save_transcript`].
+    pub(in crate) transcript: RefCell<Transcript>,
+
+    pub(in crate) debug_log: Option<BoundedLog<TuiText<'a>>>,
+    pub(in crate) log: BoundedLog<LogEntry<'a>>,
+
+    /// The signal generators driving the ADC pins (see
+    /// [`TuiData::drive_adc`]).
+    pub(in crate) adc_signals: [Option<SignalGenerator>; 6],
+    /// When the timers have fired (see [`TuiData::track_timers`]).
+    pub(in crate) timer_history: [TimerHistory; 2],
+    /// See [`TuiData::run_stimulus`].
+    pub(in crate) stimulus: Option<StimulusScript>,
+    /// What the peripherals have been up to (see
+    /// [`TuiData::sample_peripherals`]).
+    pub(in crate) trace: PeripheralTrace,
+
+    /// The loaded program's labels (updated on every load).
+    pub(in crate) symbols: Symbols,
+    /// The traps and interrupts that have been taken (see
+    /// [`TuiData::track_interrupts`]).
+    pub(in crate) interrupts: InterruptHistory,
+    /// Where the stacks are (see [`TuiData::track_stack`]).
+    pub(in crate) stack: StackTracker,
 
     pub(in crate) bp: HashMap<Addr, usize>,
     pub(in crate) wp: HashMap<Addr, usize>,
@@ -66,6 +165,25 @@ where
     pub(in crate) flush_all_events: Option<Flush>,
     /// Is `Some(_)` when an `Event` has _just_ occurred.
     pub(in crate) current_event: Option<Event>,
+
+    pub(in crate) keymap: Keymap,
+    pub(in crate) config: Config,
+    /// Set when something in `config` changes (i.e. widgets are resized).
+    pub(in crate) config_changed: bool,
+    /// Whether the border between two widgets is being dragged.
+    pub(in crate) dragging_split: bool,
+
+    /// The last thing that was copied (i.e. text selected in the console);
+    /// see [`TuiData::copy`].
+    pub(in crate) clipboard: Option<String>,
+    /// Copied text that the event loop hasn't yet sent to the terminal.
+    pub(in crate) pending_copy: Option<String>,
+    /// The errors from the last failed load, if the last load failed.
+    pub(in crate) diagnostics: Option<String>,
+
+    /// The themes that [`TuiData::cycle_theme`] cycles through.
+    pub(in crate) themes: Vec<Theme>,
+    pub(in crate) current_theme: usize,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
@@ -82,11 +200,21 @@ where
     O: OutputSource + ?Sized + 'a,
 {
     pub fn log<L: ToString>(&mut self, line: L, colour: Color) {
-        self.log.push(TuiText::styled(line.to_string(), Style::default().fg(colour)))
+        self.log_at(Level::Info, line, colour)
+    }
+
+    /// Logs a message at the given level; the entry's source is the `[tag]`
+    /// the message starts with (see [`LogEntry::new`]).
+    pub fn log_at<L: ToString>(&mut self, level: Level, line: L, colour: Color) {
+        let style = Style::default().fg(colour);
+        let entry = LogEntry::new(level, time::now(), self.sim.get_pc(), &line.to_string(), Some(style));
+
+        self.log.push(entry)
     }
 
     pub fn log_raw<L: ToString>(&mut self, line: L) {
-        self.log.push(TuiText::raw(line.to_string()))
+        let entry = LogEntry::new(Level::Info, time::now(), self.sim.get_pc(), &line.to_string(), None);
+        self.log.push(entry)
     }
 
     pub(in crate) fn flush_events(&mut self) {
@@ -100,6 +228,343 @@ where
     pub fn get_current_event(&self) -> Option<Event> {
         self.current_event
     }
+
+    pub fn keymap(&self) -> &Keymap {
+        &self.keymap
+    }
+
+    /// Copies some text; `what` describes it for the log (i.e. "console
+    /// selection").
+    ///
+    /// The event loop hands the text to the terminal; see
+    /// [`clipboard`](crate::clipboard).
+    pub fn copy(&mut self, what: &str, text: String) {
+        let msg = format!("[Copy] Copied the {} ({} characters).\n", what, text.chars().count());
+        self.log(msg, colours::c!(Success));
+
+        self.pending_copy = Some(text.clone());
+        self.clipboard = Some(text);
+    }
+
+    pub(in crate) fn copy_log(&mut self) {
+        let text = self.log.iter()
+            .map(|e| format!("{}\n", e))
+            .collect();
+
+        self.copy("program log", text)
+    }
+
+    /// Writes log entries (i.e. the ones the Log tab is showing) to
+    /// [`DEFAULT_LOG_EXPORT_PATH`].
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(in crate) fn export_log(&mut self, text: String) {
+        let path = DEFAULT_LOG_EXPORT_PATH;
+        match std::fs::write(path, text) {
+            Ok(()) => self.log(format!("[Log] Exported the log to `{}`.\n", path), colours::c!(Success)),
+            Err(e) => self.log_at(Level::Error, format!("[Log] Failed to export the log to `{}`: {}\n", path, e), colours::c!(Error)),
+        }
+    }
+
+    /// No files on the web so this copies the entries instead.
+    #[cfg(target_arch = "wasm32")]
+    pub(in crate) fn export_log(&mut self, text: String) {
+        self.copy("program log", text)
+    }
+
+    pub(in crate) fn copy_registers(&mut self) {
+        let (regs, psr, pc) = self.sim.get_registers_psr_and_pc();
+
+        let mut text = String::new();
+        for (idx, reg) in regs.iter().enumerate() {
+            text.push_str(&format!("R{}: {:#06x} {:#018b} {}\n", idx, reg, reg, reg));
+        }
+        text.push_str(&format!("PC: {:#06x}\nPSR: {:#06x}\n", pc, psr));
+
+        self.copy("registers", text)
+    }
+
+    pub(in crate) fn copy_diagnostics(&mut self) {
+        match self.diagnostics.clone() {
+            Some(d) => self.copy("load errors", d),
+            None => self.log_at(Level::Warn, "[Copy] The last load didn't fail; there are no errors to copy.\n", colours::c!(Pause)),
+        }
+    }
+
+    /// Sends the last thing that was copied to the program's input, as if it
+    /// had been typed into the console.
+    pub(in crate) fn paste(&mut self) {
+        specialize! { [var: text]
+            desktop => { self.clipboard.clone().or_else(crate::clipboard::fallback_contents) }
+            web => { self.clipboard.clone() }
+        }
+
+        let text = match text {
+            Some(t) => t,
+            None => return self.log_at(Level::Warn, "[Paste] Nothing has been copied yet.\n", colours::c!(Pause)),
+        };
+
+        for c in text.chars().filter(|c| *c != '\r') {
+            self.type_char(c);
+        }
+    }
+
+    /// Handles a character typed into the console: in line-buffered mode it
+    /// goes into the line (and newlines send the line), otherwise it's sent to
+    /// the program right away.
+    pub(in crate) fn type_char(&mut self, c: char) {
+        let line = self.console_input.get_mut();
+
+        match (self.line_buffered, c) {
+            (true, '\n') => {
+                let line = line.submit();
+                self.send_input(line.chars().chain(Some('\n')));
+            },
+            (true, c) => line.insert(c),
+            (false, '\n') => {
+                line.clear();
+                self.send_input(Some('\n'));
+            },
+            (false, c) => {
+                line.push(c);
+                self.send_input(Some(c));
+            },
+        }
+    }
+
+    /// Queues up input for the program and sends as much of it as the
+    /// program will currently take.
+    ///
+    /// The rest is sent as the program reads its input (we check on every
+    /// event, including ticks) so nothing gets dropped when the program isn't
+    /// keeping up (i.e. with big pastes).
+    pub(in crate) fn send_input(&mut self, chars: impl IntoIterator<Item = char>) {
+        self.input_queue.extend(chars);
+        self.flush_input();
+    }
+
+    pub(in crate) fn flush_input(&mut self) {
+        let sink = match self.input {
+            Some(sink) => sink,
+            None => return self.input_queue.clear(),
+        };
+
+        let pc = self.sim.get_pc();
+        let transcript = self.transcript.get_mut();
+
+        while let Some(c) = self.input_queue.front() {
+            if sink.put_char(*c).is_none() {
+                return;
+            }
+
+            transcript.input(*c, pc);
+            let _ = self.input_queue.pop_front();
+        }
+
+        // Once everything that was typed has been taken, the input script:
+        if let Some(script) = self.input_script.as_mut() {
+            while let Some(c) = script.peek() {
+                if sink.put_char(c).is_none() {
+                    break;
+                }
+
+                transcript.input(c, pc);
+                script.advance();
+            }
+        }
+    }
+
+    /// Checks whether a trap or an interrupt has been taken since the last
+    /// check; this happens on every update and after every step.
+    pub(in crate) fn track_interrupts(&mut self) {
+        let (regs, psr, pc) = self.sim.get_registers_psr_and_pc();
+        let sim = &*self.sim;
+
+        self.interrupts.observe(psr, pc, regs[6], time::now(), |addr| sim.read_word(addr));
+    }
+
+    /// Catches up with R6; this happens on every update and after every
+    /// step. Logs a warning when R6 leaves the stack region (see
+    /// [`Config::stack_region`]).
+    ///
+    /// The region is for the program's stack: R6 in user mode or, without
+    /// the OS, in either mode.
+    pub(in crate) fn track_stack(&mut self) {
+        let (regs, psr, pc) = self.sim.get_registers_psr_and_pc();
+        let (sp, supervisor) = (regs[6], !psr.bit(15));
+        let sim = &*self.sim;
+
+        self.stack.observe(pc, supervisor, sp, |addr| sim.read_word(addr));
+
+        // Stacks that haven't been set up yet don't count as leaving:
+        let region = self.config.stack_region.unwrap_or_default();
+        let inside = region.contains(sp);
+        if (!supervisor || !self.use_os) && !inside && self.stack.in_region {
+            let msg = format!(
+                "[Stack] R6 (x{:04X}) left the stack region (x{:04X} to x{:04X}) at PC x{:04X}.\n",
+                sp, region.low, region.high, pc,
+            );
+            self.log_at(Level::Warn, msg, colours::c!(Pause));
+        }
+        if !supervisor || !self.use_os {
+            self.stack.in_region = inside;
+        }
+    }
+
+    /// Replaces the input script (or removes it, when given `None`).
+    pub(in crate) fn set_input_script(&mut self, script: Option<InputScript>) {
+        let msg = match &script {
+            Some(s) => format!("[Input] Feeding {} characters to the program's input.\n", s.remaining().len()),
+            None => "[Input] Removed the input script.\n".to_string(),
+        };
+
+        self.log(msg, colours::c!(Success));
+        self.input_script = script;
+    }
+
+    /// A short description of the input that the program hasn't taken yet
+    /// (what was typed, then the input script), if there is any.
+    pub(in crate) fn pending_input(&self) -> Option<String> {
+        const PREVIEW_LEN: usize = 24;
+
+        let script = self.input_script.as_ref().map(|s| s.remaining()).unwrap_or(&[]);
+        let total = self.input_queue.len() + script.len();
+        if total == 0 {
+            return None;
+        }
+
+        let preview: String = self.input_queue.iter()
+            .chain(script.iter())
+            .take(PREVIEW_LEN)
+            .flat_map(|c| c.escape_default())
+            .collect();
+        let ellipsis = if total > PREVIEW_LEN { "…" } else { "" };
+
+        Some(format!("queued: \"{}{}\" ({} characters)", preview, ellipsis, total))
+    }
+
+    /// Writes the console history to the configured transcript path (see
+    /// [`Config::transcript_path`]): with escapes stripped (and timestamps, if
+    /// [`Config::transcript_timestamps`] is set) and, next to it with an
+    /// `.ansi` extension, exactly as the program wrote it.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(in crate) fn save_transcript(&mut self) {
+        let path = self.config.transcript_path.clone()
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_TRANSCRIPT_PATH));
+        let raw_path = path.with_extension("ansi");
+
+        let (text, raw) = {
+            let transcript = self.transcript.borrow();
+            let text = if self.config.transcript_timestamps {
+                transcript.annotated()
+            } else {
+                self.console_hist.borrow().as_string()
+            };
+
+            (text, transcript.raw().to_string())
+        };
+
+        let res = std::fs::write(&path, text).and_then(|()| std::fs::write(&raw_path, raw));
+        match res {
+            Ok(()) => {
+                let msg = format!("[Transcript] Saved the console to `{}` (and `{}`).\n", path.display(), raw_path.display());
+                self.log(msg, colours::c!(Success))
+            },
+            Err(e) => self.log_at(Level::Error, format!("[Transcript] Failed to save the console to `{}`: {}\n", path.display(), e), colours::c!(Error)),
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub(in crate) fn save_transcript(&mut self) {
+        self.log_at(Level::Warn, "[Transcript] Saving isn't supported here; use copy-console instead.\n", colours::c!(Pause))
+    }
+
+    /// Sets the console's and the logs' limits (and whether they hold on to
+    /// dropped lines) from the config.
+    pub(in crate) fn apply_history_limits(&mut self) {
+        use bounded::*;
+
+        let spilling = cfg!(not(target_arch = "wasm32")) && self.config.spill_dir.is_some();
+
+        let hist = self.console_hist.get_mut();
+        hist.set_limit(self.config.console_lines.unwrap_or(DEFAULT_CONSOLE_LINES));
+        hist.set_spilling(spilling);
+
+        self.log.set_limit(self.config.log_lines.unwrap_or(DEFAULT_LOG_LINES));
+        self.log.set_spilling(spilling);
+
+        if let Some(events) = self.debug_log.as_mut() {
+            events.set_limit(self.config.event_log_lines.unwrap_or(DEFAULT_EVENT_LOG_LINES));
+            events.set_spilling(spilling);
+        }
+    }
+
+    /// Appends the lines that have been dropped from the console and the logs
+    /// to files in [`Config::spill_dir`] (`console.txt`, `log.txt` and
+    /// `events.txt`).
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(in crate) fn spill(&mut self) {
+        use std::fs::{self, OpenOptions};
+        use std::io::Write;
+
+        let dir = match self.config.spill_dir.clone() {
+            Some(dir) => dir,
+            None => return,
+        };
+
+        let spilled = [
+            ("console.txt", self.console_hist.get_mut().take_spilled()),
+            ("log.txt", self.log.take_spilled()),
+            ("events.txt", self.debug_log.as_mut().map(|l| l.take_spilled()).unwrap_or_default()),
+        ];
+
+        for (name, lines) in spilled.iter().filter(|(_, l)| !l.is_empty()) {
+            let path = dir.join(name);
+            let res = fs::create_dir_all(&dir)
+                .and_then(|()| OpenOptions::new().create(true).append(true).open(&path))
+                .and_then(|mut f| lines.iter().try_for_each(|l| writeln!(f, "{}", l)));
+
+            if let Err(e) = res {
+                // Don't keep trying (and keep logging that it failed):
+                self.config.spill_dir = None;
+                self.apply_history_limits();
+
+                let msg = format!("[History] Couldn't save dropped lines to `{}` ({}); they'll be discarded from now on.\n", path.display(), e);
+                return self.log_at(Level::Error, msg, colours::c!(Error));
+            }
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub(in crate) fn spill(&mut self) {}
+
+    /// Switches between sending console input as it's typed and sending it a
+    /// line at a time.
+    pub(in crate) fn toggle_line_buffered(&mut self) {
+        self.line_buffered = !self.line_buffered;
+        self.console_input.get_mut().clear();
+
+        let msg = if self.line_buffered {
+            "[Console] Input is now sent a line at a time.\n"
+        } else {
+            "[Console] Input is now sent as it's typed.\n"
+        };
+        self.log(msg, colours::c!(Success));
+    }
+
+    /// Switches to the next theme.
+    pub fn cycle_theme(&mut self) {
+        if self.themes.is_empty() {
+            return;
+        }
+
+        self.current_theme = (self.current_theme + 1) % self.themes.len();
+
+        let theme = &self.themes[self.current_theme];
+        colours::set_palette(theme.palette.clone());
+
+        let msg = format!("Switched to the `{}` theme.\n", theme.name);
+        self.log(msg, colours::c!(Success));
+    }
 }
 
 
@@ -113,12 +578,18 @@ where
     pub data: TuiData<'a, 'int, C, I, O>,
 
     pub(in crate::tui) update_period: Duration,
+    /// Where changes to the config (i.e. widget sizes) are saved on exit.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(in crate::tui) config_path: Option<PathBuf>,
     // pub(in crate::tui)
 
 }
 
 impl<'a, 'int, C: Control + ?Sized + 'a, I: InputSink + ?Sized + 'a, O: OutputSource + ?Sized + 'a> Tui<'a, 'int, C, I, O> {
     pub fn new(sim: &'a mut C) -> Self {
+        let mut themes = Theme::builtin();
+        let current_theme = Theme::find_startup(&mut themes);
+
         Self {
             data: TuiData {
                 sim,
@@ -131,28 +602,60 @@ impl<'a, 'int, C: Control + ?Sized + 'a, I: InputSink + ?Sized + 'a, O: OutputSo
 
                 reset_flag: 0,
                 load_flag: 0,
+                writes: 0,
                 jump: (0,0x200),
                 mem_reg_inter: (0, 0),
 
-                console_input_string: RefCell::new(String::from("")),
-                console_hist: RefCell::new(AnsiTextContainer::with_capacity(1024)),
+                console_input: RefCell::new(LineEditor::default()),
+                input_queue: VecDeque::new(),
+                input_script: None,
+                line_buffered: false,
+                console_hist: RefCell::new({
+                    let mut hist = AnsiTextContainer::with_capacity(1024);
+                    hist.set_limit(bounded::DEFAULT_CONSOLE_LINES);
+                    hist
+                }),
+                transcript: RefCell::new(Transcript::new()),
 
                 debug_log: if crate::debug::in_debug_mode() {
-                    Some(Vec::with_capacity(32 * 1024 * 1024))
+                    Some(BoundedLog::new(bounded::DEFAULT_EVENT_LOG_LINES))
                 } else {
                     None
                 },
 
-                log: Vec::with_capacity(16 * 1024 * 1024),
+                log: BoundedLog::new(bounded::DEFAULT_LOG_LINES),
+
+                adc_signals: Default::default(),
+                timer_history: Default::default(),
+                stimulus: None,
+                trace: PeripheralTrace::default(),
+
+                symbols: Symbols::default(),
+                interrupts: InterruptHistory::default(),
+                stack: StackTracker::default(),
 
                 bp: HashMap::new(),
                 wp: HashMap::new(),
 
                 flush_all_events: None,
                 current_event: None,
+
+                keymap: Keymap::default(),
+                config: Config::default(),
+                config_changed: false,
+                dragging_split: false,
+
+                clipboard: None,
+                pending_copy: None,
+                diagnostics: None,
+
+                themes,
+                current_theme,
             },
 
             update_period: Duration::from_millis(250),
+            #[cfg(not(target_arch = "wasm32"))]
+            config_path: None,
         }
     }
 
@@ -266,4 +769,122 @@ where
         self.update_period = period;
         self
     }
+
+    pub fn set_keymap(&mut self, keymap: Keymap) -> &mut Self {
+        self.data.keymap = keymap;
+        self
+    }
+
+    /// Applies a user [`Config`]; fails if the config has invalid key
+    /// bindings or themes.
+    pub fn set_config(&mut self, config: Config) -> Res<&mut Self> {
+        let keymap = Keymap::from_config(&config).map_err(anyhow::Error::msg)?;
+
+        let mut themes = Theme::builtin();
+        specialize! { [var: _files]
+            desktop => {
+                for path in config.theme_files.iter() {
+                    themes.push(Theme::load(path)?);
+                }
+            }
+            web => {
+                if !config.theme_files.is_empty() {
+                    log::warn!("Theme files aren't supported on the web; ignoring them.");
+                }
+            }
+        }
+
+        self.set_themes(themes);
+        if let Some(ref theme) = config.theme {
+            let _ = self.set_theme(theme)?;
+        }
+
+        if config.markers {
+            markers::set_enabled(true);
+        }
+        if config.no_colour || (config.theme.is_none() && markers::no_colour_requested()) {
+            let _ = self.set_no_colour();
+        }
+
+        self.data.keymap = keymap;
+        self.data.line_buffered = config.line_buffered;
+        self.data.config = config;
+        self.data.apply_history_limits();
+        Ok(self)
+    }
+
+    /// Sets text to feed to the program's input as it reads it; see
+    /// [`InputScript`].
+    pub fn set_input_script(&mut self, script: InputScript) -> &mut Self {
+        self.data.set_input_script(Some(script));
+        self
+    }
+
+    /// Sets a script of changes to make to the peripherals' inputs as the
+    /// program runs; see [`StimulusScript`].
+    pub fn set_stimulus_script(&mut self, script: StimulusScript) -> &mut Self {
+        self.data.set_stimulus_script(Some(script));
+        self
+    }
+
+    /// Switches to the monochrome theme and turns on text markers for things
+    /// that are otherwise only shown with colour.
+    pub fn set_no_colour(&mut self) -> &mut Self {
+        markers::set_enabled(true);
+        self.set_theme("monochrome").expect("`monochrome` is a built-in theme")
+    }
+
+    /// Sets the themes that the cycle theme key binding cycles through and
+    /// switches back to the [startup theme](Theme::startup) (which is added
+    /// if it isn't one of them).
+    pub fn set_themes(&mut self, mut themes: Vec<Theme>) -> &mut Self {
+        let idx = Theme::find_startup(&mut themes);
+        colours::set_palette(themes[idx].palette.clone());
+
+        self.data.themes = themes;
+        self.data.current_theme = idx;
+        self
+    }
+
+    /// Switches to the theme with the given name. Names that don't match one
+    /// of our themes are treated as paths to theme files (except on the web).
+    pub fn set_theme(&mut self, name: &str) -> Res<&mut Self> {
+        let idx = match self.data.themes.iter().position(|t| t.name == name) {
+            Some(idx) => idx,
+            None => {
+                specialize! { [var: theme]
+                    desktop => { Theme::builtin_named(name).map(Ok).unwrap_or_else(|| Theme::load(name.as_ref()))? }
+                    web => { Theme::builtin_named(name).ok_or_else(|| anyhow::anyhow!("Unknown theme `{}`", name))? }
+                }
+
+                self.data.themes.push(theme);
+                self.data.themes.len() - 1
+            }
+        };
+
+        self.data.current_theme = idx;
+        colours::set_palette(self.data.themes[idx].palette.clone());
+
+        Ok(self)
+    }
+}
+
+// No files on wasm.
+#[cfg(not(target_arch = "wasm32"))]
+impl<'a, 'int, C: Control + ?Sized + 'a, I: InputSink + ?Sized + 'a, O: OutputSource + ?Sized + 'a> Tui<'a, 'int, C, I, O> {
+    /// Sets where the config is written back to when it changes (i.e. when
+    /// widgets are resized).
+    pub fn set_config_path(&mut self, path: PathBuf) -> &mut Self {
+        self.config_path = Some(path);
+        self
+    }
+
+    pub(in crate::tui) fn save_config_if_changed(&mut self) {
+        if let (true, Some(path)) = (self.data.config_changed, &self.config_path) {
+            match Config::save_panes(&self.data.config.panes, path) {
+                Ok(()) => self.data.config_changed = false,
+                Err(e) => log::warn!("Failed to save the config to `{}`: {:?}", path.display(), e),
+            }
+        }
+    }
 }
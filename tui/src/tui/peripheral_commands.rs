@@ -0,0 +1,434 @@
+//! The peripheral console's commands: parsing them, completing them, and
+//! describing them.
+//!
+//! Commands are words separated by spaces or colons (so `gpio 1 1` and the
+//! older `gpio:1:1` are the same command).
+
+use lc3_traits::peripherals::gpio::GpioPin;
+use lc3_traits::peripherals::adc::AdcPin;
+use lc3_traits::peripherals::pwm::PwmPin;
+use lc3_traits::peripherals::timers::TimerId;
+
+use super::signal::{Signal, DEFAULT_PERIOD};
+
+pub const GPIO_PINS: [GpioPin; 8] = [
+    GpioPin::G0, GpioPin::G1, GpioPin::G2, GpioPin::G3,
+    GpioPin::G4, GpioPin::G5, GpioPin::G6, GpioPin::G7,
+];
+pub const ADC_PINS: [AdcPin; 6] = [AdcPin::A0, AdcPin::A1, AdcPin::A2, AdcPin::A3, AdcPin::A4, AdcPin::A5];
+pub const PWM_PINS: [PwmPin; 2] = [PwmPin::P0, PwmPin::P1];
+pub const TIMERS: [TimerId; 2] = [TimerId::T0, TimerId::T1];
+
+/// The commands that aren't peripherals.
+const OTHER_COMMANDS: &[&str] = &["clear", "help", "history", "stimulus"];
+/// What can follow `stimulus`.
+const STIMULUS_COMMANDS: &[&str] = &["load", "off", "restart"];
+/// What can follow an ADC pin instead of a value.
+const ADC_SIGNALS: &[&str] = &["const", "csv", "noise", "off", "ramp", "sine", "square"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Peripheral {
+    Gpio,
+    Adc,
+    Pwm,
+    Timer,
+    Clock,
+}
+
+impl Peripheral {
+    pub const ALL: &'static [Peripheral] = &[
+        Peripheral::Gpio,
+        Peripheral::Adc,
+        Peripheral::Pwm,
+        Peripheral::Timer,
+        Peripheral::Clock,
+    ];
+
+    /// The command for the peripheral.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Peripheral::Gpio => "gpio",
+            Peripheral::Adc => "adc",
+            Peripheral::Pwm => "pwm",
+            Peripheral::Timer => "timer",
+            Peripheral::Clock => "clock",
+        }
+    }
+
+    /// What one of the peripheral's pins is called, i.e. "GPIO pin".
+    pub fn label(&self) -> &'static str {
+        match self {
+            Peripheral::Gpio => "GPIO pin",
+            Peripheral::Adc => "ADC pin",
+            Peripheral::Pwm => "PWM pin",
+            Peripheral::Timer => "timer",
+            Peripheral::Clock => "clock",
+        }
+    }
+
+    /// How many pins (or timers) there are; the clock doesn't have any.
+    pub fn count(&self) -> usize {
+        match self {
+            Peripheral::Gpio => GPIO_PINS.len(),
+            Peripheral::Adc => ADC_PINS.len(),
+            Peripheral::Pwm => PWM_PINS.len(),
+            Peripheral::Timer => TIMERS.len(),
+            Peripheral::Clock => 0,
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "gpio" | "g" => Peripheral::Gpio,
+            "adc" | "a" => Peripheral::Adc,
+            "pwm" | "p" => Peripheral::Pwm,
+            "timer" | "timers" | "t" => Peripheral::Timer,
+            "clock" | "clk" | "c" => Peripheral::Clock,
+            _ => return None,
+        })
+    }
+
+    fn usage(&self) -> &'static str {
+        match self {
+            Peripheral::Gpio => "gpio [pin] [0|1]",
+            Peripheral::Adc => "adc [pin] [value | const <value> | ramp|sine|square|noise [low] [high] [period] | csv <path> | off]",
+            Peripheral::Pwm => "pwm [pin]",
+            Peripheral::Timer => "timer [timer]",
+            Peripheral::Clock => "clock",
+        }
+    }
+
+    /// A longer description of the peripheral's commands, for `help <name>`.
+    pub fn help(&self) -> &'static [&'static str] {
+        match self {
+            Peripheral::Gpio => &[
+                "gpio            shows every GPIO pin",
+                "gpio 3          shows GPIO pin 3",
+                "gpio 3 1        sets GPIO pin 3 high (0, low, and off set it low)",
+                "Only pins the program has set up as inputs (or interrupts) can be set;",
+                "outputs are driven by the program.",
+            ],
+            Peripheral::Adc => &[
+                "adc             shows every ADC pin",
+                "adc 2           shows ADC pin 2",
+                "adc 2 200       sets ADC pin 2 to 200 (values go from 0 to 255; x80 is hex)",
+                "adc 2 sine 0 255 1s    has ADC pin 2 follow a sine wave (from 0 to 255, every",
+                "                second); ramp, square, and noise work the same way",
+                "adc 2 const 200 keeps ADC pin 2 at 200",
+                "adc 2 csv data.csv     replays samples from a CSV file: a value per line",
+                "                (100ms apart) or `time,value` lines (times in ms)",
+                "adc 2 off       stops ADC pin 2 following a signal",
+                "Only pins the program has enabled can be set; signals wait until they are.",
+            ],
+            Peripheral::Pwm => &[
+                "pwm             shows both PWM pins",
+                "pwm 1           shows PWM pin 1 (its period and duty cycle)",
+                "PWM pins are driven by the program, so they can't be set from here.",
+            ],
+            Peripheral::Timer => &[
+                "timer           shows both timers",
+                "timer 0         shows timer 0 (its mode and period)",
+                "Timers are driven by the program, so they can't be set from here.",
+            ],
+            Peripheral::Clock => &[
+                "clock           shows the clock (in milliseconds)",
+                "The clock is driven by the program, so it can't be set from here.",
+            ],
+        }
+    }
+}
+
+/// The overview `help` shows.
+pub const HELP: &[&str] = &[
+    "gpio [pin] [0|1]     shows GPIO pins, or sets an input pin",
+    "adc [pin] [value]    shows ADC pins, or sets an enabled pin (0 to 255)",
+    "adc <pin> <signal>   drives an ADC pin with a signal (see `help adc`)",
+    "pwm [pin]            shows PWM pins",
+    "timer [timer]        shows timers",
+    "clock                shows the clock",
+    "stimulus [load <path> | restart | off]",
+    "                     shows, loads, restarts, or stops a stimulus script",
+    "                     (i.e. a file of `at 500ms gpio 3 = 1` lines)",
+    "history              lists the commands entered so far",
+    "clear                clears this console",
+    "help [peripheral]    shows this (or more about a peripheral)",
+    "Words can be separated by spaces or colons (gpio:1:1). Tab completes",
+    "commands and pins; up and down go through the history.",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Command {
+    /// Describe the commands (or one peripheral's commands).
+    Help(Option<Peripheral>),
+    /// List the commands entered so far.
+    History,
+    /// Clear the console.
+    Clear,
+    /// Show one pin (or timer) or, without one, all of a peripheral's pins.
+    Show(Peripheral, Option<usize>),
+    /// Set a GPIO pin high or low.
+    SetGpio(usize, bool),
+    /// Set an ADC pin's value.
+    SetAdc(usize, u8),
+    /// Attach a signal generator to an ADC pin (or detach the pin's).
+    SetAdcSignal(usize, Option<Signal>),
+    /// Have an ADC pin follow samples from the CSV file at the given path.
+    LoadAdcSamples(usize, String),
+    /// Show the stimulus script's upcoming and fired events.
+    ShowStimulus,
+    /// Load the stimulus script at the given path.
+    LoadStimulus(String),
+    RestartStimulus,
+    StopStimulus,
+}
+
+fn is_separator(c: char) -> bool {
+    c.is_whitespace() || c == ':'
+}
+
+fn words(line: &str) -> impl Iterator<Item = &str> {
+    line.split(is_separator).filter(|w| !w.is_empty())
+}
+
+pub fn parse(line: &str) -> Result<Command, String> {
+    use Command::*;
+
+    let words: Vec<String> = words(line).map(|w| w.to_lowercase()).collect();
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+
+    let (first, rest) = match words.split_first() {
+        Some(split) => split,
+        None => return Err("Type a command (`help` lists them)".to_string()),
+    };
+
+    match (*first, rest) {
+        ("help", []) | ("?", []) => Ok(Help(None)),
+        ("help", [name]) | ("?", [name]) => match Peripheral::from_name(name) {
+            Some(p) => Ok(Help(Some(p))),
+            None => Err(format!("There's no peripheral called `{}`", name)),
+        },
+        ("history", []) => Ok(History),
+        ("clear", []) => Ok(Clear),
+        ("stimulus", []) => Ok(ShowStimulus),
+        ("stimulus", ["load", _, ..]) => Ok(LoadStimulus(after_words(line, 2).to_string())),
+        ("stimulus", ["restart"]) => Ok(RestartStimulus),
+        ("stimulus", ["off"]) => Ok(StopStimulus),
+        ("stimulus", _) => Err("Wrong arguments; usage: stimulus [load <path> | restart | off]".to_string()),
+        ("help", _) | ("?", _) | ("history", _) | ("clear", _) => {
+            Err(format!("Too many arguments; usage: {}", if *first == "clear" || *first == "history" { *first } else { "help [peripheral]" }))
+        },
+
+        (name, rest) => {
+            let p = match Peripheral::from_name(name) {
+                Some(p) => p,
+                None => return Err(format!("Unknown command `{}` (`help` lists the commands)", name)),
+            };
+
+            match (p, rest) {
+                (p, []) => Ok(Show(p, None)),
+                (Peripheral::Clock, _) => Err("The clock doesn't take any arguments".to_string()),
+                (p, [pin]) => Ok(Show(p, Some(pin_number(p, pin)?))),
+
+                (Peripheral::Gpio, [pin, level]) => Ok(SetGpio(pin_number(p, pin)?, parse_level(level)?)),
+                (Peripheral::Adc, [pin, kind, args @ ..]) if ADC_SIGNALS.contains(kind) => {
+                    let pin = pin_number(p, pin)?;
+                    let arg = |idx: usize, default: u8| args.get(idx).map(|a| parse_value(a)).unwrap_or(Ok(default));
+
+                    match (*kind, args) {
+                        ("off", []) => Ok(SetAdcSignal(pin, None)),
+                        ("const", [value]) => Ok(SetAdcSignal(pin, Some(Signal::Constant(parse_value(value)?)))),
+                        // Paths keep their case (and can have spaces or colons):
+                        ("csv", [_, ..]) => Ok(LoadAdcSamples(pin, after_words(line, 3).to_string())),
+                        ("noise", _) if args.len() <= 2 => Ok(SetAdcSignal(pin, Signal::waveform("noise", arg(0, 0)?, arg(1, 255)?, 0))),
+                        (wave, _) if wave != "noise" && Signal::WAVEFORMS.contains(&wave) && args.len() <= 3 => {
+                            let period = args.get(2).map(|a| parse_period(a)).unwrap_or(Ok(DEFAULT_PERIOD))?;
+                            Ok(SetAdcSignal(pin, Signal::waveform(wave, arg(0, 0)?, arg(1, 255)?, period)))
+                        },
+                        _ => Err(format!("Wrong arguments for `{}`; see `help adc`", kind)),
+                    }
+                },
+                (Peripheral::Adc, [pin, value]) => Ok(SetAdc(pin_number(p, pin)?, parse_value(value)?)),
+                (Peripheral::Pwm, [_, _]) => Err("PWM pins are driven by the program; they can't be set from here".to_string()),
+                (Peripheral::Timer, [_, _]) => Err("Timers are driven by the program; they can't be set from here".to_string()),
+
+                (p, _) => Err(format!("Too many arguments; usage: {}", p.usage())),
+            }
+        },
+    }
+}
+
+// Takes `3`, or `g3` for GPIO (`a3` for ADC, etc.).
+pub(in crate) fn pin_number(p: Peripheral, word: &str) -> Result<usize, String> {
+    let prefix = &p.name()[..1];
+    let num = word.strip_prefix(prefix).unwrap_or(word);
+
+    match num.parse::<usize>() {
+        Ok(n) if n < p.count() => Ok(n),
+        Ok(n) => Err(format!("There's no {} {} (they go from 0 to {})", p.label(), n, p.count() - 1)),
+        Err(_) => Err(format!("`{}` isn't a {} (they go from 0 to {})", word, p.label(), p.count() - 1)),
+    }
+}
+
+pub(in crate) fn parse_level(word: &str) -> Result<bool, String> {
+    match word {
+        "1" | "high" | "on" | "true" => Ok(true),
+        "0" | "low" | "off" | "false" => Ok(false),
+        _ => Err(format!("`{}` isn't a level; use 1 (high) or 0 (low)", word)),
+    }
+}
+
+// Takes milliseconds, optionally with a unit (`500ms`, `2s`).
+pub(in crate) fn parse_period(word: &str) -> Result<u64, String> {
+    let parsed = match word.strip_suffix("ms") {
+        Some(ms) => ms.parse::<u64>(),
+        None => match word.strip_suffix('s') {
+            Some(s) => s.parse::<u64>().map(|s| s.saturating_mul(1000)),
+            None => word.parse::<u64>(),
+        },
+    };
+
+    match parsed {
+        Ok(p) if p > 0 => Ok(p),
+        _ => Err(format!("`{}` isn't a period (i.e. 500ms or 2s)", word)),
+    }
+}
+
+// What's left of `line` after its first `n` words.
+fn after_words(line: &str, n: usize) -> &str {
+    let mut rest = line;
+    for _ in 0..n {
+        rest = rest.trim_start_matches(is_separator);
+        rest = &rest[rest.find(is_separator).unwrap_or(rest.len())..];
+    }
+
+    rest.trim_start_matches(is_separator).trim_end()
+}
+
+// Takes decimal or (with an `x` or `0x` prefix) hex.
+pub(in crate) fn parse_value(word: &str) -> Result<u8, String> {
+    let parsed = match word.strip_prefix("0x").or_else(|| word.strip_prefix('x')) {
+        Some(hex) => u32::from_str_radix(hex, 16),
+        None => word.parse::<u32>(),
+    };
+
+    match parsed {
+        Ok(v) if v <= u8::max_value() as u32 => Ok(v as u8),
+        Ok(_) => Err(format!("`{}` is too big; ADC values go from 0 to 255", word)),
+        Err(_) => Err(format!("`{}` isn't a number", word)),
+    }
+}
+
+/// Completes the last word in `line`.
+///
+/// Returns the line with the word extended as far as the candidates agree
+/// (plus a space if there's only one) and the candidates.
+pub fn complete(line: &str) -> (String, Vec<String>) {
+    let start = line.rfind(is_separator).map(|i| i + 1).unwrap_or(0);
+    let (before, word) = line.split_at(start);
+    let prior: Vec<String> = words(before).map(|w| w.to_lowercase()).collect();
+
+    let options: Vec<String> = match prior.as_slice() {
+        [] => {
+            let mut names: Vec<&str> = Peripheral::ALL.iter().map(Peripheral::name).collect();
+            names.extend(OTHER_COMMANDS);
+            names.sort();
+            names.into_iter().map(String::from).collect()
+        },
+        [cmd] if cmd == "help" || cmd == "?" => Peripheral::ALL.iter().map(|p| p.name().to_string()).collect(),
+        [cmd] if cmd == "stimulus" => STIMULUS_COMMANDS.iter().map(|c| c.to_string()).collect(),
+        [cmd] => match Peripheral::from_name(cmd) {
+            Some(p) => (0..p.count()).map(|n| n.to_string()).collect(),
+            None => Vec::new(),
+        },
+        [cmd, _] => match Peripheral::from_name(cmd) {
+            Some(Peripheral::Gpio) => vec!["0".to_string(), "1".to_string()],
+            Some(Peripheral::Adc) => ADC_SIGNALS.iter().map(|s| s.to_string()).collect(),
+            _ => Vec::new(),
+        },
+        _ => Vec::new(),
+    };
+
+    let lower = word.to_lowercase();
+    let candidates: Vec<String> = options.into_iter().filter(|o| o.starts_with(&lower)).collect();
+
+    let completed = match candidates.as_slice() {
+        [] => line.to_string(),
+        [only] => format!("{}{} ", before, only),
+        [first, rest @ ..] => {
+            let common = rest.iter().fold(first.len(), |len, c| {
+                first.chars().zip(c.chars()).take_while(|(a, b)| a == b).count().min(len)
+            });
+
+            if common > word.len() { format!("{}{}", before, &first[..common]) } else { line.to_string() }
+        },
+    };
+
+    (completed, candidates)
+}
+
+#[cfg(test)]
+mod peripheral_commands_tests {
+    use super::*;
+    use pretty_assertions::assert_eq as eq;
+
+    #[test]
+    fn parsing() {
+        eq!(parse("gpio 1 1"), Ok(Command::SetGpio(1, true)));
+        eq!(parse("gpio:1:1"), Ok(Command::SetGpio(1, true)));
+        eq!(parse("  GPIO  g7 low "), Ok(Command::SetGpio(7, false)));
+        eq!(parse("adc:0:200"), Ok(Command::SetAdc(0, 200)));
+        eq!(parse("adc 5 x80"), Ok(Command::SetAdc(5, 0x80)));
+        eq!(parse("timer"), Ok(Command::Show(Peripheral::Timer, None)));
+        eq!(parse("pwm 1"), Ok(Command::Show(Peripheral::Pwm, Some(1))));
+        eq!(parse("clock"), Ok(Command::Show(Peripheral::Clock, None)));
+        eq!(parse("help adc"), Ok(Command::Help(Some(Peripheral::Adc))));
+        eq!(parse("history"), Ok(Command::History));
+        eq!(parse("stimulus"), Ok(Command::ShowStimulus));
+        eq!(parse("stimulus load labs/Blink 2.stim"), Ok(Command::LoadStimulus("labs/Blink 2.stim".to_string())));
+        eq!(parse("stimulus off"), Ok(Command::StopStimulus));
+        assert!(parse("stimulus load").is_err());
+    }
+
+    #[test]
+    fn adc_signals() {
+        eq!(parse("adc 1 off"), Ok(Command::SetAdcSignal(1, None)));
+        eq!(parse("adc 1 const x10"), Ok(Command::SetAdcSignal(1, Some(Signal::Constant(16)))));
+        eq!(parse("adc 1 sine"), Ok(Command::SetAdcSignal(1, Signal::waveform("sine", 0, 255, DEFAULT_PERIOD))));
+        eq!(parse("adc:1:square:10:20:2s"), Ok(Command::SetAdcSignal(1, Signal::waveform("square", 10, 20, 2000))));
+        eq!(parse("adc 1 noise 5"), Ok(Command::SetAdcSignal(1, Signal::waveform("noise", 5, 255, 0))));
+        eq!(parse("adc a1 csv C:\\Lab Data\\In.csv "), Ok(Command::LoadAdcSamples(1, "C:\\Lab Data\\In.csv".to_string())));
+
+        assert!(parse("adc 1 csv").is_err());
+        assert!(parse("adc 1 ramp 0 255 0ms").is_err());
+        assert!(parse("adc 1 noise 0 255 100").is_err());
+        assert!(parse("adc 1 off 2").is_err());
+    }
+
+    #[test]
+    fn errors() {
+        assert!(parse("").is_err());
+        eq!(parse("gpoi 1 1"), Err("Unknown command `gpoi` (`help` lists the commands)".to_string()));
+        eq!(parse("gpio 9 1"), Err("There's no GPIO pin 9 (they go from 0 to 7)".to_string()));
+        eq!(parse("gpio x 1"), Err("`x` isn't a GPIO pin (they go from 0 to 7)".to_string()));
+        eq!(parse("gpio 1 2"), Err("`2` isn't a level; use 1 (high) or 0 (low)".to_string()));
+        eq!(parse("adc 0 256"), Err("`256` is too big; ADC values go from 0 to 255".to_string()));
+        eq!(parse("gpio 0 1 2"), Err("Too many arguments; usage: gpio [pin] [0|1]".to_string()));
+        assert!(parse("adc 0 1 2").is_err());
+        assert!(parse("pwm 0 100").is_err());
+        assert!(parse("clock 1").is_err());
+    }
+
+    #[test]
+    fn completions() {
+        eq!(complete("g"), ("gpio ".to_string(), vec!["gpio".to_string()]));
+        eq!(complete("c").1, vec!["clear", "clock"]);
+        eq!(complete("c").0, "cl");
+        eq!(complete("h").0, "h");
+        eq!(complete("adc ").1.len(), 6);
+        eq!(complete("gpio:1:").1, vec!["0", "1"]);
+        eq!(complete("help t").0, "help timer ");
+        eq!(complete("adc 1 s").1, vec!["sine", "square"]);
+        eq!(complete("adc 1 2 3").1, Vec::<String>::new());
+        eq!(complete("stimulus r").0, "stimulus restart ");
+    }
+}
@@ -0,0 +1,111 @@
+//! A record of what the peripherals have been up to, for the logic analyser
+//! (see [`Analyser`](crate::widgets::Analyser)).
+//!
+//! The peripherals are sampled on every update but only changes are kept:
+//! the value of a channel at some time is the value from the last change
+//! before it.
+
+use std::collections::VecDeque;
+
+/// How many changes we hold on to.
+pub const TRACE_LEN: usize = 20_000;
+
+/// Everything the logic analyser shows, as of one update.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Values {
+    /// The pins' levels; `None` when they're disabled.
+    pub gpio: [Option<bool>; 8],
+    /// The pins' values; `None` when they're disabled.
+    pub adc: [Option<u8>; 6],
+    /// The pins' duty cycles; `None` when they're disabled.
+    pub pwm: [Option<u8>; 2],
+    /// The timers' periods (in milliseconds); `None` when they're disabled.
+    pub timers: [Option<u64>; 2],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Sample {
+    /// When (see `now`).
+    pub at: u64,
+    pub values: Values,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct PeripheralTrace {
+    /// Changes, oldest first.
+    samples: VecDeque<Sample>,
+    /// When we last sampled (which may be after the last change).
+    latest: u64,
+}
+
+impl PeripheralTrace {
+    pub fn record(&mut self, at: u64, values: Values) {
+        self.latest = at;
+        if self.samples.back().map(|s| s.values == values).unwrap_or(false) {
+            return;
+        }
+
+        if self.samples.len() == TRACE_LEN {
+            let _ = self.samples.pop_front();
+        }
+        self.samples.push_back(Sample { at, values });
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    /// When the trace starts and ends; `None` if it's empty.
+    pub fn span(&self) -> Option<(u64, u64)> {
+        self.samples.front().map(|s| (s.at, self.latest))
+    }
+
+    /// What everything was at time `t`; `None` if that's outside the trace.
+    pub fn at(&self, t: u64) -> Option<&Values> {
+        let (start, end) = self.span()?;
+        if t < start || t > end {
+            return None;
+        }
+
+        let idx = self.samples.partition_point(|s| s.at <= t);
+        Some(&self.samples[idx - 1].values)
+    }
+
+    /// The changes made after `from`, up to (and including) `to`.
+    pub fn changes(&self, from: u64, to: u64) -> impl Iterator<Item = &Sample> + '_ {
+        let start = self.samples.partition_point(|s| s.at <= from);
+        self.samples.range(start..).take_while(move |s| s.at <= to)
+    }
+}
+
+#[cfg(test)]
+mod peripheral_trace_tests {
+    use super::*;
+    use pretty_assertions::assert_eq as eq;
+
+    fn gpio0(level: bool) -> Values {
+        let mut values = Values::default();
+        values.gpio[0] = Some(level);
+        values
+    }
+
+    #[test]
+    fn only_changes_are_kept() {
+        let mut trace = PeripheralTrace::default();
+        eq!(trace.at(0), None);
+
+        trace.record(10, gpio0(false));
+        trace.record(20, gpio0(false));
+        trace.record(30, gpio0(true));
+        trace.record(40, gpio0(true));
+
+        eq!(trace.span(), Some((10, 40)));
+        eq!(trace.at(9), None);
+        eq!(trace.at(25), Some(&gpio0(false)));
+        eq!(trace.at(30), Some(&gpio0(true)));
+        eq!(trace.at(41), None);
+
+        eq!(trace.changes(10, 40).map(|s| s.at).collect::<Vec<_>>(), vec![30]);
+        eq!(trace.changes(0, 29).map(|s| s.at).collect::<Vec<_>>(), vec![10]);
+    }
+}
@@ -0,0 +1,331 @@
+//! Driving the peripherals' inputs (and reading their pins) from the TUI.
+//!
+//! Writes go through the shims, so they need [`TuiData::shims`]; they're
+//! checked against the states the program has put the pins in first, so users
+//! are told why a write didn't happen.
+
+use super::TuiData;
+use super::program_log::Level;
+use super::interrupts::Source;
+use super::peripheral_commands::{ADC_PINS, GPIO_PINS, PWM_PINS, TIMERS};
+use super::peripheral_trace::Values;
+use super::signal::{Signal, SignalGenerator};
+use super::stimulus::{Action, StimulusScript};
+use super::time::now;
+use crate::colours::c;
+
+use lc3_application_support::io_peripherals::{InputSink, OutputSource};
+use lc3_isa::Word;
+use lc3_traits::control::{Control, State};
+use lc3_traits::peripherals::adc::AdcState;
+use lc3_traits::peripherals::gpio::{Gpio, GpioState};
+use lc3_traits::peripherals::pwm::PwmState;
+use lc3_traits::peripherals::timers::{TimerMode, TimerState, Timers};
+
+use std::path::Path;
+use std::sync::RwLock;
+
+const NO_SHIMS: &str = "The peripherals can't be set here (the simulator's peripherals aren't available)";
+
+impl<'a, 'int, C, I, O> TuiData<'a, 'int, C, I, O>
+where
+    C: Control + ?Sized + 'a,
+    I: InputSink + ?Sized + 'a,
+    O: OutputSource + ?Sized + 'a,
+{
+    /// What GPIO pin `idx` is at (outputs included); `None` if the pin is
+    /// disabled or can't be read.
+    pub fn gpio_level(&self, idx: usize) -> Option<bool> {
+        let pin = GPIO_PINS[idx];
+
+        match self.sim.get_gpio_states()[pin] {
+            GpioState::Disabled => None,
+            GpioState::Output => self.shims.as_ref().and_then(|s| RwLock::read(&s.gpio).unwrap().get_pin(pin)),
+            GpioState::Input | GpioState::Interrupt => match self.sim.get_gpio_readings()[pin] {
+                Ok(level) => Some(level),
+                Err(_) => None,
+            },
+        }
+    }
+
+    /// Drives GPIO pin `idx`, which the program must have set up as an input
+    /// (or an interrupt).
+    pub fn set_gpio_input(&mut self, idx: usize, level: bool) -> Result<(), String> {
+        self.write_gpio(idx, &[level])?;
+
+        self.log(format!("[GPIO] Pin {} set to {}\n", idx, level as u8), c!(Success));
+        Ok(())
+    }
+
+    /// Gives GPIO pin `idx` a rising edge (which is what fires interrupts),
+    /// leaving it at the level it was at.
+    pub fn pulse_gpio_input(&mut self, idx: usize) -> Result<(), String> {
+        let levels = self.pulse_levels(idx);
+        self.write_gpio(idx, &levels)?;
+
+        self.log(format!("[GPIO] Pulsed pin {}\n", idx), c!(Success));
+        Ok(())
+    }
+
+    fn pulse_levels(&self, idx: usize) -> [bool; 2] {
+        match self.gpio_level(idx) {
+            Some(true) => [false, true],
+            Some(false) | None => [true, false],
+        }
+    }
+
+    fn write_gpio(&mut self, idx: usize, levels: &[bool]) -> Result<(), String> {
+        let pin = GPIO_PINS[idx];
+        match self.sim.get_gpio_states()[pin] {
+            GpioState::Input | GpioState::Interrupt => {},
+            GpioState::Output => return Err(format!(
+                "GPIO pin {} is an output (it's driven by the program); only input and interrupt pins can be set", idx
+            )),
+            GpioState::Disabled => return Err(format!(
+                "GPIO pin {} is disabled; the program has to make it an input before it can be set", idx
+            )),
+        }
+
+        let shims = self.shims.as_ref().ok_or_else(|| NO_SHIMS.to_string())?;
+        let mut gpio = RwLock::write(&shims.gpio).unwrap();
+        for level in levels {
+            let _ = gpio.set_pin(pin, *level);
+        }
+
+        Ok(())
+    }
+
+    /// Sets ADC pin `idx`, which the program must have enabled; this detaches
+    /// the pin's signal generator, if it has one.
+    pub fn set_adc_input(&mut self, idx: usize, value: u8) -> Result<(), String> {
+        self.write_adc(idx, value)?;
+        self.adc_signals[idx] = None;
+
+        Ok(())
+    }
+
+    /// Attaches a signal generator to ADC pin `idx` or, with `None`, detaches
+    /// the pin's generator.
+    pub fn set_adc_signal(&mut self, idx: usize, signal: Option<Signal>) {
+        match &signal {
+            Some(signal) => self.log(format!("[ADC] Pin {} now follows: {}\n", idx, signal), c!(Success)),
+            None if self.adc_signals[idx].is_some() => self.log(format!("[ADC] Pin {} no longer follows a signal\n", idx), c!(Success)),
+            None => {},
+        }
+
+        self.adc_signals[idx] = signal.map(SignalGenerator::new);
+    }
+
+    /// The signal ADC pin `idx` is following, if it's following one.
+    pub fn adc_signal(&self, idx: usize) -> Option<&Signal> {
+        self.adc_signals[idx].as_ref().map(|g| &g.signal)
+    }
+
+    /// Has ADC pin `idx` follow samples from a CSV file (see
+    /// [`Signal::samples_from_csv`]).
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load_adc_samples(&mut self, idx: usize, path: &Path) -> Result<(), String> {
+        let signal = Signal::load_samples(path)?;
+        self.set_adc_signal(idx, Some(signal));
+
+        Ok(())
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn load_adc_samples(&mut self, _idx: usize, _path: &Path) -> Result<(), String> {
+        Err("Loading samples from files isn't supported here".to_string())
+    }
+
+    /// Sets every ADC pin that has a signal generator attached to the
+    /// generator's current value; this happens on every update.
+    pub(in crate) fn drive_adc(&mut self) {
+        if self.adc_signals.iter().all(Option::is_none) {
+            return;
+        }
+
+        for idx in 0..ADC_PINS.len() {
+            let value = match self.adc_signals[idx].as_mut() {
+                Some(generator) => generator.sample(),
+                None => continue,
+            };
+
+            // Pins the program hasn't enabled (yet) are left alone:
+            let _ = self.write_adc(idx, value);
+        }
+    }
+
+    fn write_adc(&mut self, idx: usize, value: u8) -> Result<(), String> {
+        let pin = ADC_PINS[idx];
+        if let AdcState::Disabled = self.sim.get_adc_states()[pin] {
+            return Err(format!("ADC pin {} is disabled; the program has to enable it before it can be set", idx));
+        }
+
+        let shims = self.shims.as_ref().ok_or_else(|| NO_SHIMS.to_string())?;
+        let _ = RwLock::write(&shims.adc).unwrap().set_value(pin, value);
+
+        Ok(())
+    }
+
+    /// Catches the timers' histories up; this happens on every update.
+    pub(in crate) fn track_timers(&mut self) {
+        let (states, modes) = (self.sim.get_timer_states(), self.sim.get_timer_modes());
+        let (now, pc) = (now(), self.sim.get_pc());
+        let running = self.sim.get_state() == State::RunningUntilEvent;
+
+        for (idx, id) in TIMERS.iter().enumerate() {
+            let config = match states[*id] {
+                TimerState::Disabled => None,
+                TimerState::WithPeriod(period) => {
+                    let period = (u16::from(period) as u64).max(1);
+                    Some((period, matches!(modes[*id], TimerMode::Repeated)))
+                },
+            };
+
+            self.timer_history[idx].observe(config, now, pc, running);
+        }
+    }
+
+    /// Adds the peripherals' current state to the trace; this happens on
+    /// every update (after [`TuiData::track_timers`]).
+    pub(in crate) fn sample_peripherals(&mut self) {
+        let mut values = Values::default();
+
+        for idx in 0..GPIO_PINS.len() {
+            values.gpio[idx] = self.gpio_level(idx);
+        }
+
+        let (adc_states, adc_readings) = (self.sim.get_adc_states(), self.sim.get_adc_readings());
+        for (idx, pin) in ADC_PINS.iter().enumerate() {
+            values.adc[idx] = match (matches!(adc_states[*pin], AdcState::Enabled), &adc_readings[*pin]) {
+                (true, Ok(value)) => Some(*value),
+                _ => None,
+            };
+        }
+
+        let (pwm_states, pwm_config) = (self.sim.get_pwm_states(), self.sim.get_pwm_config());
+        for (idx, pin) in PWM_PINS.iter().enumerate() {
+            values.pwm[idx] = match pwm_states[*pin] {
+                PwmState::Enabled(_) => Some(pwm_config[*pin]),
+                PwmState::Disabled => None,
+            };
+        }
+
+        for idx in 0..TIMERS.len() {
+            values.timers[idx] = self.timer_history[idx].config().map(|(period, _)| period);
+        }
+
+        self.trace.record(now(), values);
+    }
+
+    /// The peripherals that can raise interrupts (the GPIO pins and the
+    /// timers), whether they're set up to and whether they have one waiting.
+    pub(in crate) fn interrupt_sources(&self) -> Vec<Source> {
+        let (gpio_states, timer_states) = (self.sim.get_gpio_states(), self.sim.get_timer_states());
+
+        let gpio = GPIO_PINS.iter().enumerate().map(|(idx, pin)| Source {
+            name: format!("GPIO {}", idx),
+            enabled: matches!(gpio_states[*pin], GpioState::Interrupt),
+            pending: self.shims.as_ref().map(|s| RwLock::read(&s.gpio).unwrap().interrupt_occurred(*pin)),
+        });
+
+        let timers = TIMERS.iter().enumerate().map(|(idx, id)| Source {
+            name: format!("Timer {}", idx),
+            enabled: !matches!(timer_states[*id], TimerState::Disabled),
+            pending: self.shims.as_ref().map(|s| RwLock::read(&s.timers).unwrap().interrupt_occurred(*id)),
+        });
+
+        gpio.chain(timers).collect()
+    }
+
+    /// Replaces the stimulus script (or removes it, when given `None`); the
+    /// new script starts from the beginning.
+    pub(in crate) fn set_stimulus_script(&mut self, mut script: Option<StimulusScript>) {
+        let msg = match &mut script {
+            Some(s) => {
+                s.restart();
+                format!("[Stimulus] Loaded `{}` ({} events).\n", s.name, s.events.len())
+            },
+            None if self.stimulus.is_some() => "[Stimulus] Removed the stimulus script.\n".to_string(),
+            None => return,
+        };
+
+        self.log(msg, c!(Success));
+        self.stimulus = script;
+    }
+
+    /// Loads (and starts) the stimulus script at `path`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load_stimulus_script(&mut self, path: &Path) -> Result<(), String> {
+        let script = StimulusScript::load(path).map_err(|e| format!("{:#}", e))?;
+        self.set_stimulus_script(Some(script));
+
+        Ok(())
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn load_stimulus_script(&mut self, _path: &Path) -> Result<(), String> {
+        Err("Loading stimulus scripts from files isn't supported here".to_string())
+    }
+
+    /// Moves the stimulus script along (with the clock and the steps taken
+    /// since the last update) and makes the changes that are due; this
+    /// happens on every update.
+    ///
+    /// Changes go through the shims, like changes made from the peripheral
+    /// widgets, but aren't logged (they'd flood the log); only the first
+    /// failure of each event is.
+    pub(in crate) fn run_stimulus(&mut self) {
+        let clock: Word = self.sim.get_clock();
+        let script = match self.stimulus.as_mut() {
+            Some(script) => script,
+            None => return,
+        };
+
+        // The clock wraps around:
+        let millis = script.last_clock.map(|last| clock.wrapping_sub(last) as u64).unwrap_or(0);
+        script.last_clock = Some(clock);
+        let steps = std::mem::replace(&mut script.pending_steps, 0);
+
+        for idx in script.advance(millis, steps) {
+            let event = self.stimulus.as_ref().unwrap().events[idx].clone();
+            let res = self.apply_stimulus(event.action);
+
+            if let Err(err) = &res {
+                if !event.failed {
+                    let msg = format!("[Stimulus] Line {} (`{}`): {}\n", event.line, event.action, err);
+                    self.log_at(Level::Warn, msg, c!(InvalidInput));
+                }
+            }
+
+            self.stimulus.as_mut().unwrap().record(idx, res.err());
+        }
+
+        if self.sim.get_state() == State::RunningUntilEvent {
+            let script = self.stimulus.as_mut().unwrap();
+            let missed: Vec<_> = script.miss_steps().into_iter().map(|idx| script.events[idx].clone()).collect();
+
+            for event in missed {
+                let msg = format!(
+                    "[Stimulus] Line {} (`{}`): steps only count while single stepping, not while running\n",
+                    event.line, event.action,
+                );
+                self.log_at(Level::Warn, msg, c!(InvalidInput));
+            }
+        }
+    }
+
+    fn apply_stimulus(&mut self, action: Action) -> Result<(), String> {
+        match action {
+            Action::SetGpio(idx, level) => self.write_gpio(idx, &[level]),
+            Action::ToggleGpio(idx) => {
+                let level = self.gpio_level(idx).unwrap_or(false);
+                self.write_gpio(idx, &[!level])
+            },
+            Action::PulseGpio(idx) => {
+                let levels = self.pulse_levels(idx);
+                self.write_gpio(idx, &levels)
+            },
+            Action::SetAdc(idx, value) => self.set_adc_input(idx, value),
+        }
+    }
+}
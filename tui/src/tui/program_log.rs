@@ -0,0 +1,176 @@
+//! The program log (what the Log tab shows): messages from the TUI, each with
+//! a source, a level, when it was logged and the PC at the time.
+
+use super::bounded::{dropped_marker, LogLine};
+
+use lc3_isa::Addr;
+
+use tui::widgets::Text as TuiText;
+use tui::style::{Modifier, Style};
+
+use std::fmt::{self, Display};
+
+/// How important a log entry is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    /// Chatter, i.e. every event the machine reports.
+    Debug,
+    Info,
+    /// Things that didn't go as asked, i.e. invalid input.
+    Warn,
+    Error,
+}
+
+impl Level {
+    pub const ALL: &'static [Level] = &[Level::Debug, Level::Info, Level::Warn, Level::Error];
+
+    /// The next level up, wrapping around to the lowest level.
+    pub fn next(self) -> Self {
+        let idx = Self::ALL.iter().position(|l| *l == self).unwrap();
+        Self::ALL[(idx + 1) % Self::ALL.len()]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Level::Debug => "debug",
+            Level::Info => "info",
+            Level::Warn => "warn",
+            Level::Error => "error",
+        }
+    }
+}
+
+impl Display for Level {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "{}", self.name())
+    }
+}
+
+/// An entry in the program log.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogEntry<'a> {
+    /// What logged this: the tag the message started with, i.e. `Load` for
+    /// `"[Load] ..."`.
+    pub source: Option<String>,
+    pub level: Level,
+    /// Milliseconds since the TUI started; `None` for the marker that stands
+    /// in for dropped entries.
+    pub at: Option<u64>,
+    pub pc: Option<Addr>,
+    /// The message (without the source tag or a trailing newline), styled.
+    pub text: TuiText<'a>,
+}
+
+impl<'a> LogEntry<'a> {
+    /// Makes an entry out of a message, taking its source from the `[tag]` at
+    /// the start of the message (if there is one).
+    pub fn new(level: Level, at: u64, pc: Addr, message: &str, style: Option<Style>) -> Self {
+        let (source, message) = split_source(message);
+        let message = message.trim_end_matches('\n').to_string();
+
+        Self {
+            source: source.map(String::from),
+            level,
+            at: Some(at),
+            pc: Some(pc),
+            text: match style {
+                Some(style) => TuiText::styled(message, style),
+                None => TuiText::raw(message),
+            },
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match &self.text {
+            TuiText::Raw(s) | TuiText::Styled(s, _) => s,
+        }
+    }
+
+    /// Whether the source or the message contain `needle` (which is expected
+    /// to be lowercase).
+    pub fn contains(&self, needle: &str) -> bool {
+        self.message().to_lowercase().contains(needle)
+            || self.source.as_ref().map(|s| s.to_lowercase().contains(needle)).unwrap_or(false)
+    }
+
+    /// The part of the entry that comes before the message, i.e.
+    /// `"+12.345s x3000 warn  [Addr] "`.
+    pub fn prefix(&self) -> String {
+        let at = match self.at {
+            Some(at) => format!("+{}.{:03}s", at / 1000, at % 1000),
+            None => String::new(),
+        };
+        let pc = self.pc.map(|pc| format!("x{:04X}", pc)).unwrap_or_default();
+        let source = self.source.as_ref().map(|s| format!("[{}] ", s)).unwrap_or_default();
+
+        format!("{:>10} {:5} {:5} {}", at, pc, self.level.name(), source)
+    }
+}
+
+impl<'a> Display for LogEntry<'a> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "{}{}", self.prefix(), self.message())
+    }
+}
+
+impl<'a> LogLine for LogEntry<'a> {
+    fn dropped_marker(dropped: usize) -> Self {
+        Self {
+            source: None,
+            level: Level::Warn,
+            at: None,
+            pc: None,
+            text: TuiText::styled(dropped_marker(dropped), Style::default().modifier(Modifier::DIM)),
+        }
+    }
+
+    fn to_spilled(&self) -> String {
+        self.to_string()
+    }
+}
+
+/// Splits a message into its `[tag]` (without the brackets) and the rest.
+pub fn split_source(message: &str) -> (Option<&str>, &str) {
+    if message.starts_with('[') {
+        if let Some(end) = message.find(']') {
+            let tag = &message[1..end];
+            if !tag.is_empty() && !tag.contains(char::is_whitespace) {
+                return (Some(tag), message[end + 1..].trim_start_matches(' '));
+            }
+        }
+    }
+
+    (None, message)
+}
+
+#[cfg(test)]
+mod program_log_tests {
+    use super::*;
+    use pretty_assertions::assert_eq as eq;
+
+    #[test]
+    fn sources() {
+        eq!(split_source("[Load] Loaded!\n"), (Some("Load"), "Loaded!\n"));
+        eq!(split_source("[modeline]Resetting"), (Some("modeline"), "Resetting"));
+        eq!(split_source("[not a tag] hmm"), (None, "[not a tag] hmm"));
+        eq!(split_source("no tag"), (None, "no tag"));
+    }
+
+    #[test]
+    fn formatting() {
+        let entry = LogEntry::new(Level::Warn, 12_345, 0x3000, "[Addr] Invalid hex value: xyz\n", None);
+
+        eq!(entry.source.as_deref(), Some("Addr"));
+        eq!(entry.message(), "Invalid hex value: xyz");
+        eq!(entry.to_string(), "  +12.345s x3000 warn  [Addr] Invalid hex value: xyz");
+        assert!(entry.contains("addr"));
+        assert!(!entry.contains("load"));
+    }
+
+    #[test]
+    fn levels() {
+        eq!(Level::Debug.next(), Level::Info);
+        eq!(Level::Error.next(), Level::Debug);
+        assert!(Level::Warn > Level::Info);
+    }
+}
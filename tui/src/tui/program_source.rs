@@ -6,7 +6,7 @@ use lc3_assembler::{
     lexer::Lexer,
     parser::{parse, LeniencyLevel},
 };
-use lc3_isa::{ADDR_SPACE_SIZE_IN_WORDS, ADDR_SPACE_SIZE_IN_BYTES, util::MemoryDump};
+use lc3_isa::{Addr, Word, ADDR_SPACE_SIZE_IN_WORDS, ADDR_SPACE_SIZE_IN_BYTES, util::MemoryDump};
 #[cfg(not(target_arch = "wasm32"))]
 use lc3_shims::memory::FileBackedMemoryShim;
 use lc3_traits::control::metadata::{
@@ -161,6 +161,24 @@ pub(in crate) fn assemble_mem_dump_str(src: &str, path: Option<&str>, with_os: b
     Ok(assemble(cst.objects, background))  // TODO: can still fail. fix in assembler.
 }
 
+/// Assembles one instruction as if it were at `addr` (for changing memory in
+/// place); errors are boiled down to their first line.
+pub(in crate) fn assemble_instruction(inst: &str, addr: Addr) -> Result<Word, String> {
+    let src = format!(".ORIG x{:04X}\n{}\n.END\n", addr, inst);
+
+    let dump = assemble_mem_dump_str(&src, None, false).map_err(|err| {
+        let reason = err.lines()
+            .map(|l| l.trim_start_matches('|').trim())
+            .find(|l| !l.is_empty())
+            .unwrap_or("the assembler rejected it")
+            .to_string();
+
+        format!("`{}` didn't assemble: {}", inst, reason)
+    })?;
+
+    Ok(dump[addr as usize])
+}
+
 // A bad hack..
 //
 // I think this is the approach we'll have to go with but we should do things to make
@@ -201,6 +219,18 @@ impl ProgramSource {
         }
     }
 
+    /// The program's assembly, if it's assembly we can get at without
+    /// fetching anything.
+    pub(in crate) fn assembly(&self) -> Option<String> {
+        use ProgramSource::*;
+        match self {
+            #[cfg(not(target_arch = "wasm32"))]
+            FilePath(p) if file_requires_assembly(p) => fs::read_to_string(p).ok(),
+            ImmediateSource(src) => Some(src.clone()),
+            _ => None,
+        }
+    }
+
     pub(in crate) fn long_ident(&self) -> LongIdentifier {
         use ProgramSource::*;
 
@@ -0,0 +1,129 @@
+//! A line of text that a widget asks for (a search, a path, a new value …)
+//! and the status line it's shown in.
+
+use crate::colours::c;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use tui::buffer::Buffer;
+use tui::layout::Rect;
+use tui::style::{Color as Colour, Style};
+use tui::widgets::{Paragraph, Text as TuiText, Widget as TuiWidget};
+
+/// Text being typed in, and what it's for (when a widget asks for more than
+/// one thing).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Prompt<K = ()> {
+    pub kind: K,
+    pub text: String,
+}
+
+/// What a key did to a [`Prompt`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Outcome<K> {
+    /// The text changed (or the key was ignored).
+    Typing,
+    /// Enter was pressed; the prompt is gone.
+    Entered(K, String),
+    /// Esc was pressed; the prompt is gone.
+    Cancelled,
+}
+
+impl<K> Prompt<K> {
+    pub fn new(kind: K) -> Self {
+        Self::with_text(kind, String::new())
+    }
+
+    pub fn with_text(kind: K, text: String) -> Self {
+        Self { kind, text }
+    }
+}
+
+/// Handles a key for `prompt` (which should be `Some`): characters and
+/// backspace edit the text and Enter or Esc take the prompt away.
+pub fn key<K>(prompt: &mut Option<Prompt<K>>, key: KeyEvent) -> Outcome<K> {
+    let p = match prompt.as_mut() {
+        Some(p) => p,
+        None => return Outcome::Typing,
+    };
+
+    match key {
+        KeyEvent { code: KeyCode::Char(c), modifiers } if modifiers == KeyModifiers::empty() || modifiers == KeyModifiers::SHIFT => {
+            p.text.push(c)
+        },
+        KeyEvent { code: KeyCode::Backspace, .. } => { let _ = p.text.pop(); },
+        KeyEvent { code: KeyCode::Enter, .. } => {
+            let Prompt { kind, text } = prompt.take().unwrap();
+            return Outcome::Entered(kind, text);
+        },
+        KeyEvent { code: KeyCode::Esc, .. } => {
+            *prompt = None;
+            return Outcome::Cancelled;
+        },
+        _ => {},
+    }
+
+    Outcome::Typing
+}
+
+/// The prompt, as `label: text`, and then `message` (an error, if the `bool`
+/// is set), a line each.
+pub fn status_lines(prompt: Option<(String, &str)>, message: Option<(&str, bool)>) -> Vec<TuiText<'static>> {
+    let mut lines = Vec::new();
+
+    if let Some((label, text)) = prompt {
+        lines.push(TuiText::styled(format!("{}: {}\n", label, text), Style::default().fg(c!(ConsoleIn))));
+    }
+    if let Some((msg, error)) = message {
+        let colour = if error { c!(Error) } else { c!(Success) };
+        lines.push(TuiText::styled(format!("{}\n", msg), Style::default().fg(colour)));
+    }
+
+    lines
+}
+
+/// Draws the [status lines](status_lines) along the bottom of `area` (if
+/// there's room) and returns the rest of it.
+pub fn draw_status(prompt: Option<(String, &str)>, message: Option<(&str, bool)>, area: Rect, buf: &mut Buffer) -> Rect {
+    let status = status_lines(prompt, message);
+    let height = status.len() as u16;
+
+    if height == 0 || area.height <= height {
+        return area;
+    }
+
+    let bottom = Rect { y: area.y + area.height - height, height, ..area };
+    Paragraph::new(status.iter())
+        .style(Style::default().bg(Colour::Reset))
+        .render(bottom, buf);
+
+    Rect { height: area.height - height, ..area }
+}
+
+#[cfg(test)]
+mod prompt_tests {
+    use super::*;
+    use pretty_assertions::assert_eq as eq;
+
+    fn press(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent { code, modifiers }
+    }
+
+    #[test]
+    fn typing() {
+        let mut prompt = Some(Prompt::new(7));
+
+        for c in "aB!".chars() {
+            eq!(key(&mut prompt, press(KeyCode::Char(c), KeyModifiers::SHIFT)), Outcome::Typing);
+        }
+        key(&mut prompt, press(KeyCode::Char('x'), KeyModifiers::CONTROL));
+        key(&mut prompt, press(KeyCode::Backspace, KeyModifiers::empty()));
+        eq!(prompt.as_ref().map(|p| p.text.as_str()), Some("aB"));
+
+        eq!(key(&mut prompt, press(KeyCode::Enter, KeyModifiers::empty())), Outcome::Entered(7, "aB".to_string()));
+        eq!(prompt, None);
+
+        let mut prompt = Some(Prompt::with_text((), "old".to_string()));
+        eq!(key(&mut prompt, press(KeyCode::Esc, KeyModifiers::empty())), Outcome::Cancelled);
+        eq!(prompt, None);
+    }
+}
@@ -4,7 +4,11 @@ use super::Res as Result;
 use super::Tui;
 use super::events::{self, Event, WidgetEvent, FocusEvent, CrosstermEvent};
 use super::widget::Widget;
+use super::program_log::Level;
 use crate::strings::{s, HelloMsg, StartupMsg};
+use crate::keymap::{Action, Scope};
+use crate::clipboard::{self, Copied};
+use crate::colours::c;
 
 use lc3_traits::control::Control;
 use lc3_application_support::event_loop::Backoff;
@@ -13,7 +17,7 @@ use lc3_application_support::io_peripherals::{InputSink, OutputSource};
 use anyhow::{Context, anyhow};
 use chrono::{DateTime, offset::Local};
 use crossterm::ExecutableCommand;
-use crossterm::event::{KeyEvent, KeyCode, KeyModifiers, DisableMouseCapture};
+use crossterm::event::DisableMouseCapture;
 use tui::terminal::Terminal;
 use tui::widgets::Text as TuiText;
 use tui::layout::Rect;
@@ -68,7 +72,7 @@ where
         last_window_size: &mut Option<(u16, u16)>,
     ) -> bool
     where
-        B: tui::backend::Backend,
+        B: tui::backend::Backend + Write,
         B: ExecutableCommand<&'static str>,
     {
         use Event::*;
@@ -148,6 +152,13 @@ where
             // Currently, we only redraw on ticks (TODO: is this okay or should we
             // redraw on events too?):
             Tick => {
+                self.data.flush_input();
+                self.data.drive_adc();
+                self.data.track_timers();
+                self.data.track_interrupts();
+                self.data.track_stack();
+                self.data.sample_peripherals();
+                self.data.run_stimulus();
                 drop(root.update(WidgetEvent::Update, &mut self.data, term));
 
                 term.draw(|mut f| {
@@ -159,21 +170,49 @@ where
                     }
 
                     Widget::render(root, &self.data, &mut f, area)
-                }).unwrap() // TODO: is unwrapping okay here?
+                }).unwrap(); // TODO: is unwrapping okay here?
+
+                // Drawing the console is what pulls in new output so this
+                // goes after:
+                self.data.spill();
             }
 
             ActualEvent(e) => match e {
-                // Capture `ctrl + q`/`alt + f4` and forward everything else:
-                Key(KeyEvent { code: KeyCode::Char('w'), modifiers: KeyModifiers::CONTROL }) |
-                Key(KeyEvent { code: KeyCode::F(4), modifiers: KeyModifiers::ALT }) => {
-                    return false
-                }
+                // Capture the quit binding (`ctrl + w`/`alt + f4` by default)
+                // and the other global bindings and forward everything else:
+                Key(k) => match self.data.keymap.lookup(&k, Scope::Global) {
+                    Some(Action::Quit) => return false,
+                    Some(Action::CycleTheme) => self.data.cycle_theme(),
+                    Some(Action::CopyConsole) => {
+                        let text = self.data.console_hist.borrow().as_string();
+                        self.data.copy("console history", text)
+                    },
+                    Some(Action::SaveTranscript) => self.data.save_transcript(),
+                    Some(Action::CopyLog) => self.data.copy_log(),
+                    Some(Action::CopyRegisters) => self.data.copy_registers(),
+                    Some(Action::CopyDiagnostics) => self.data.copy_diagnostics(),
+                    Some(Action::Paste) => self.data.paste(),
+                    _ => drop(root.update(e.into(), &mut self.data, term)),
+                },
                 e => drop(root.update(e.into(), &mut self.data, term)),
             }
 
             _ => unreachable!("Got {:?} which shouldn't be possible.", event),
         }
 
+        // Hand anything that was just copied to the terminal:
+        if let Some(text) = self.data.pending_copy.take() {
+            match clipboard::copy(term.backend_mut(), &text) {
+                Ok(Copied::Osc52) => {},
+                #[cfg(not(target_arch = "wasm32"))]
+                Ok(Copied::File(path)) => {
+                    let msg = format!("[Copy] The terminal doesn't seem to support OSC 52; wrote the text to `{}` instead.\n", path.display());
+                    self.data.log_at(Level::Warn, msg, c!(Pause))
+                },
+                Err(e) => self.data.log_at(Level::Error, format!("[Copy] Failed to copy: {}\n", e), c!(Error)),
+            }
+        }
+
         // onwards! (i.e. don't stop)
         true
     }
@@ -195,7 +234,7 @@ where
 { specialize! {
     desktop => {
         /// TODO: docs
-        pub fn run_with_custom_layout<B: tui::backend::Backend + 'a>(mut self, term: &mut Terminal<B>, mut root: impl Widget<'a, 'int, C, I, O, B>) -> Result<()>
+        pub fn run_with_custom_layout<B: tui::backend::Backend + Write + 'a>(mut self, term: &mut Terminal<B>, mut root: impl Widget<'a, 'int, C, I, O, B>) -> Result<()>
         where
             B: ExecutableCommand<&'static str>,
             Terminal<B>: Send,
@@ -218,13 +257,16 @@ where
 
             // TODO: expose better errors from here! have `run_tick_with_event_with_project` return
             // a type that impls `Error`!
-            backoff.run_tick_with_event_with_project(&mut self, |t| t.data.sim, event_recv, |tui, event| {
+            let res = backoff.run_tick_with_event_with_project(&mut self, |t| t.data.sim, event_recv, |tui, event| {
                 tui.handle_event(event, term, &tx, &mut root, &mut last_window_size)
-            }).map_err(|_| anyhow!("Channel disconnected; maybe something crashed?"))
+            }).map_err(|_| anyhow!("Channel disconnected; maybe something crashed?"));
+
+            self.save_config_if_changed();
+            res
         }
 
         // Run with default layout and a backend of your choosing.
-        pub fn run<B: tui::backend::Backend + 'a>(self, term: &mut Terminal<B>) -> Result<()>
+        pub fn run<B: tui::backend::Backend + Write + 'a>(self, term: &mut Terminal<B>) -> Result<()>
         where
             B: ExecutableCommand<&'static str>,
             Terminal<B>: Send,
@@ -0,0 +1,243 @@
+//! Signal generators: time-varying values that drive ADC pins.
+
+use super::time::now;
+
+use std::f64::consts::PI;
+use std::fmt::{self, Display};
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
+
+/// The period waveforms get when one isn't given, in milliseconds.
+pub const DEFAULT_PERIOD: u64 = 2_000;
+/// How far apart samples are (when the CSV doesn't say), in milliseconds.
+pub const DEFAULT_SAMPLE_INTERVAL: u64 = 100;
+
+/// What an ADC pin is driven with; values are in `low..=high` and periods are
+/// in milliseconds.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Signal {
+    Constant(u8),
+    /// Climbs from `low` to `high` and then starts over.
+    Ramp { low: u8, high: u8, period: u64 },
+    Sine { low: u8, high: u8, period: u64 },
+    /// `high` for the first half of every period, `low` for the second.
+    Square { low: u8, high: u8, period: u64 },
+    /// A new random value on every update.
+    Noise { low: u8, high: u8 },
+    /// Values that take effect at the given times (from the start of the
+    /// signal); the whole thing repeats every `length`.
+    Samples { samples: Vec<(u64, u8)>, length: u64 },
+}
+
+impl Signal {
+    /// The names [`Signal::waveform`] takes.
+    pub const WAVEFORMS: &'static [&'static str] = &["noise", "ramp", "sine", "square"];
+
+    /// Makes one of the generated signals (by name) that goes from `low` to
+    /// `high`.
+    pub fn waveform(name: &str, low: u8, high: u8, period: u64) -> Option<Self> {
+        let (low, high) = (low.min(high), low.max(high));
+        let period = period.max(2);
+
+        Some(match name {
+            "ramp" => Signal::Ramp { low, high, period },
+            "sine" => Signal::Sine { low, high, period },
+            "square" => Signal::Square { low, high, period },
+            "noise" => Signal::Noise { low, high },
+            _ => return None,
+        })
+    }
+
+    /// Parses samples from CSV: either a value per line (which are
+    /// `interval` apart) or `time,value` lines (with times in milliseconds).
+    /// Lines that don't start with a number (i.e. headers) are skipped.
+    pub fn samples_from_csv(csv: &str, interval: u64) -> Result<Self, String> {
+        let mut samples = Vec::new();
+        let mut timed = None;
+
+        for (num, line) in csv.lines().enumerate() {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            if fields.iter().all(|f| f.is_empty()) || fields[0].parse::<f64>().is_err() {
+                continue;
+            }
+
+            let is_timed = fields.len() > 1 && !fields[1].is_empty();
+            if *timed.get_or_insert(is_timed) != is_timed {
+                return Err(format!("Line {}: every line needs to have the same number of columns", num + 1));
+            }
+
+            let value = if is_timed { fields[1] } else { fields[0] };
+            let value = match value.parse::<f64>() {
+                Ok(v) if (0.0..=255.0).contains(&v) => v.round() as u8,
+                _ => return Err(format!("Line {}: `{}` isn't a value from 0 to 255", num + 1, value)),
+            };
+
+            let time = if is_timed {
+                match fields[0].parse::<u64>() {
+                    Ok(t) if samples.last().map(|(last, _)| t > *last).unwrap_or(true) => t,
+                    _ => return Err(format!("Line {}: `{}` isn't a time after the one before it", num + 1, fields[0])),
+                }
+            } else {
+                samples.len() as u64 * interval
+            };
+
+            samples.push((time, value));
+        }
+
+        // The last sample lasts as long as the gap before it:
+        let length = match samples.as_slice() {
+            [] => return Err("There aren't any samples".to_string()),
+            [.., (a, _), (b, _)] => b + (b - a),
+            [(t, _)] => t + interval,
+        };
+
+        Ok(Signal::Samples { samples, length: length.max(1) })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Signal::Constant(_) => "constant",
+            Signal::Ramp { .. } => "ramp",
+            Signal::Sine { .. } => "sine",
+            Signal::Square { .. } => "square",
+            Signal::Noise { .. } => "noise",
+            Signal::Samples { .. } => "samples",
+        }
+    }
+
+    /// How long the signal takes to repeat, for the generated signals that
+    /// have a period.
+    pub fn period(&self) -> Option<u64> {
+        match *self {
+            Signal::Ramp { period, .. } | Signal::Sine { period, .. } | Signal::Square { period, .. } => Some(period),
+            _ => None,
+        }
+    }
+
+    /// The same signal with a different period (if it has one).
+    pub fn with_period(&self, period: u64) -> Self {
+        match *self {
+            Signal::Ramp { low, high, .. } | Signal::Sine { low, high, .. } | Signal::Square { low, high, .. } => {
+                Signal::waveform(self.name(), low, high, period).unwrap()
+            },
+            _ => self.clone(),
+        }
+    }
+
+    /// The signal's value `t` milliseconds after it started; `random` is used
+    /// for noise.
+    pub fn value(&self, t: u64, random: u64) -> u8 {
+        fn scale(low: u8, high: u8, frac: f64) -> u8 {
+            (low as f64 + high.saturating_sub(low) as f64 * frac).round() as u8
+        }
+
+        match *self {
+            Signal::Constant(v) => v,
+            Signal::Ramp { low, high, period } => scale(low, high, (t % period) as f64 / (period - 1) as f64),
+            Signal::Sine { low, high, period } => {
+                let angle = 2.0 * PI * (t % period) as f64 / period as f64;
+                scale(low, high, (angle.sin() + 1.0) / 2.0)
+            },
+            Signal::Square { low, high, period } => if t % period < period / 2 { high } else { low },
+            Signal::Noise { low, high } => low + (random % (high.saturating_sub(low) as u64 + 1)) as u8,
+            Signal::Samples { ref samples, length } => {
+                let t = t % length;
+                let idx = samples.iter().rposition(|(at, _)| *at <= t).unwrap_or(0);
+                samples[idx].1
+            },
+        }
+    }
+}
+
+// No files on wasm.
+#[cfg(not(target_arch = "wasm32"))]
+impl Signal {
+    pub fn load_samples(path: &Path) -> Result<Self, String> {
+        let csv = std::fs::read_to_string(path)
+            .map_err(|e| format!("Couldn't read `{}`: {}", path.display(), e))?;
+
+        Self::samples_from_csv(&csv, DEFAULT_SAMPLE_INTERVAL)
+    }
+}
+
+impl Display for Signal {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Signal::Constant(v) => write!(fmt, "constant {}", v),
+            Signal::Ramp { low, high, period }
+            | Signal::Sine { low, high, period }
+            | Signal::Square { low, high, period } => write!(fmt, "{} {}-{} every {}ms", self.name(), low, high, period),
+            Signal::Noise { low, high } => write!(fmt, "noise {}-{}", low, high),
+            Signal::Samples { samples, length } => write!(fmt, "{} samples over {}ms", samples.len(), length),
+        }
+    }
+}
+
+/// A [`Signal`] that's been attached to a pin.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SignalGenerator {
+    pub signal: Signal,
+    /// When the signal was attached (see `now`).
+    start: u64,
+    /// State for the (xorshift) random numbers noise uses.
+    rng: u64,
+}
+
+impl SignalGenerator {
+    pub fn new(signal: Signal) -> Self {
+        let start = now();
+        Self { signal, start, rng: start | 1 }
+    }
+
+    /// The signal's value as of now.
+    pub fn sample(&mut self) -> u8 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+
+        self.signal.value(now().saturating_sub(self.start), self.rng)
+    }
+}
+
+#[cfg(test)]
+mod signal_tests {
+    use super::*;
+    use pretty_assertions::assert_eq as eq;
+
+    fn values(signal: &Signal, times: &[u64]) -> Vec<u8> {
+        times.iter().map(|t| signal.value(*t, 0)).collect()
+    }
+
+    #[test]
+    fn waveforms() {
+        let ramp = Signal::waveform("ramp", 0, 100, 101).unwrap();
+        eq!(values(&ramp, &[0, 50, 100, 101]), vec![0, 50, 100, 0]);
+
+        let sine = Signal::waveform("sine", 255, 0, 1000).unwrap();
+        eq!(values(&sine, &[0, 250, 500, 750]), vec![128, 255, 128, 0]);
+
+        let square = Signal::waveform("square", 10, 20, 100).unwrap();
+        eq!(values(&square, &[0, 49, 50, 99, 100]), vec![20, 20, 10, 10, 20]);
+
+        let noise = Signal::waveform("noise", 10, 20, 100).unwrap();
+        assert!((0..1000).all(|r| (10..=20).contains(&noise.value(0, r))));
+
+        eq!(Signal::waveform("saw", 0, 1, 1), None);
+    }
+
+    #[test]
+    fn samples() {
+        let plain = Signal::samples_from_csv("value\n10\n20\n\n30\n", 100).unwrap();
+        eq!(plain, Signal::Samples { samples: vec![(0, 10), (100, 20), (200, 30)], length: 300 });
+        eq!(values(&plain, &[0, 99, 100, 250, 300]), vec![10, 10, 20, 30, 10]);
+
+        let timed = Signal::samples_from_csv("time,value\n0, 1\n50, 2.4\n", 100).unwrap();
+        eq!(timed, Signal::Samples { samples: vec![(0, 1), (50, 2)], length: 100 });
+
+        assert!(Signal::samples_from_csv("1\n256\n", 100).is_err());
+        assert!(Signal::samples_from_csv("0,1\n0,2\n", 100).is_err());
+        assert!(Signal::samples_from_csv("0,1\n5\n", 100).is_err());
+        assert!(Signal::samples_from_csv("header\n", 100).is_err());
+    }
+}
@@ -0,0 +1,150 @@
+//! Keeps track of the stacks (R6) for the [`StackInspector`].
+//!
+//! The LC-3 has a stack per mode: R6 is the stack pointer for whichever mode
+//! the processor is in and the other one is tucked away (in `Saved_USP` or
+//! `Saved_SSP`) where the simulator doesn't let us see it. So, for the other
+//! stack, we go with the last R6 we saw in that mode.
+//!
+//! Pushes and pops are worked out by comparing R6 (and the words around it)
+//! now with what they were at the previous PC, like the register view does.
+//!
+//! [`StackInspector`]: crate::widgets::StackInspector
+
+use lc3_isa::{Addr, Word};
+
+use std::ops::Range;
+
+/// How many words on either side of R6 we remember (to spot changes).
+pub const WINDOW: Word = 32;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Snapshot {
+    pc: Addr,
+    supervisor: bool,
+    sp: Word,
+    /// The words from `sp - WINDOW` to `sp + WINDOW`.
+    words: Vec<Word>,
+}
+
+impl Snapshot {
+    fn start(&self) -> Addr {
+        self.sp.wrapping_sub(WINDOW)
+    }
+
+    fn word(&self, addr: Addr) -> Option<Word> {
+        self.words.get(addr.wrapping_sub(self.start()) as usize).copied()
+    }
+}
+
+/// What happened to the stack in the last step.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Change {
+    /// The words that were pushed (the stack grows down).
+    Pushed(Range<u32>),
+    /// The words that were popped; they're still in memory but they aren't
+    /// on the stack anymore.
+    Popped(Range<u32>),
+}
+
+impl Change {
+    pub fn contains(&self, addr: Addr) -> bool {
+        match self {
+            Change::Pushed(r) | Change::Popped(r) => r.contains(&(addr as u32)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct StackTracker {
+    /// The last R6 we saw in each mode.
+    pub user_sp: Option<Word>,
+    pub supervisor_sp: Option<Word>,
+    /// As of the PC before this one.
+    previous: Option<Snapshot>,
+    current: Option<Snapshot>,
+    /// Whether the program's R6 was in the stack region (see
+    /// `Config::stack_region`) as of the last update.
+    pub(in crate) in_region: bool,
+}
+
+impl StackTracker {
+    /// Catches up with the processor; `read` reads memory.
+    pub fn observe(&mut self, pc: Addr, supervisor: bool, sp: Word, read: impl Fn(Addr) -> Word) {
+        if supervisor {
+            self.supervisor_sp = Some(sp);
+        } else {
+            self.user_sp = Some(sp);
+        }
+
+        if self.current.as_ref().map(|c| c.pc) != Some(pc) {
+            self.previous = self.current.take();
+        }
+
+        let start = sp.wrapping_sub(WINDOW);
+        let words = (0..=2 * WINDOW).map(|off| read(start.wrapping_add(off))).collect();
+        self.current = Some(Snapshot { pc, supervisor, sp, words });
+    }
+
+    /// The pushes or pops since the previous PC on the stack that's in use
+    /// (if any); switching stacks doesn't count.
+    pub fn change(&self) -> Option<Change> {
+        let (prev, cur) = (self.previous.as_ref()?, self.current.as_ref()?);
+        if prev.supervisor != cur.supervisor {
+            return None;
+        }
+
+        let (old, new) = (prev.sp as u32, cur.sp as u32);
+        if new < old {
+            Some(Change::Pushed(new..old))
+        } else if new > old {
+            Some(Change::Popped(old..new))
+        } else {
+            None
+        }
+    }
+
+    /// What the word at `addr` was at the previous PC, if it changed (and
+    /// it's close enough to R6 that we were watching it).
+    pub fn changed(&self, addr: Addr, now: Word) -> Option<Word> {
+        self.previous.as_ref()?.word(addr).filter(|old| *old != now)
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod stack_tests {
+    use super::*;
+    use pretty_assertions::assert_eq as eq;
+
+    #[test]
+    fn pushes_and_pops() {
+        let mut stack = StackTracker::default();
+        let mut memory = vec![0; 0x10000];
+
+        stack.observe(0x3000, false, 0xFE00, |a| memory[a as usize]);
+        eq!(stack.change(), None);
+
+        // Two pushes:
+        memory[0xFDFE] = 7;
+        memory[0xFDFF] = 8;
+        stack.observe(0x3001, false, 0xFDFE, |a| memory[a as usize]);
+        eq!(stack.change(), Some(Change::Pushed(0xFDFE..0xFE00)));
+        eq!(stack.changed(0xFDFE, 7), Some(0));
+        eq!(stack.changed(0xFE00, 0), None);
+
+        // Updates at the same PC don't count as a step:
+        stack.observe(0x3001, false, 0xFDFE, |a| memory[a as usize]);
+        assert!(stack.change().unwrap().contains(0xFDFF));
+
+        stack.observe(0x3002, false, 0xFDFF, |a| memory[a as usize]);
+        eq!(stack.change(), Some(Change::Popped(0xFDFE..0xFDFF)));
+
+        // Switching to the supervisor stack:
+        stack.observe(0x0500, true, 0x2FFE, |a| memory[a as usize]);
+        eq!(stack.change(), None);
+        eq!((stack.user_sp, stack.supervisor_sp), (Some(0xFDFF), Some(0x2FFE)));
+    }
+}
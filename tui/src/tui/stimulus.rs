@@ -0,0 +1,349 @@
+//! Stimulus scripts: changes to the peripherals' inputs at set times, so that
+//! peripheral labs play out the same way on every run.
+//!
+//! Scripts have an event per line (`#` starts a comment):
+//! ```text
+//! at 500ms gpio 3 = 1          # once, 500ms in
+//! at step 2000 adc 1 = 128     # once, after 2000 steps
+//! every 100ms toggle gpio 0    # every 100ms (starting 100ms in)
+//! every 2s pulse gpio 2        # a rising edge (for interrupts)
+//! ```
+//!
+//! Times (`500ms`, `2s`, or plain milliseconds) are measured with the
+//! simulator's clock (`Control::get_clock`); both start from when the script
+//! is loaded (or the machine is reset).
+//!
+//! Steps only count instructions that are single stepped (with the step
+//! button or key): the simulator doesn't tell us how many instructions it ran
+//! while running freely, so `step` events are reported as failing (once each)
+//! if the machine is run while they're still pending.
+
+use super::peripheral_commands::{parse_level, parse_period, parse_value, pin_number, Peripheral};
+
+use lc3_isa::Word;
+
+use std::collections::VecDeque;
+use std::fmt::{self, Display};
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
+
+/// How many fired events we remember.
+pub const FIRED_LEN: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum When {
+    Millis(u64),
+    Steps(u64),
+}
+
+impl Display for When {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            When::Millis(ms) if ms % 1000 == 0 && *ms > 0 => write!(fmt, "{}s", ms / 1000),
+            When::Millis(ms) => write!(fmt, "{}ms", ms),
+            When::Steps(steps) => write!(fmt, "step {}", steps),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    SetGpio(usize, bool),
+    ToggleGpio(usize),
+    PulseGpio(usize),
+    SetAdc(usize, u8),
+}
+
+impl Display for Action {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::SetGpio(pin, level) => write!(fmt, "gpio {} = {}", pin, *level as u8),
+            Action::ToggleGpio(pin) => write!(fmt, "toggle gpio {}", pin),
+            Action::PulseGpio(pin) => write!(fmt, "pulse gpio {}", pin),
+            Action::SetAdc(pin, value) => write!(fmt, "adc {} = {}", pin, value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Event {
+    /// Where in the script the event is (starting from 1).
+    pub line: usize,
+    /// When the event happens or, if it repeats, how often.
+    pub when: When,
+    pub repeats: bool,
+    pub action: Action,
+    /// When the event is next due (in `when`'s units); `None` once it's done.
+    pub next: Option<u64>,
+    /// Whether the event has failed (we only log the first failure).
+    pub(in crate) failed: bool,
+}
+
+impl Event {
+    fn first_due(when: When) -> u64 {
+        match when {
+            When::Millis(n) | When::Steps(n) => n,
+        }
+    }
+}
+
+/// An event that went off.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Fired {
+    pub line: usize,
+    pub action: Action,
+    /// When it went off (the time if it's a timed event, the step otherwise).
+    pub at: When,
+    /// Why it didn't do anything, if it didn't.
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StimulusScript {
+    /// Where the script came from (for display).
+    pub name: String,
+    pub events: Vec<Event>,
+    /// The most recent fired events, oldest first.
+    pub fired: VecDeque<Fired>,
+    /// How far into the script we are.
+    pub millis: u64,
+    pub steps: u64,
+    /// The clock's reading as of the last update (see
+    /// [`TuiData::run_stimulus`](super::TuiData::run_stimulus)).
+    pub(in crate) last_clock: Option<Word>,
+    /// Steps taken since the last update.
+    pub(in crate) pending_steps: u64,
+}
+
+impl StimulusScript {
+    pub fn parse(name: &str, src: &str) -> Result<Self, String> {
+        let events = src
+            .lines()
+            .enumerate()
+            .map(|(idx, line)| (idx + 1, line.split('#').next().unwrap().trim()))
+            .filter(|(_, line)| !line.is_empty())
+            .map(|(num, line)| Self::parse_event(num, line).map_err(|e| format!("Line {}: {}", num, e)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if events.is_empty() {
+            return Err("There aren't any events".to_string());
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            events,
+            fired: VecDeque::new(),
+            millis: 0,
+            steps: 0,
+            last_clock: None,
+            pending_steps: 0,
+        })
+    }
+
+    fn parse_event(line: usize, src: &str) -> Result<Event, String> {
+        const USAGE: &str = "events look like `at 500ms gpio 3 = 1` or `every 100ms toggle gpio 0`";
+
+        // `=` is optional (and doesn't need spaces around it):
+        let src = src.to_lowercase().replace('=', " ");
+        let words: Vec<&str> = src.split_whitespace().collect();
+
+        let (repeats, rest) = match words.split_first() {
+            Some((&"at", rest)) => (false, rest),
+            Some((&"every", rest)) => (true, rest),
+            _ => return Err(format!("Expected `at` or `every`; {}", USAGE)),
+        };
+
+        let (when, rest) = match rest {
+            ["step", n, rest @ ..] | ["steps", n, rest @ ..] => match n.parse::<u64>() {
+                Ok(n) if n > 0 || !repeats => (When::Steps(n), rest),
+                _ => return Err(format!("`{}` isn't a number of steps", n)),
+            },
+            [time, rest @ ..] => match (*time, repeats) {
+                ("0", false) | ("0ms", false) | ("0s", false) => (When::Millis(0), rest),
+                (time, _) => (When::Millis(parse_period(time)?), rest),
+            },
+            [] => return Err(format!("Expected a time; {}", USAGE)),
+        };
+
+        let action = match rest {
+            ["gpio", pin, level] => Action::SetGpio(pin_number(Peripheral::Gpio, pin)?, parse_level(level)?),
+            ["toggle", "gpio", pin] => Action::ToggleGpio(pin_number(Peripheral::Gpio, pin)?),
+            ["pulse", "gpio", pin] => Action::PulseGpio(pin_number(Peripheral::Gpio, pin)?),
+            ["adc", pin, value] => Action::SetAdc(pin_number(Peripheral::Adc, pin)?, parse_value(value)?),
+            _ => return Err(format!(
+                "Expected `gpio <pin> = <0|1>`, `toggle gpio <pin>`, `pulse gpio <pin>` or `adc <pin> = <value>`; {}", USAGE
+            )),
+        };
+
+        Ok(Event { line, when, repeats, action, next: Some(Event::first_due(when)), failed: false })
+    }
+
+    /// Starts the script over.
+    pub fn restart(&mut self) {
+        for event in self.events.iter_mut() {
+            event.next = Some(Event::first_due(event.when));
+            event.failed = false;
+        }
+
+        self.fired.clear();
+        self.millis = 0;
+        self.steps = 0;
+        self.last_clock = None;
+        self.pending_steps = 0;
+    }
+
+    /// Moves the script on by `millis` and `steps`; returns the events that
+    /// are now due (by index, in the order they're in the script), which
+    /// should then be [recorded].
+    ///
+    /// Repeating events that were due more than once (i.e. because we fell
+    /// behind) only go off once.
+    ///
+    /// [recorded]: StimulusScript::record
+    pub fn advance(&mut self, millis: u64, steps: u64) -> Vec<usize> {
+        self.millis += millis;
+        self.steps += steps;
+
+        let (now_ms, now_steps) = (self.millis, self.steps);
+        let mut due = Vec::new();
+
+        for (idx, event) in self.events.iter_mut().enumerate() {
+            let (now, period) = match event.when {
+                When::Millis(p) => (now_ms, p),
+                When::Steps(p) => (now_steps, p),
+            };
+
+            match event.next {
+                Some(next) if next <= now => {
+                    due.push(idx);
+                    event.next = if event.repeats { Some(now - (now - next) % period + period) } else { None };
+                },
+                _ => {},
+            }
+        }
+
+        due
+    }
+
+    /// Notes that event `idx` went off (and whether it worked).
+    pub fn record(&mut self, idx: usize, error: Option<String>) {
+        let event = &mut self.events[idx];
+        if error.is_some() {
+            event.failed = true;
+        }
+
+        let at = match event.when {
+            When::Millis(_) => When::Millis(self.millis),
+            When::Steps(_) => When::Steps(self.steps),
+        };
+
+        if self.fired.len() == FIRED_LEN {
+            let _ = self.fired.pop_front();
+        }
+        self.fired.push_back(Fired { line: event.line, action: event.action, at, error });
+    }
+
+    /// Marks the pending `step` events that haven't already failed as failed
+    /// and returns them; for when the machine runs freely (and so steps go
+    /// uncounted).
+    pub fn miss_steps(&mut self) -> Vec<usize> {
+        self.events
+            .iter_mut()
+            .enumerate()
+            .filter(|(_, e)| matches!(e.when, When::Steps(_)) && e.next.is_some() && !e.failed)
+            .map(|(idx, e)| { e.failed = true; idx })
+            .collect()
+    }
+
+    /// The events that haven't finished, soonest first (timed events before
+    /// stepped ones).
+    pub fn upcoming(&self) -> Vec<&Event> {
+        let mut upcoming: Vec<&Event> = self.events.iter().filter(|e| e.next.is_some()).collect();
+        upcoming.sort_by_key(|e| (matches!(e.when, When::Steps(_)), e.next));
+
+        upcoming
+    }
+}
+
+// No files on wasm.
+#[cfg(not(target_arch = "wasm32"))]
+impl StimulusScript {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        use anyhow::Context;
+
+        let src = std::fs::read_to_string(path)
+            .with_context(|| format!("Couldn't read the stimulus script at `{}`", path.display()))?;
+
+        let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        Self::parse(&name, &src)
+            .map_err(anyhow::Error::msg)
+            .with_context(|| format!("Couldn't parse the stimulus script at `{}`", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod stimulus_tests {
+    use super::*;
+    use pretty_assertions::assert_eq as eq;
+
+    #[test]
+    fn parsing() {
+        let script = StimulusScript::parse("test", "
+            # A comment.
+            at 500ms gpio 3 = 1
+            at step 2000 adc 1=128   # trailing comment
+            every 100ms toggle gpio 0
+            every 2s pulse gpio g2
+            at 0 adc 5 = x10
+        ").unwrap();
+
+        let events: Vec<_> = script.events.iter().map(|e| (e.line, e.when, e.repeats, e.action)).collect();
+        eq!(events, vec![
+            (3, When::Millis(500), false, Action::SetGpio(3, true)),
+            (4, When::Steps(2000), false, Action::SetAdc(1, 128)),
+            (5, When::Millis(100), true, Action::ToggleGpio(0)),
+            (6, When::Millis(2000), true, Action::PulseGpio(2)),
+            (7, When::Millis(0), false, Action::SetAdc(5, 16)),
+        ]);
+    }
+
+    #[test]
+    fn errors() {
+        let err = |src: &str| StimulusScript::parse("test", src).unwrap_err();
+
+        eq!(err(""), "There aren't any events");
+        assert!(err("\nat 5ms gpio 8 = 1").starts_with("Line 2: There's no GPIO pin 8"));
+        assert!(err("in 5ms gpio 1 = 1").starts_with("Line 1: Expected `at` or `every`"));
+        assert!(err("every 0 toggle gpio 1").starts_with("Line 1: `0` isn't a period"));
+        assert!(err("at 5ms adc 1 = 256").contains("too big"));
+        assert!(err("at 5ms pwm 1 = 2").contains("Expected `gpio"));
+    }
+
+    #[test]
+    fn scheduling() {
+        let mut script = StimulusScript::parse("test", "
+            every 100ms toggle gpio 0
+            at 150ms gpio 1 = 1
+            at step 2 adc 0 = 1
+        ").unwrap();
+
+        eq!(script.advance(99, 0), Vec::<usize>::new());
+        eq!(script.advance(1, 0), vec![0]);
+        eq!(script.advance(60, 1), vec![1]);
+
+        // Falling behind only fires repeating events once:
+        eq!(script.advance(500, 1), vec![0, 2]);
+        eq!(script.events[0].next, Some(700));
+        eq!(script.upcoming().len(), 1);
+
+        script.record(2, Some("nope".to_string()));
+        eq!(script.fired.back().map(|f| f.at), Some(When::Steps(2)));
+
+        script.restart();
+        eq!(script.upcoming().len(), 3);
+        eq!(script.miss_steps(), vec![2]);
+        eq!(script.miss_steps(), Vec::<usize>::new());
+        eq!(script.advance(100, 0), vec![0]);
+    }
+}
@@ -0,0 +1,295 @@
+//! The labels in the loaded program, so addresses can be shown by name (i.e.
+//! `x3010 LOOP`).
+//!
+//! The assembler doesn't hand its symbol table back, so this walks the
+//! source the same way it lays the program out: `.ORIG` sets the address,
+//! instructions and `.FILL` take a word, `.BLKW n` takes `n` and `.STRINGZ`
+//! takes a word per character plus one for the terminator. Sources the
+//! assembler would reject can give wrong addresses but never fail here.
+
+use lc3_isa::{Addr, Word};
+
+use std::collections::{BTreeMap, HashMap};
+
+/// Everything that isn't a label at the start of a line.
+const OPCODES: &[&str] = &[
+    "add", "and", "br", "brn", "brz", "brp", "brnz", "brnp", "brzp", "brnzp", "jmp", "jsr", "jsrr",
+    "ld", "ldi", "ldr", "lea", "not", "ret", "rti", "st", "sti", "str", "trap",
+    "getc", "out", "puts", "in", "putsp", "halt",
+    ".orig", ".fill", ".blkw", ".stringz", ".end",
+];
+
+/// The OS's trap routines, by trap vector.
+pub const TRAP_NAMES: &[(u8, &str)] = &[
+    (0x20, "GETC"),
+    (0x21, "OUT"),
+    (0x22, "PUTS"),
+    (0x23, "IN"),
+    (0x24, "PUTSP"),
+    (0x25, "HALT"),
+];
+
+pub fn trap_name(vector: u8) -> Option<&'static str> {
+    TRAP_NAMES.iter().find(|(v, _)| *v == vector).map(|(_, n)| *n)
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Symbols {
+    by_addr: BTreeMap<Addr, String>,
+    /// Lowercase names (labels aren't case sensitive).
+    by_name: HashMap<String, Addr>,
+}
+
+impl Symbols {
+    pub fn from_source(src: &str) -> Self {
+        let mut symbols = Self::default();
+        let mut addr: Option<Addr> = None;
+
+        for line in src.lines() {
+            let words = tokens(line);
+            let (label, rest) = match words.split_first() {
+                Some((first, rest)) if !is_opcode(first) => (Some(first.as_str()), rest),
+                _ => (None, &words[..]),
+            };
+
+            let (directive, operand) = match rest {
+                [op, arg, ..] => (op.to_lowercase(), Some(arg.as_str())),
+                [op] => (op.to_lowercase(), None),
+                [] => (String::new(), None),
+            };
+
+            if directive == ".orig" {
+                addr = operand.and_then(parse_number);
+                continue;
+            }
+            if directive == ".end" {
+                addr = None;
+                continue;
+            }
+
+            let here = match addr {
+                Some(a) => a,
+                None => continue,
+            };
+
+            if let Some(label) = label {
+                symbols.insert(here, label);
+            }
+
+            let size = match (directive.as_str(), operand) {
+                ("", _) => 0,
+                (".blkw", Some(n)) => parse_number(n).unwrap_or(1),
+                (".stringz", _) => string_len(line) + 1,
+                _ => 1,
+            };
+            addr = Some(here.wrapping_add(size));
+        }
+
+        symbols
+    }
+
+    fn insert(&mut self, addr: Addr, name: &str) {
+        let _ = self.by_name.entry(name.to_lowercase()).or_insert(addr);
+        let _ = self.by_addr.entry(addr).or_insert_with(|| name.to_string());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_addr.is_empty()
+    }
+
+    /// The label at `addr`, if there is one.
+    pub fn name(&self, addr: Addr) -> Option<&str> {
+        self.by_addr.get(&addr).map(|n| n.as_str())
+    }
+
+    /// Where the label `name` is (ignoring case).
+    pub fn addr(&self, name: &str) -> Option<Addr> {
+        self.by_name.get(&name.to_lowercase()).copied()
+    }
+
+    /// `addr` as a label plus an offset from it (i.e. `LOOP+2`), using the
+    /// closest label at or before it; `None` if that's more than `max_offset`
+    /// away.
+    pub fn describe(&self, addr: Addr, max_offset: Word) -> Option<String> {
+        let (at, name) = self.by_addr.range(..=addr).next_back()?;
+
+        match addr - at {
+            0 => Some(name.clone()),
+            off if off <= max_offset => Some(format!("{}+{}", name, off)),
+            _ => None,
+        }
+    }
+
+    /// The address `target` refers to: a register (the address in it), `PC`,
+    /// a label or a number. A `*` in front follows the pointer stored there
+    /// instead; `read` reads memory.
+    pub fn resolve(&self, target: &str, regs: &[Word; 8], pc: Addr, read: impl Fn(Addr) -> Word) -> Result<Addr, String> {
+        let target = target.trim();
+        let (deref, target) = match target.strip_prefix('*') {
+            Some(t) => (true, t.trim_start()),
+            None => (false, target),
+        };
+
+        let lower = target.to_lowercase();
+        let reg = lower.strip_prefix('r').and_then(|n| n.parse::<usize>().ok()).filter(|n| *n < 8);
+
+        let addr = match (reg, lower == "pc", self.addr(target), parse_number(target)) {
+            (Some(r), _, _, _) => regs[r],
+            (None, true, _, _) => pc,
+            (None, false, Some(addr), _) | (None, false, None, Some(addr)) => addr,
+            (None, false, None, None) => return Err(format!("`{}` isn't an address, a label or a register", target)),
+        };
+
+        Ok(if deref { read(addr) } else { addr })
+    }
+}
+
+fn is_opcode(word: &str) -> bool {
+    let word = word.to_lowercase();
+    OPCODES.contains(&word.as_str()) || word.starts_with('.')
+}
+
+// The line without its comment, split on whitespace and commas; strings are
+// left as one (quoted) token.
+fn tokens(line: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for c in line.chars() {
+        match c {
+            _ if in_string => {
+                current.push(c);
+                match (escaped, c) {
+                    (false, '\\') => escaped = true,
+                    (false, '"') => in_string = false,
+                    _ => escaped = false,
+                }
+            },
+            ';' => break,
+            '"' => { current.push(c); in_string = true },
+            c if c.is_whitespace() || c == ',' => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            },
+            c => current.push(c),
+        }
+    }
+
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+// How many characters are in the (first) string on the line, with escapes
+// counted once.
+fn string_len(line: &str) -> Word {
+    let mut chars = line.chars().skip_while(|c| *c != '"').skip(1);
+    let mut len = 0;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => break,
+            '\\' => { let _ = chars.next(); },
+            _ => {},
+        }
+        len += 1;
+    }
+
+    len
+}
+
+/// Parses numbers the way the assembler writes them: `x3000`, `#10`, `10`,
+/// `b101` (and `0x3000`). Negative numbers wrap.
+pub fn parse_number(src: &str) -> Option<Word> {
+    let src = src.trim();
+    let (negative, src) = match src.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, src),
+    };
+    let src = src.strip_prefix('#').unwrap_or(src);
+
+    let lower = src.to_lowercase();
+    let (digits, radix) = if let Some(hex) = lower.strip_prefix("0x").or_else(|| lower.strip_prefix('x')) {
+        (hex, 16)
+    } else if let Some(bin) = lower.strip_prefix("0b").or_else(|| lower.strip_prefix('b')) {
+        (bin, 2)
+    } else {
+        (lower.as_str(), 10)
+    };
+
+    if digits.is_empty() || digits.starts_with('-') || digits.starts_with('+') {
+        return None;
+    }
+
+    let value = u32::from_str_radix(digits, radix).ok().filter(|v| *v <= 0xFFFF)? as Word;
+    Some(if negative { value.wrapping_neg() } else { value })
+}
+
+#[cfg(test)]
+mod symbols_tests {
+    use super::*;
+    use pretty_assertions::assert_eq as eq;
+
+    #[test]
+    fn layout() {
+        let symbols = Symbols::from_source(r#"
+            .ORIG x3000
+            START   LEA R0, MSG     ; a comment with a LABEL in it
+                    PUTS
+            LOOP
+                    ADD R1, R1, #-1
+                    BRp LOOP
+                    HALT
+            MSG     .STRINGZ "Hi; \"you\""
+            BUF     .BLKW #4
+            END     .FILL x0
+            .END
+
+            .ORIG x4000
+            table .fill 1
+            .END
+        "#);
+
+        eq!(symbols.addr("start"), Some(0x3000));
+        eq!(symbols.addr("LOOP"), Some(0x3002));
+        eq!(symbols.addr("msg"), Some(0x3005));
+        eq!(symbols.addr("buf"), Some(0x3005 + 10));
+        eq!(symbols.addr("end"), Some(0x3005 + 14));
+        eq!(symbols.addr("TABLE"), Some(0x4000));
+        eq!(symbols.addr("label"), None);
+
+        eq!(symbols.name(0x3002), Some("LOOP"));
+        eq!(symbols.describe(0x3004, 4), Some("LOOP+2".to_string()));
+        eq!(symbols.describe(0x2FFF, 4), None);
+    }
+
+    #[test]
+    fn numbers() {
+        eq!(parse_number("x3000"), Some(0x3000));
+        eq!(parse_number("0xff"), Some(0xFF));
+        eq!(parse_number("#10"), Some(10));
+        eq!(parse_number("b101"), Some(5));
+        eq!(parse_number("-1"), Some(0xFFFF));
+        eq!(parse_number("x10000"), None);
+        eq!(parse_number("loop"), None);
+    }
+
+    #[test]
+    fn targets() {
+        let symbols = Symbols::from_source(".ORIG x3000\nHEAD .FILL x4000\n.END");
+        let regs = [0, 0x5000, 0, 0, 0, 0, 0, 0];
+        let read = |addr: Addr| if addr == 0x3000 { 0x4000 } else { 0 };
+        let resolve = |target: &str| symbols.resolve(target, &regs, 0x3001, read);
+
+        eq!(resolve("head"), Ok(0x3000));
+        eq!(resolve("*HEAD"), Ok(0x4000));
+        eq!(resolve("R1"), Ok(0x5000));
+        eq!(resolve("pc"), Ok(0x3001));
+        eq!(resolve("x10"), Ok(0x10));
+        assert!(resolve("TAIL").is_err());
+    }
+}
@@ -0,0 +1,24 @@
+//! A clock for timestamps (input script delays, transcripts, the log).
+
+// Milliseconds since the TUI started (more or less: since this was first
+// called).
+specialize! {
+    desktop => {
+        pub(in crate) fn now() -> u64 {
+            lazy_static::lazy_static! {
+                static ref START: std::time::Instant = std::time::Instant::now();
+            }
+
+            START.elapsed().as_millis() as u64
+        }
+    }
+    web => {
+        pub(in crate) fn now() -> u64 {
+            lazy_static::lazy_static! {
+                static ref START: f64 = js_sys::Date::now();
+            }
+
+            (js_sys::Date::now() - *START) as u64
+        }
+    }
+}
@@ -0,0 +1,179 @@
+//! Keeps track of when the timers fire.
+//!
+//! The simulator doesn't tell us when a timer goes off, so we work it out
+//! from when the program set the timer up (or, for timers that were already
+//! running when we started watching, from when we first saw them): timers
+//! count real time, like [`now`](super::time::now), but only while the
+//! program is running.
+//!
+//! We don't see the interrupt being taken either so the PC recorded for a fire
+//! is just the one we saw on the first update after it; a few fires in one
+//! update all get the same PC.
+
+use lc3_isa::Addr;
+
+use std::collections::VecDeque;
+
+/// How many fires we remember (per timer).
+pub const RECENT_FIRES: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Fire {
+    /// When (see `now`).
+    pub at: u64,
+    /// The PC as of the first update after the fire (_not_ where the
+    /// interrupt was taken).
+    pub pc: Addr,
+}
+
+/// A timer's period (in milliseconds) and whether it repeats.
+pub type TimerConfig = (u64, bool);
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct TimerHistory {
+    config: Option<TimerConfig>,
+    /// When the timer was set up with `config` (pushed back by however long
+    /// the program has been stopped for since).
+    start: u64,
+    /// When we last caught up.
+    last: u64,
+    /// How many times the timer has fired with `config`.
+    fired_since_start: u64,
+    /// How many times the timer has fired in all.
+    pub fire_count: u64,
+    pub recent: VecDeque<Fire>,
+}
+
+impl TimerHistory {
+    /// Catches up with the timer: `config` is its current setup (`None` if
+    /// it's disabled), `pc` is where the program is and `running` is whether
+    /// it's running (time spent paused or halted doesn't count).
+    pub fn observe(&mut self, config: Option<TimerConfig>, now: u64, pc: Addr, running: bool) {
+        let since_last = now.saturating_sub(self.last);
+        self.last = now;
+
+        if config != self.config {
+            self.config = config;
+            self.start = now;
+            self.fired_since_start = 0;
+            return;
+        }
+
+        let (period, repeated) = match config {
+            Some(c) => c,
+            None => return,
+        };
+
+        if !running {
+            self.start += since_last;
+            return;
+        }
+
+        let elapsed = now.saturating_sub(self.start);
+        let due = if repeated { elapsed / period } else { (elapsed >= period) as u64 };
+        if due <= self.fired_since_start {
+            return;
+        }
+
+        // Only the most recent fires are kept (if we fell far behind):
+        let missed = due - self.fired_since_start;
+        for n in (due - missed.min(RECENT_FIRES as u64))..due {
+            if self.recent.len() == RECENT_FIRES {
+                let _ = self.recent.pop_front();
+            }
+
+            self.recent.push_back(Fire { at: self.start + (n + 1) * period, pc });
+        }
+
+        self.fire_count += missed;
+        self.fired_since_start = due;
+    }
+
+    /// How long until the timer next fires; `None` if it's disabled or was a
+    /// single shot timer that's already gone off.
+    pub fn remaining(&self, now: u64) -> Option<u64> {
+        let (period, repeated) = self.config?;
+        let elapsed = now.saturating_sub(self.start);
+
+        match repeated {
+            true => Some(period - elapsed % period),
+            false if elapsed < period => Some(period - elapsed),
+            false => None,
+        }
+    }
+
+    pub fn config(&self) -> Option<TimerConfig> {
+        self.config
+    }
+}
+
+#[cfg(test)]
+mod timer_history_tests {
+    use super::*;
+    use pretty_assertions::assert_eq as eq;
+
+    fn fires(history: &TimerHistory) -> Vec<(u64, Addr)> {
+        history.recent.iter().map(|f| (f.at, f.pc)).collect()
+    }
+
+    #[test]
+    fn repeated() {
+        let mut t = TimerHistory::default();
+        t.observe(Some((100, true)), 1000, 0x3000, true);
+        eq!(t.remaining(1030), Some(70));
+
+        t.observe(Some((100, true)), 1099, 0x3001, true);
+        eq!(t.fire_count, 0);
+
+        t.observe(Some((100, true)), 1250, 0x3002, true);
+        eq!(fires(&t), vec![(1100, 0x3002), (1200, 0x3002)]);
+        eq!(t.remaining(1250), Some(50));
+
+        // Changing the period starts it over:
+        t.observe(Some((50, true)), 1260, 0x3003, true);
+        t.observe(Some((50, true)), 1310, 0x3004, true);
+        eq!(t.fire_count, 3);
+        eq!(t.recent.back(), Some(&Fire { at: 1310, pc: 0x3004 }));
+
+        t.observe(None, 1400, 0x3005, true);
+        eq!(t.remaining(1400), None);
+    }
+
+    #[test]
+    fn single_shot() {
+        let mut t = TimerHistory::default();
+        t.observe(Some((100, false)), 0, 0x3000, true);
+        t.observe(Some((100, false)), 500, 0x3001, true);
+        t.observe(Some((100, false)), 900, 0x3002, true);
+
+        eq!(fires(&t), vec![(100, 0x3001)]);
+        eq!(t.remaining(900), None);
+    }
+
+    #[test]
+    fn stopped() {
+        let mut t = TimerHistory::default();
+        t.observe(Some((100, true)), 0, 0x3000, true);
+        t.observe(Some((100, true)), 50, 0x3001, true);
+
+        // Paused for a second:
+        t.observe(Some((100, true)), 500, 0x3001, false);
+        t.observe(Some((100, true)), 1050, 0x3001, false);
+        eq!(t.fire_count, 0);
+        eq!(t.remaining(1050), Some(50));
+
+        t.observe(Some((100, true)), 1120, 0x3002, true);
+        eq!(fires(&t), vec![(1100, 0x3002)]);
+    }
+
+    #[test]
+    fn falling_behind() {
+        let mut t = TimerHistory::default();
+        t.observe(Some((1, true)), 0, 0x3000, true);
+        t.observe(Some((1, true)), 1000, 0x3000, true);
+
+        eq!(t.fire_count, 1000);
+        eq!(t.recent.len(), RECENT_FIRES);
+        eq!(t.recent.back().map(|f| f.at), Some(1000));
+    }
+}
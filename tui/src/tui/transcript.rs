@@ -0,0 +1,222 @@
+//! A record of what went through the console (and when), for saving the
+//! console to a file.
+
+use super::time::now;
+
+use lc3_isa::Word;
+
+use std::fmt::Write as _;
+
+/// Which way a [`Chunk`] went.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Source {
+    /// Written by the program.
+    Output,
+    /// Taken by the program as input.
+    Input,
+}
+
+/// Text that arrived at one time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Chunk {
+    source: Source,
+    /// Milliseconds since the transcript was started.
+    at: u64,
+    /// The PC when the text arrived.
+    pc: Word,
+    text: String,
+}
+
+/// Everything the program has written to the console (escapes and all) and
+/// everything it has taken as input, timestamped.
+///
+/// The console history ([`AnsiTextContainer`](super::ansi::AnsiTextContainer))
+/// only holds on to the text; this is what's used for the raw and annotated
+/// transcripts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transcript {
+    start: u64,
+    raw: String,
+    chunks: Vec<Chunk>,
+}
+
+impl Default for Transcript {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Transcript {
+    pub fn new() -> Self {
+        Self {
+            start: now(),
+            raw: String::new(),
+            chunks: Vec::new(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Records output from the program.
+    pub fn output(&mut self, text: &str, pc: Word) {
+        if text.is_empty() { return; }
+
+        self.raw.push_str(text);
+        self.chunks.push(Chunk { source: Source::Output, at: self.elapsed(), pc, text: text.to_string() });
+    }
+
+    /// Records a character the program took as input.
+    ///
+    /// Characters are grouped into lines: a line's timestamp and PC are from
+    /// when its first character was taken.
+    pub fn input(&mut self, c: char, pc: Word) {
+        match self.chunks.last_mut() {
+            Some(Chunk { source: Source::Input, text, .. }) if !text.ends_with('\n') => text.push(c),
+            _ => {
+                let at = self.elapsed();
+                self.chunks.push(Chunk { source: Source::Input, at, pc, text: c.to_string() })
+            },
+        }
+    }
+
+    /// The program's output exactly as it was written (ANSI escapes
+    /// included).
+    pub fn raw(&self) -> &str {
+        &self.raw
+    }
+
+    /// The output, without escapes, with input lines interleaved. Every line
+    /// starts with when it arrived and what the PC was at the time; input
+    /// lines are marked with `>`.
+    pub fn annotated(&self) -> String {
+        let mut out = String::new();
+        let mut strip = Stripper::default();
+        let mut line_start = true;
+        let mut last_source = Source::Output;
+
+        for chunk in self.chunks.iter() {
+            let text = match chunk.source {
+                Source::Output => strip.strip(&chunk.text),
+                Source::Input => chunk.text.clone(),
+            };
+
+            // Input and output don't share lines:
+            if chunk.source != last_source && !line_start {
+                out.push('\n');
+                line_start = true;
+            }
+            last_source = chunk.source;
+
+            for c in text.chars().filter(|c| *c != '\r') {
+                if line_start {
+                    let marker = match chunk.source { Source::Output => ' ', Source::Input => '>' };
+                    let _ = write!(out, "[{:>4}.{:03}s PC {:#06x}] {} ", chunk.at / 1000, chunk.at % 1000, chunk.pc, marker);
+                    line_start = false;
+                }
+
+                out.push(c);
+                line_start = c == '\n';
+            }
+        }
+
+        if !line_start {
+            out.push('\n');
+        }
+
+        out
+    }
+
+    fn elapsed(&self) -> u64 {
+        now().saturating_sub(self.start)
+    }
+}
+
+// Drops escape sequences; keeps state across calls since sequences can be
+// split between chunks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+struct Stripper {
+    state: StripState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StripState {
+    Text,
+    /// Just saw an `ESC`.
+    Escape,
+    /// In a `ESC [` sequence; ends with a character in `@..=~`.
+    Csi,
+    /// The character after `ESC (` or `ESC )`.
+    Charset,
+}
+
+impl Default for StripState {
+    fn default() -> Self {
+        StripState::Text
+    }
+}
+
+impl Stripper {
+    fn strip(&mut self, text: &str) -> String {
+        use StripState::*;
+        const ESC: char = 0x1B as char;
+
+        let mut out = String::with_capacity(text.len());
+        for c in text.chars() {
+            self.state = match (self.state, c) {
+                (Text, ESC) => Escape,
+                (Text, c) => { out.push(c); Text },
+                (Escape, '[') => Csi,
+                (Escape, '(') | (Escape, ')') => Charset,
+                (Escape, _) | (Charset, _) => Text,
+                (Csi, '@'..='~') => Text,
+                (Csi, _) => Csi,
+            };
+        }
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod transcript_tests {
+    use super::*;
+    use pretty_assertions::assert_eq as eq;
+
+    fn chunk(source: Source, at: u64, pc: Word, text: &str) -> Chunk {
+        Chunk { source, at, pc, text: text.to_string() }
+    }
+
+    #[test]
+    fn stripping() {
+        let mut s = Stripper::default();
+        eq!(s.strip("\x1b[1;31mred\x1b[0m \x1b(Bplain"), "red plain");
+        eq!(s.strip("a\x1b[3"), "a");
+        eq!(s.strip("2mb"), "b");
+    }
+
+    #[test]
+    fn input_lines() {
+        let mut t = Transcript::new();
+        "hi\nyo".chars().for_each(|c| t.input(c, 0x3000));
+        eq!(t.chunks.iter().map(|c| c.text.as_str()).collect::<Vec<_>>(), vec!["hi\n", "yo"]);
+    }
+
+    #[test]
+    fn annotating() {
+        let mut t = Transcript::new();
+        t.chunks = vec![
+            chunk(Source::Output, 5, 0x3002, "Name: \x1b[1m"),
+            chunk(Source::Input, 1250, 0x3004, "Bob\n"),
+            chunk(Source::Output, 1300, 0x3008, "Hi Bob!\nBye"),
+        ];
+
+        eq!(t.annotated(), concat!(
+            "[   0.005s PC 0x3002]   Name: \n",
+            "[   1.250s PC 0x3004] > Bob\n",
+            "[   1.300s PC 0x3008]   Hi Bob!\n",
+            "[   1.300s PC 0x3008]   Bye\n",
+        ));
+    }
+}
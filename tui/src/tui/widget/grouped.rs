@@ -2,6 +2,7 @@
 
 use crate::tui::TuiData;
 use crate::tui::events::{WidgetEvent, FocusEvent};
+use crate::keymap::{Action, Scope};
 use super::single::SingleWidget;
 use super::Widget;
 
@@ -11,9 +12,10 @@ use lc3_traits::control::Control;
 use tui::backend::Backend;
 use tui::buffer::Buffer;
 use tui::layout::{Layout, Direction, Constraint, Rect};
+use tui::style::Color;
 use tui::widgets::Block;
 use tui::terminal::Terminal;
-use crossterm::event::{MouseEvent, MouseButton, KeyEvent, KeyCode, KeyModifiers};
+use crossterm::event::{MouseEvent, MouseButton, KeyEvent};
 
 
 /// A bunch of Widgets that split the are they are given in *one* direction. In
@@ -40,8 +42,23 @@ where
     /// The index of the widget to dispatch events to.
     focused: Option<usize>,
     previously_focused: Option<usize>,
+    /// Used to save the sizes of the widgets within to the user's config.
+    id: Option<String>,
+    /// The area we were last drawn in.
+    area: Rect,
+    /// Whether we've applied the sizes saved in the user's config yet.
+    saved_sizes_applied: bool,
+    /// The pair of widgets whose shared border is being dragged.
+    dragging: Option<(usize, usize)>,
+    /// The index of the widget that's been maximised, if any.
+    zoomed: Option<usize>,
 }
 
+/// Widgets can't be resized to be smaller than this.
+const MIN_PERCENTAGE: u16 = 5;
+/// How much the resize key bindings grow or shrink widgets by.
+const RESIZE_STEP: u16 = 5;
+
 impl<'a, 'int, C, I, O, B> Widgets<'a, 'int, C, I, O, B>
 where
     C: Control + ?Sized + 'a,
@@ -56,9 +73,21 @@ where
             areas_valid: false,
             focused: None,
             previously_focused: None,
+            id: None,
+            area: Rect::default(),
+            saved_sizes_applied: false,
+            dragging: None,
+            zoomed: None,
         }
     }
 
+    /// Widgets that have an id have their sizes saved in (and restored from)
+    /// the user's config when they're resized.
+    pub fn set_id<S: ToString>(&mut self, id: S) -> &mut Self {
+        self.id = Some(id.to_string());
+        self
+    }
+
     // `block` is optional; widgets that wish to manage their block themselves
     // (or don't want a `Block`) are free to not use this.
     //
@@ -77,25 +106,68 @@ where
         self
     }
 
+    // Blocks added with `add_widget` use the palette's border colour; this uses
+    // the colour given instead (except when focused).
+    pub fn add_widget_with_border_colour<W>(&mut self, constraint: Constraint, widget: W, block: Block<'a>, colour: Color) -> &mut Self
+    where
+        W: Widget<'a, 'int, C, I, O, B> + 'a
+    {
+        let mut single = SingleWidget::new(constraint, Box::new(widget), Some(block));
+        single.border_colour = Some(colour);
+
+        self.widgets.push(single);
+        self.areas_valid = false;
+
+        self
+    }
+
     fn update_areas(&mut self, area: Rect) {
-        if !self.areas_valid {
+        if !self.areas_valid || self.area != area {
             let layout = self.layout.clone();
 
-            let constraints: Vec<_> = self.widgets
+            // Hidden widgets (collapsed ones or everything but the zoomed
+            // widget) don't get any space:
+            let visible: Vec<usize> = (0..self.widgets.len())
+                .filter(|idx| self.is_visible(*idx))
+                .collect();
+
+            let mut constraints: Vec<_> = visible
                 .iter()
-                .map(|w| w.constraint)
+                .map(|idx| self.widgets[*idx].constraint)
                 .collect();
 
+            // If some widgets are hidden, scale up the percentages of the ones
+            // that are left so that they fill the space:
+            if visible.len() != self.widgets.len() {
+                let total: u16 = constraints.iter().map(|c| match c {
+                    Constraint::Percentage(p) => *p,
+                    _ => 0,
+                }).sum();
+
+                if total > 0 {
+                    for c in constraints.iter_mut() {
+                        if let Constraint::Percentage(p) = c {
+                            *p = (*p as u32 * 100 / total as u32) as u16;
+                        }
+                    }
+                }
+            }
+
             let rects = layout
                 .constraints(constraints)
                 .split(area);
 
-            assert_eq!(self.widgets.len(), rects.len());
+            assert_eq!(visible.len(), rects.len());
+
+            for w in self.widgets.iter_mut() {
+                w.area = Rect::new(area.x, area.y, 0, 0);
+            }
 
-            for (idx, rect) in rects.iter().enumerate() {
-                self.widgets[idx].area = *rect;
+            for (idx, rect) in visible.into_iter().zip(rects) {
+                self.widgets[idx].area = rect;
             }
 
+            self.area = area;
             self.areas_valid = true;
         }
     }
@@ -127,14 +199,212 @@ where
     }
 }
 
-const fn with_control(code: KeyCode) -> KeyEvent {
-    KeyEvent { code, modifiers: KeyModifiers::CONTROL }
-}
+// Resizing.
+impl<'a, 'int, C, I, O, B> Widgets<'a, 'int, C, I, O, B>
+where
+    C: Control + ?Sized + 'a,
+    I: InputSink + ?Sized + 'a,
+    O: OutputSource + ?Sized + 'a,
+    B: Backend,
+{
+    fn is_visible(&self, idx: usize) -> bool {
+        match self.zoomed {
+            Some(z) => z == idx,
+            None => !self.widgets[idx].collapsed,
+        }
+    }
 
-const UP: KeyEvent = with_control(KeyCode::Up);
-const DOWN: KeyEvent = with_control(KeyCode::Down);
-const LEFT: KeyEvent = with_control(KeyCode::Left);
-const RIGHT: KeyEvent = with_control(KeyCode::Right);
+    // The length of a `Rect` along the direction we split in.
+    fn length(&self, area: Rect) -> u16 {
+        match extract_direction_from_layout(&self.layout) {
+            Direction::Horizontal => area.width,
+            Direction::Vertical => area.height,
+        }
+    }
+
+    // The current sizes of the widgets within, as percentages.
+    //
+    // Widgets that weren't given percentages to begin with (i.e. ones with a
+    // fixed length) are converted using the area they were last drawn in.
+    fn percentages(&self) -> Vec<u16> {
+        let percentages: Option<Vec<u16>> = self.widgets
+            .iter()
+            .map(|w| match w.constraint {
+                Constraint::Percentage(p) => Some(p),
+                _ => None,
+            })
+            .collect();
+
+        if let Some(p) = percentages {
+            return p;
+        }
+
+        let total: u32 = self.widgets.iter().map(|w| self.length(w.area) as u32).sum();
+        let even_split = (100 / self.widgets.len().max(1) as u16).max(MIN_PERCENTAGE);
+
+        self.widgets
+            .iter()
+            .map(|w| match self.length(w.area) as u32 {
+                0 => even_split,
+                l => (l * 100 / total) as u16,
+            })
+            .collect()
+    }
+
+    fn set_percentages(&mut self, percentages: &[u16], data: &mut TuiData<'a, 'int, C, I, O>) {
+        for (w, p) in self.widgets.iter_mut().zip(percentages) {
+            w.constraint = Constraint::Percentage(*p);
+        }
+
+        self.areas_valid = false;
+
+        if let Some(ref id) = self.id {
+            let _ = data.config.panes.insert(id.clone(), percentages.to_vec());
+            data.config_changed = true;
+        }
+    }
+
+    // Applies the sizes saved in the user's config, if there are any.
+    fn apply_saved_sizes(&mut self, data: &TuiData<'a, 'int, C, I, O>) {
+        self.saved_sizes_applied = true;
+
+        let saved = self.id.as_ref().and_then(|id| data.config.panes.get(id));
+        if let Some(sizes) = saved.filter(|s| s.len() == self.widgets.len()) {
+            for (w, p) in self.widgets.iter_mut().zip(sizes) {
+                w.constraint = Constraint::Percentage(*p);
+            }
+
+            self.areas_valid = false;
+        }
+    }
+
+    // Grows (or shrinks) the widget at `idx` by taking space from (or giving
+    // space to) its next visible neighbour.
+    fn resize(&mut self, idx: usize, grow: bool, data: &mut TuiData<'a, 'int, C, I, O>) -> bool {
+        let neighbour = (idx + 1..self.widgets.len())
+            .find(|i| self.is_visible(*i))
+            .or_else(|| (0..idx).rev().find(|i| self.is_visible(*i)));
+
+        let neighbour = if let Some(n) = neighbour { n } else { return false };
+
+        let mut percentages = self.percentages();
+        let (from, to) = if grow { (neighbour, idx) } else { (idx, neighbour) };
+
+        let step = RESIZE_STEP.min(percentages[from].saturating_sub(MIN_PERCENTAGE));
+        if step == 0 {
+            return false;
+        }
+
+        percentages[from] -= step;
+        percentages[to] += step;
+        self.set_percentages(&percentages, data);
+
+        true
+    }
+
+    // Returns the pair of (visible) widgets whose shared border is at the
+    // given position.
+    fn border_at(&self, col: u16, row: u16) -> Option<(usize, usize)> {
+        if self.zoomed.is_some() {
+            return None;
+        }
+
+        let visible: Vec<usize> = (0..self.widgets.len())
+            .filter(|idx| self.is_visible(*idx))
+            .collect();
+
+        visible
+            .windows(2)
+            .find(|pair| {
+                let (a, b) = (self.widgets[pair[0]].area, self.widgets[pair[1]].area);
+
+                match extract_direction_from_layout(&self.layout) {
+                    Direction::Horizontal => (a.top()..a.bottom()).contains(&row)
+                        && (col + 1 == a.right() || col == b.left()),
+                    Direction::Vertical => (a.left()..a.right()).contains(&col)
+                        && (row + 1 == a.bottom() || row == b.top()),
+                }
+            })
+            .map(|pair| (pair[0], pair[1]))
+    }
+
+    // Moves the border between `a` and `b` to the given position.
+    fn drag_border(&mut self, (a, b): (usize, usize), col: u16, row: u16, data: &mut TuiData<'a, 'int, C, I, O>) {
+        let (start, pos) = match extract_direction_from_layout(&self.layout) {
+            Direction::Horizontal => (self.widgets[a].area.left(), col),
+            Direction::Vertical => (self.widgets[a].area.top(), row),
+        };
+
+        let total = self.length(self.area) as u32;
+        if total == 0 {
+            return;
+        }
+
+        let mut percentages = self.percentages();
+        let combined = percentages[a] + percentages[b];
+
+        let new = ((pos.saturating_sub(start) as u32 + 1) * 100 / total) as u16;
+        let new = new.max(MIN_PERCENTAGE).min(combined.saturating_sub(MIN_PERCENTAGE));
+
+        percentages[a] = new;
+        percentages[b] = combined - new;
+        self.set_percentages(&percentages, data);
+    }
+
+    // Hides the focused widget and focuses one of its neighbours instead.
+    //
+    // Does nothing if none of the other widgets will accept focus.
+    fn collapse_focused(&mut self, data: &mut TuiData<'a, 'int, C, I, O>, terminal: &mut Terminal<B>) -> bool {
+        use WidgetEvent::Focus;
+
+        let idx = match (self.focused, self.zoomed) {
+            (Some(idx), None) => idx,
+            _ => return false,
+        };
+
+        let candidates: Vec<usize> = (idx + 1..self.widgets.len())
+            .chain((0..idx).rev())
+            .filter(|i| self.is_visible(*i))
+            .collect();
+
+        for c in candidates {
+            if self.widgets[c].widget.update(Focus(FocusEvent::GotFocus), data, terminal) {
+                let _ = self.propagate_to_focused(Focus(FocusEvent::LostFocus), data, terminal);
+
+                self.widgets[idx].collapsed = true;
+                self.focused = Some(c);
+                self.areas_valid = false;
+
+                return true;
+            } else {
+                let _ = self.widgets[c].widget.update(Focus(FocusEvent::LostFocus), data, terminal);
+            }
+        }
+
+        false
+    }
+
+    // Unhides collapsed widgets and restores the sizes we were created with.
+    fn reset_sizes(&mut self, data: &mut TuiData<'a, 'int, C, I, O>) -> bool {
+        let changed = self.widgets
+            .iter()
+            .any(|w| w.collapsed || w.constraint != w.default_constraint);
+
+        for w in self.widgets.iter_mut() {
+            w.collapsed = false;
+            w.constraint = w.default_constraint;
+        }
+
+        if let Some(ref id) = self.id {
+            if data.config.panes.remove(id).is_some() {
+                data.config_changed = true;
+            }
+        }
+
+        self.areas_valid = false;
+        changed
+    }
+}
 
 impl<'a, 'int, C, I, O, B> Widgets<'a, 'int, C, I, O, B>
 where
@@ -143,10 +413,11 @@ where
     O: OutputSource + ?Sized + 'a,
     B: Backend,
 {
-    fn handle_focus_key_event(&mut self, event: KeyEvent, data: &mut TuiData<'a, 'int, C, I, O>, terminal: &mut Terminal<B>) -> bool {
+    fn handle_focus_key_event(&mut self, action: Action, event: KeyEvent, data: &mut TuiData<'a, 'int, C, I, O>, terminal: &mut Terminal<B>) -> bool {
         use WidgetEvent::{Focus, Key};
+        use Action::{FocusUp, FocusDown, FocusLeft, FocusRight};
 
-        if let UP | DOWN | LEFT | RIGHT = event { } else {
+        if let FocusUp | FocusDown | FocusLeft | FocusRight = action { } else {
             panic!("Called the focus key event handler without a focus key event!")
         }
 
@@ -156,9 +427,9 @@ where
         // children would never _not_ handle these events and we'd never be able
         // to break out of nested Widgets.
         use Direction::*;
-        match (dir, event) {
-            (Vertical, UP) | (Vertical, DOWN) |
-            (Horizontal, LEFT) | (Horizontal, RIGHT) => {
+        match (dir, action) {
+            (Vertical, FocusUp) | (Vertical, FocusDown) |
+            (Horizontal, FocusLeft) | (Horizontal, FocusRight) => {
                 // First let's check if our focused thing can handle this:
                 if self.propagate_to_focused(Key(event), data, terminal) {
                     true
@@ -171,14 +442,18 @@ where
                     if let Some(focused_idx) = self.focused {
                         let mut focused_idx = focused_idx;
                         let new_idx = loop {
-                            if let Some(n) = match event {
-                                UP | LEFT => focused_idx.checked_sub(1),
-                                DOWN | RIGHT => focused_idx.checked_add(1),
+                            if let Some(n) = match action {
+                                FocusUp | FocusLeft => focused_idx.checked_sub(1),
+                                FocusDown | FocusRight => focused_idx.checked_add(1),
                                 _ => unreachable!(), // Obvious to us; not rustc :-/
                             }
                             .filter(|i| (0..self.widgets.len()).contains(i)) {
                                 focused_idx = n;
 
+                                if !self.is_visible(n) {
+                                    continue;
+                                }
+
                                 let accepted = self.widgets[n].widget.update(Focus(FocusEvent::GotFocus), data, terminal);
                                 if accepted {
                                     break Some(n)
@@ -216,13 +491,47 @@ where
 
             // If the key event doesn't match us, send it below
             // and return.
-            (Vertical, LEFT) | (Vertical, RIGHT) |
-            (Horizontal, UP) | (Horizontal, DOWN) => {
+            (Vertical, FocusLeft) | (Vertical, FocusRight) |
+            (Horizontal, FocusUp) | (Horizontal, FocusDown) => {
                 self.propagate_to_focused(Key(event), data, terminal)
             }
             _ => unreachable!(), // Unnamed union types.. we long for ye
         }
     }
+
+    fn handle_pane_key_event(&mut self, action: Action, event: KeyEvent, data: &mut TuiData<'a, 'int, C, I, O>, terminal: &mut Terminal<B>) -> bool {
+        use WidgetEvent::Key;
+        use Action::{GrowPane, ShrinkPane, ZoomPane, CollapsePane, ResetPanes};
+
+        // Zooming happens at every level so that the focused widget fills the
+        // whole tab:
+        if let ZoomPane = action {
+            let _ = self.propagate_to_focused(Key(event), data, terminal);
+
+            self.zoomed = match self.zoomed {
+                Some(_) => None,
+                None => self.focused,
+            };
+            self.areas_valid = false;
+
+            return self.focused.is_some();
+        }
+
+        // Everything else is handled by the innermost `Widgets` that can:
+        if self.propagate_to_focused(Key(event), data, terminal) {
+            return true;
+        }
+
+        match action {
+            GrowPane | ShrinkPane => match (self.focused, self.zoomed) {
+                (Some(idx), None) => self.resize(idx, action == GrowPane, data),
+                _ => false,
+            },
+            CollapsePane => self.collapse_focused(data, terminal),
+            ResetPanes => self.reset_sizes(data),
+            _ => panic!("Called the pane key event handler without a pane key event!"),
+        }
+    }
 }
 
 impl<'a, 'int, C, I, O, B> Widget<'a, 'int, C, I, O, B> for Widgets<'a, 'int, C, I, O, B>
@@ -233,9 +542,18 @@ where
     B: Backend,
 {
     fn draw(&mut self, data: &TuiData<'a, 'int, C, I, O>, rect: Rect, buf: &mut Buffer) {
+        if !self.saved_sizes_applied {
+            self.apply_saved_sizes(data);
+        }
+
         self.update_areas(rect);
 
         for (idx, sw) in self.widgets.iter_mut().enumerate() {
+            // Skip hidden widgets:
+            if sw.area.area() == 0 {
+                continue;
+            }
+
             sw.draw(data, buf, self.focused.map(|f| f == idx).unwrap_or(false))
         }
     }
@@ -265,8 +583,9 @@ where
                             }
                         } else {
                             // If we don't cycle through what we've got:
-                            for (idx, w) in self.widgets.iter_mut().enumerate() {
-                                if w.widget.update(event, data, terminal) {
+                            // (skipping hidden widgets)
+                            for idx in 0..self.widgets.len() {
+                                if self.is_visible(idx) && self.widgets[idx].widget.update(event, data, terminal) {
                                     break 'outer Some(idx)
                                 }
                             }
@@ -295,7 +614,29 @@ where
             Mouse(e) => {
                 use MouseEvent::*;
 
+                // Clicking on the border between two widgets starts a resize:
+                if let Down(MouseButton::Left, col, row, _) = e {
+                    if let Some(pair) = self.border_at(col, row) {
+                        self.dragging = Some(pair);
+                        data.dragging_split = true;
+
+                        return true;
+                    }
+                }
+
                 match e {
+                    Up(_, _, _, _) if self.dragging.is_some() => {
+                        self.dragging = None;
+                        data.dragging_split = false;
+
+                        true
+                    },
+                    // One of our children is resizing; make sure it gets the
+                    // event even if the mouse has wandered off of it.
+                    Up(_, _, _, _) if data.dragging_split => {
+                        self.propagate_to_focused(event, data, terminal)
+                    },
+
                     // We don't care about buttons, up or down, or modifiers for
                     // focus purposes; all can change the currently focused
                     // widget.
@@ -306,7 +647,7 @@ where
                         // to click based on the last drawn frame anyways.
                         let new_focused_idx = self.widgets.iter()
                             .enumerate()
-                            .filter(|(_, w)| w.area.contains(col, row))
+                            .filter(|(_, w)| w.area.area() > 0 && w.area.contains(col, row))
                             .map(|(idx, _)| idx)
                             .next();
 
@@ -342,7 +683,13 @@ where
                             }
                         }
                     },
-                    Drag(_, _, _, _) => { /* ignore drag events! */ false },
+                    Drag(_, col, row, _) => match self.dragging {
+                        Some(pair) => {
+                            self.drag_border(pair, col, row, data);
+                            true
+                        },
+                        None => self.propagate_to_focused(event, data, terminal),
+                    },
                     ScrollDown(_, _, _) | ScrollUp(_, _, _) => {
                         // Just propagate scroll events:
                         self.propagate_to_focused(event, data, terminal)
@@ -350,14 +697,23 @@ where
                 }
             }
 
-            Key(e) => match e {
-                UP | DOWN | LEFT | RIGHT => self.handle_focus_key_event(e, data, terminal),
-
+            // The focused widget gets first dibs (i.e. while it has a prompt
+            // open):
+            Key(e) if self.wants_key(&e, data) => self.propagate_to_focused(event, data, terminal),
+            Key(e) => if let Some(action) = data.keymap.lookup(&e, Scope::Focus) {
+                self.handle_focus_key_event(action, e, data, terminal)
+            } else if let Some(action) = data.keymap.lookup(&e, Scope::Panes) {
+                self.handle_pane_key_event(action, e, data, terminal)
+            } else {
                 // For events that don't change the focus, just propagate:
-                _ => self.propagate_to_focused(event, data, terminal),
+                self.propagate_to_focused(event, data, terminal)
             }
         }
     }
+
+    fn wants_key(&self, key: &KeyEvent, data: &TuiData<'a, 'int, C, I, O>) -> bool {
+        self.focused.map_or(false, |idx| self.widgets[idx].widget.wants_key(key, data))
+    }
 }
 
 /// `Layout.direction` is private and we'd rather not clutter out API by making
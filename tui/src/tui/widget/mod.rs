@@ -14,6 +14,8 @@ use tui::layout::Rect;
 use tui::terminal::Terminal;
 use tui::widgets::StatefulWidget;
 
+use crossterm::event::KeyEvent;
+
 use std::marker::PhantomData;
 
 
@@ -72,4 +74,40 @@ where
     // This is useful for events that must be handled only once (i.e. changing
     // which widget is currently focused).
     fn update(&mut self, event: WidgetEvent, data: &mut TuiData<'a, 'int, C, I, O>, terminal: &mut Terminal<B>) -> bool;
+
+    /// Whether we (a focused widget) want `key` before the usual bindings for
+    /// it (i.e. switching tabs, stepping) get a look in; widgets with a prompt
+    /// open want everything, for example.
+    ///
+    /// Widgets that hold other widgets should ask their focused widget.
+    fn wants_key(&self, _key: &KeyEvent, _data: &TuiData<'a, 'int, C, I, O>) -> bool {
+        false
+    }
+}
+
+// So that boxed widgets (i.e. ones built from a layout description) can be
+// used anywhere a widget can.
+impl<'a, 'int, C, I, O, B, W> Widget<'a, 'int, C, I, O, B> for Box<W>
+where
+    C: Control + ?Sized + 'a,
+    I: InputSink + ?Sized + 'a,
+    O: OutputSource + ?Sized + 'a,
+    B: Backend,
+    W: Widget<'a, 'int, C, I, O, B> + ?Sized,
+{
+    fn draw(&mut self, data: &TuiData<'a, 'int, C, I, O>, area: Rect, buf: &mut Buffer) {
+        (**self).draw(data, area, buf)
+    }
+
+    fn render<'s>(&'s mut self, data: &'s TuiData<'a, 'int, C, I, O>, f: &mut Frame<'_, B>, area: Rect) {
+        (**self).render(data, f, area)
+    }
+
+    fn update(&mut self, event: WidgetEvent, data: &mut TuiData<'a, 'int, C, I, O>, terminal: &mut Terminal<B>) -> bool {
+        (**self).update(event, data, terminal)
+    }
+
+    fn wants_key(&self, key: &KeyEvent, data: &TuiData<'a, 'int, C, I, O>) -> bool {
+        (**self).wants_key(key, data)
+    }
 }
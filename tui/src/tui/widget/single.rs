@@ -2,6 +2,7 @@
 
 use super::{Widget, TuiWidget};
 use crate::tui::TuiData;
+use crate::colours::c;
 
 use lc3_application_support::io_peripherals::InputSink;
 use lc3_application_support::io_peripherals::OutputSource;
@@ -24,7 +25,12 @@ where
 {
     pub(super) widget: Box<dyn Widget<'a, 'int, C, I, O, B> + 'a>,
     pub(super) block: Option<Block<'a>>,
+    /// Overrides the palette's border colour.
+    pub(super) border_colour: Option<Color>,
     pub(super) constraint: Constraint,
+    /// The constraint we were created with; used when resetting sizes.
+    pub(super) default_constraint: Constraint,
+    pub(super) collapsed: bool,
     pub(super) area: Rect,
 }
 
@@ -39,26 +45,33 @@ where
         Self {
             widget,
             block,
+            border_colour: None,
             constraint,
+            default_constraint: constraint,
+            collapsed: false,
             area: Rect::default(),
         }
     }
 
     pub(super) fn draw(&mut self, data: &TuiData<'a, 'int, C, I, O>, buf: &mut Buffer, focused: bool) {
         // If we have a block, draw it.
-        let area = if let Some(ref mut block) = self.block {
-            // Change the border colour of the block if we're focused.
-            if focused {
-                let mut block = block
-                    .clone()
-                    .border_style(Style::default().fg(Color::Red));
-
-                block.render(self.area, buf);
+        let area = if let Some(ref block) = self.block {
+            // Colours come from the current palette (so that they follow
+            // theme changes); the border changes colour if we're focused.
+            let border_colour = if focused {
+                c!(Focus)
             } else {
-                block.clone().render(self.area, buf);
-            }
+                self.border_colour.unwrap_or(c!(Border))
+            };
+
+            let block = block
+                .clone()
+                .border_style(Style::default().fg(border_colour))
+                .title_style(Style::default().fg(c!(Title)));
 
-            block.inner(self.area)
+            let inner = block.inner(self.area);
+            block.render(self.area, buf);
+            inner
         } else {
             self.area
         };
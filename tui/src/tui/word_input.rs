@@ -0,0 +1,157 @@
+//! Parses the values typed into the [`Mem`] and [`Regs`] views: numbers
+//! (`x3000`, `#10`, `-1`, `b101`), character literals (`'a'`, `'\n'`),
+//! labels (for their address) and instructions, which get assembled.
+//!
+//! The assembler only sees the one instruction, so labels used as PC-relative
+//! operands (i.e. `BRz LOOP`) are turned into offsets from where the
+//! instruction is going first.
+//!
+//! [`Mem`]: crate::widgets::Mem
+//! [`Regs`]: crate::widgets::Regs
+
+use super::mem_search::{decode, is_mnemonic};
+use super::symbols::{parse_number, Symbols};
+
+use lc3_isa::{Addr, Word};
+
+const EXPECTED: &str = "a number (x3000, #10, b101), a character ('a'), a label or an instruction";
+
+/// Parses `'a'`, `'\n'` and friends.
+pub fn char_literal(src: &str) -> Result<Word, String> {
+    let inner = src.strip_prefix('\'').and_then(|s| s.strip_suffix('\''))
+        .filter(|s| !s.is_empty())
+        .ok_or_else(|| format!("`{}` isn't a character; those look like 'a' or '\\n'", src))?;
+
+    let c = match inner {
+        "\\n" => '\n',
+        "\\t" => '\t',
+        "\\0" => '\0',
+        "\\'" => '\'',
+        "\\\\" => '\\',
+        _ => {
+            let mut chars = inner.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) if c != '\\' => c,
+                _ => return Err(format!("`{}` isn't a single character (escapes are \\n, \\t, \\0, \\' and \\\\)", src)),
+            }
+        },
+    };
+
+    match c as u32 {
+        c if c <= 0xFFFF => Ok(c as Word),
+        _ => Err(format!("`{}` doesn't fit in a word", src)),
+    }
+}
+
+/// Swaps labels used as PC-relative operands in `inst` for offsets from
+/// `addr` (where the instruction is going).
+pub fn resolve_labels(inst: &str, addr: Addr, symbols: &Symbols) -> Result<String, String> {
+    let inst = inst.trim();
+    let (mnemonic, operands) = match inst.split_once(char::is_whitespace) {
+        Some((m, ops)) => (m, ops),
+        None => return Ok(inst.to_string()),
+    };
+
+    let lower = mnemonic.to_lowercase();
+    let bits = match lower.as_str() {
+        "jsr" => 11,
+        "ld" | "ldi" | "lea" | "st" | "sti" => 9,
+        br if br.starts_with("br") => 9,
+        _ => return Ok(inst.to_string()),
+    };
+
+    let mut operands: Vec<String> = operands.split(',').map(|o| o.trim().to_string()).collect();
+    let last = operands.last_mut().expect("split always gives at least one piece");
+    if let (None, Some(target)) = (parse_number(last), symbols.addr(last)) {
+        let offset = target.wrapping_sub(addr.wrapping_add(1)) as i16;
+        let limit = 1i16 << (bits - 1);
+        if offset < -limit || offset >= limit {
+            return Err(format!(
+                "`{}` (x{:04X}) is {} words away from x{:04X}; {} can only reach {} to {}",
+                last, target, offset, addr, mnemonic.to_uppercase(), -limit, limit - 1,
+            ));
+        }
+
+        *last = format!("#{}", offset);
+    }
+
+    Ok(format!("{} {}", mnemonic, operands.join(", ")))
+}
+
+/// Works out the word `src` describes, for the word at `addr`; `assemble`
+/// assembles an instruction as if it were at `addr`.
+pub fn parse_word(src: &str, addr: Addr, symbols: &Symbols, assemble: impl FnOnce(&str, Addr) -> Result<Word, String>) -> Result<Word, String> {
+    let src = src.trim();
+    if src.is_empty() {
+        return Err(format!("Expected {}", EXPECTED));
+    }
+    if src.starts_with('\'') {
+        return char_literal(src);
+    }
+    if let Some(word) = parse_number(src) {
+        return Ok(word);
+    }
+
+    let first = src.split_whitespace().next().unwrap_or_default();
+    if is_mnemonic(first) {
+        let inst = resolve_labels(src, addr, symbols)?;
+        let word = assemble(&inst, addr)?;
+
+        // The assembler takes lines it doesn't understand as labels (and
+        // makes nothing out of them):
+        return match decode(word) {
+            Some(_) => Ok(word),
+            None => Err(format!("`{}` didn't assemble into an instruction", src)),
+        };
+    }
+
+    symbols.addr(src).ok_or_else(|| format!("`{}` isn't {}", src, EXPECTED))
+}
+
+#[cfg(test)]
+mod word_input_tests {
+    use super::*;
+    use pretty_assertions::assert_eq as eq;
+
+    #[test]
+    fn characters() {
+        eq!(char_literal("'a'"), Ok(0x61));
+        eq!(char_literal("'\\n'"), Ok(0x0A));
+        eq!(char_literal("'\\''"), Ok(0x27));
+        assert!(char_literal("'ab'").is_err());
+        assert!(char_literal("'").is_err());
+    }
+
+    #[test]
+    fn labels() {
+        let symbols = Symbols::from_source(".ORIG x3000\nLOOP ADD R1, R1, #-1\n.END\n.ORIG x5000\nFAR .FILL 0\n.END");
+
+        eq!(resolve_labels("BRp LOOP", 0x3004, &symbols), Ok("BRp #-5".to_string()));
+        eq!(resolve_labels("lea r0, loop", 0x2FF0, &symbols), Ok("lea r0, #15".to_string()));
+        eq!(resolve_labels("ADD R1, R1, #1", 0x3004, &symbols), Ok("ADD R1, R1, #1".to_string()));
+        eq!(resolve_labels("JSR LOOP", 0x3004, &symbols), Ok("JSR #-5".to_string()));
+        assert!(resolve_labels("JSR FAR", 0x3000, &symbols).is_err());
+        assert!(resolve_labels("LD R0, FAR", 0x3000, &symbols).unwrap_err().contains("can only reach -256 to 255"));
+    }
+
+    #[test]
+    fn words() {
+        let symbols = Symbols::from_source(".ORIG x3000\nLOOP HALT\n.END");
+        let never = |inst: &str, _| -> Result<Word, String> { panic!("`{}` shouldn't be assembled", inst) };
+        let parse = |src| parse_word(src, 0x3000, &symbols, never);
+
+        eq!(parse("x1234"), Ok(0x1234));
+        eq!(parse("#-1"), Ok(0xFFFF));
+        eq!(parse("b101"), Ok(5));
+        eq!(parse("'A'"), Ok(0x41));
+        eq!(parse("loop"), Ok(0x3000));
+        assert!(parse("nope").unwrap_err().starts_with("`nope` isn't"));
+        assert!(parse("").is_err());
+
+        eq!(parse_word("add r1, r1, #1", 0x3000, &symbols, |inst, addr| {
+            eq!((inst, addr), ("add r1, r1, #1", 0x3000));
+            Ok(0x1261)
+        }), Ok(0x1261));
+        assert!(parse_word("HALT", 0x3000, &symbols, |_, _| Ok(0)).is_err());
+    }
+}
@@ -1,18 +1,49 @@
-//! TODO!
+//! The ADC pins, as sliders that can be dragged or moved with keys; pins can
+//! also be driven by signal generators (see [`Signal`]).
 
 use super::widget_impl_support::*;
+use crate::keymap::{Action, Scope};
+use crate::tui::peripheral_commands::ADC_PINS;
+use crate::tui::prompt::{self, Outcome, Prompt};
+use crate::tui::signal::{Signal, DEFAULT_PERIOD};
 
-use lc3_traits::peripherals::adc::{AdcPin, AdcState};
+use lc3_traits::peripherals::adc::AdcState;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+use std::path::Path;
+
+/// How much the fast bindings move a slider by.
+const FAST_STEP: u8 = 16;
+/// The longest period the slower binding goes up to, in milliseconds.
+const MAX_PERIOD: u64 = 10 * 60 * 1000;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Adc {
+    /// Whether the pins can be driven from here (the widget is display-only
+    /// otherwise).
     pub focusable: bool,
+    focused: bool,
+    /// The pin keys act on.
+    selected: usize,
+    /// Where each pin's slider was last drawn, for clicks and drags.
+    sliders: [Rect; 6],
+    /// The pin whose slider is being dragged.
+    dragging: Option<usize>,
+    /// The path of a CSV file that's being typed in.
+    prompt: Option<Prompt>,
+    /// Why the last thing asked of us didn't happen.
+    error: Option<String>,
 }
 
 impl Default for Adc {
     fn default() -> Self {
         Self {
             focusable: false,
+            focused: false,
+            selected: 0,
+            sliders: [Rect::default(); 6],
+            dragging: None,
+            prompt: None,
+            error: None,
         }
     }
 }
@@ -22,6 +53,128 @@ impl Adc {
         self.focusable = focusable;
         self
     }
+
+    fn value<'a, 'int, C, I, O>(idx: usize, data: &TuiData<'a, 'int, C, I, O>) -> Option<u8>
+    where
+        C: Control + ?Sized + 'a,
+        I: InputSink + ?Sized + 'a,
+        O: OutputSource + ?Sized + 'a,
+    {
+        match data.sim.get_adc_readings()[ADC_PINS[idx]] {
+            Ok(value) => Some(value),
+            Err(_) => None,
+        }
+    }
+
+    fn set<'a, 'int, C, I, O>(&mut self, idx: usize, value: u8, data: &mut TuiData<'a, 'int, C, I, O>)
+    where
+        C: Control + ?Sized + 'a,
+        I: InputSink + ?Sized + 'a,
+        O: OutputSource + ?Sized + 'a,
+    {
+        self.selected = idx;
+
+        let error = data.set_adc_input(idx, value).err();
+        match &error {
+            // Dragging a slider makes lots of these; they only need logging once.
+            Some(err) if error != self.error => {
+                data.log_at(Level::Warn, format!("[ADC] {}\n", err), c!(InvalidInput))
+            },
+            _ => {},
+        }
+
+        self.error = error;
+    }
+
+    // Moves the selected pin's slider.
+    fn nudge<'a, 'int, C, I, O>(&mut self, by: i16, data: &mut TuiData<'a, 'int, C, I, O>)
+    where
+        C: Control + ?Sized + 'a,
+        I: InputSink + ?Sized + 'a,
+        O: OutputSource + ?Sized + 'a,
+    {
+        let current = Self::value(self.selected, data).unwrap_or(0) as i16;
+        self.set(self.selected, (current + by).max(0).min(255) as u8, data);
+    }
+
+    fn scroll<'a, 'int, C, I, O>(&mut self, col: u16, row: u16, by: i16, data: &mut TuiData<'a, 'int, C, I, O>)
+    where
+        C: Control + ?Sized + 'a,
+        I: InputSink + ?Sized + 'a,
+        O: OutputSource + ?Sized + 'a,
+    {
+        if let Some(idx) = self.pin_at(col, row) {
+            self.selected = idx;
+            self.nudge(by, data);
+        }
+    }
+
+    // The value for a click at `col` on a slider.
+    fn value_at(slider: Rect, col: u16) -> u8 {
+        let pos = col.saturating_sub(slider.x).min(slider.width.saturating_sub(1)) as u32;
+        (pos * 255 / (slider.width.max(2) as u32 - 1)) as u8
+    }
+
+    // Moves the selected pin on to the next generated signal (or back to no
+    // signal after the last one), keeping the period.
+    fn cycle_signal<'a, 'int, C, I, O>(&mut self, data: &mut TuiData<'a, 'int, C, I, O>)
+    where
+        C: Control + ?Sized + 'a,
+        I: InputSink + ?Sized + 'a,
+        O: OutputSource + ?Sized + 'a,
+    {
+        const ORDER: &[&str] = &["ramp", "sine", "square", "noise"];
+
+        let current = data.adc_signal(self.selected);
+        let period = current.and_then(Signal::period).unwrap_or(DEFAULT_PERIOD);
+        let next = match current.and_then(|s| ORDER.iter().position(|n| *n == s.name())) {
+            Some(idx) => ORDER.get(idx + 1),
+            None => ORDER.first(),
+        };
+
+        let signal = next.and_then(|name| Signal::waveform(name, 0, 255, period));
+        data.set_adc_signal(self.selected, signal);
+        self.error = None;
+    }
+
+    fn scale_period<'a, 'int, C, I, O>(&mut self, slower: bool, data: &mut TuiData<'a, 'int, C, I, O>)
+    where
+        C: Control + ?Sized + 'a,
+        I: InputSink + ?Sized + 'a,
+        O: OutputSource + ?Sized + 'a,
+    {
+        let signal = match data.adc_signal(self.selected) {
+            Some(s) => s,
+            None => return,
+        };
+
+        if let Some(period) = signal.period() {
+            let period = if slower { (period * 2).min(MAX_PERIOD) } else { period / 2 };
+            let signal = signal.with_period(period);
+            data.set_adc_signal(self.selected, Some(signal));
+        }
+    }
+
+    fn load_samples<'a, 'int, C, I, O>(&mut self, path: String, data: &mut TuiData<'a, 'int, C, I, O>)
+    where
+        C: Control + ?Sized + 'a,
+        I: InputSink + ?Sized + 'a,
+        O: OutputSource + ?Sized + 'a,
+    {
+        let path = path.trim();
+        if path.is_empty() {
+            return;
+        }
+
+        self.error = data.load_adc_samples(self.selected, Path::new(path)).err();
+        if let Some(err) = &self.error {
+            data.log_at(Level::Warn, format!("[ADC] {}\n", err), c!(InvalidInput));
+        }
+    }
+
+    fn pin_at(&self, col: u16, row: u16) -> Option<usize> {
+        self.sliders.iter().position(|r| r.intersects(Rect::new(col, row, 1, 1)))
+    }
 }
 
 impl<'a, 'int, C, I, O, B> Widget<'a, 'int, C, I, O, B> for Adc
@@ -31,129 +184,156 @@ where
     O: OutputSource + ?Sized + 'a,
     B: Backend,
 {
-
     fn draw(&mut self, data: &TuiData<'a, 'int, C, I, O>, area: Rect, buf: &mut Buffer) {
-        let adc_states = data.sim.get_adc_states();
-        let adcin = data.sim.get_adc_readings();
+        const LABEL: u16 = 6; // "ADC 0 "
+
+        let states = data.sim.get_adc_states();
 
+        // The slider gets what's left after the label and room for the value
+        // and the signal:
+        let slider_width = area.width.saturating_sub(LABEL + 28).max(4).min(48);
 
-        let text = [
-            TuiText::styled("ADC 0: \nADC 1: \nADC 2: \n", Style::default().fg(c!(Name))),
-        ];
+        for (idx, pin) in ADC_PINS.iter().enumerate() {
+            let row = idx as u16;
+            if row >= area.height {
+                self.sliders[idx] = Rect::default();
+                continue;
+            }
 
-        let mut para = Paragraph::new(text.iter())
-            .style(Style::default().fg(Colour::White).bg(Colour::Reset))
-            .alignment(Alignment::Left)
-            .wrap(true);
+            let mut label = Style::default().fg(c!(Name));
+            if self.focused && idx == self.selected {
+                label = label.modifier(Modifier::REVERSED);
+            }
 
-        para.render(area, buf);
+            let mut text = vec![TuiText::styled(format!("ADC {}", idx), label), TuiText::raw(" ")];
 
+            let enabled = matches!(states[*pin], AdcState::Enabled);
+            let value = Self::value(idx, data).filter(|_| enabled);
+            let filled = value.map(|v| (v as u32 * slider_width as u32 + 127) / 255).unwrap_or(0) as usize;
 
-        let mut s1 = String::from("");
+            let slider_colour = if enabled { c!(Data) } else { c!(Disabled) };
+            text.push(TuiText::styled("█".repeat(filled), Style::default().fg(slider_colour)));
+            text.push(TuiText::styled(
+                (if enabled { "░" } else { "·" }).repeat(slider_width as usize - filled),
+                Style::default().fg(c!(Disabled)),
+            ));
 
-        let adc_pins_1 = [AdcPin::A0, AdcPin::A1, AdcPin::A2];
-        let adc_pins_2 = [AdcPin::A3, AdcPin::A4, AdcPin::A5];
-        for i in 0..3 {
-            match adc_states[adc_pins_1[i]]{
-                AdcState::Disabled => {
-                    let disabled_string = "Disabled";
-                    s1.push_str(&format!("{}\n",
-                    disabled_string, ));
-                }
-                _ => {
-                    match adcin[adc_pins_1[i]] {
-                        Ok(val) => {
-                                s1.push_str(&format!(
-                                "{}\n",
-                                val,
-
-                                ));
-                            }
-                        _ => {
-                            let err_string = "-";
-                            s1.push_str(&format!(
-                                "{}\n",
-                                err_string,
-
-                                ));
-                        }
-                        }
-                }
-            }
-        }
+            let value = match value {
+                Some(v) => format!(" {:3}", v),
+                None if enabled => "   ?".to_string(),
+                None => " disabled".to_string(),
+            };
+            text.push(TuiText::styled(value, Style::default().fg(c!(Data))));
 
-        let text = [TuiText::styled(s1, Style::default().fg(c!(Data)))];
-        para = Paragraph::new(text.iter())
-            .style(Style::default().fg(Colour::White).bg(Colour::Reset))
-            .alignment(Alignment::Left)
-            .wrap(true);
-        let area = increment(10, Axis::X, area);
-        para.render(area, buf);
-
-
-
-        let text = [
-            TuiText::styled("ADC 3: \nADC 4: \nADC 5: \n", Style::default().fg(c!(Name))),
-            ];
-
-        let mut para = Paragraph::new(text.iter())
-        .style(Style::default().fg(Colour::White).bg(Colour::Reset))
-        .alignment(Alignment::Left)
-        .wrap(true);
-        let area = increment(40, Axis::X, area);
-        para.render(area, buf);
-
-        let mut s2 = String::from("");
-
-
-
-            for i in 0..3 {
-                match adc_states[adc_pins_2[i]]{
-                    AdcState::Disabled => {
-                        let disabled_string = "Disabled";
-                        s2.push_str(&format!("{}\n",
-                        disabled_string, ));
-                    }
-                    _ => {
-                        match adcin[adc_pins_2[i]] {
-                            Ok(val) => {
-                                    s2.push_str(&format!(
-                                    "{}\n",
-                                    val,
-
-                                    ));
-                                }
-                            _ => {
-                                let err_string = "-";
-                                s2.push_str(&format!(
-                                    "{}\n",
-                                    err_string,
-
-                                    ));
-                            }
-                            }
-                    }
-                }
+            if let Some(signal) = data.adc_signal(idx) {
+                text.push(TuiText::styled(format!(" ~ {}", signal), Style::default().fg(c!(Inst))));
             }
 
-            let text = [TuiText::styled(s2, Style::default().fg(c!(Data)))];
-            para = Paragraph::new(text.iter())
+            self.sliders[idx] = Rect::new(area.x + LABEL, area.y + row, slider_width.min(area.width.saturating_sub(LABEL)), 1);
+
+            Paragraph::new(text.iter())
                 .style(Style::default().fg(Colour::White).bg(Colour::Reset))
-                .alignment(Alignment::Left)
-                .wrap(true);
+                .render(Rect { y: area.y + row, height: 1, ..area }, buf);
+        }
 
-            let area = increment(10, Axis::X, area);
-            para.render(area, buf);
+        let pin_rows = ADC_PINS.len() as u16;
+        if !self.focusable || area.height <= pin_rows {
+            return;
+        }
 
+        let mut lines = prompt::status_lines(
+            self.prompt.as_ref().map(|p| (format!("CSV file for ADC {}", self.selected), p.text.as_str())),
+            self.error.as_deref().map(|err| (err, true)),
+        );
+        if lines.is_empty() {
+            lines.push(TuiText::styled(
+                format!(
+                    "{}/{}: adjust · drag to set · {}: signal · {}/{}: slower/faster · {}: replay a CSV",
+                    data.keymap().describe(Action::AdcDecrease),
+                    data.keymap().describe(Action::AdcIncrease),
+                    data.keymap().describe(Action::AdcSignal),
+                    data.keymap().describe(Action::AdcSlower),
+                    data.keymap().describe(Action::AdcFaster),
+                    data.keymap().describe(Action::AdcLoadSamples),
+                ),
+                Style::default().fg(c!(ConsoleHelp)),
+            ));
+        }
 
+        Paragraph::new(lines.iter())
+            .style(Style::default().bg(Colour::Reset))
+            .wrap(true)
+            .render(Rect { y: area.y + pin_rows, height: area.height - pin_rows, ..area }, buf);
     }
 
+    fn update(&mut self, event: WidgetEvent, data: &mut TuiData<'a, 'int, C, I, O>, _terminal: &mut Terminal<B>) -> bool {
+        use WidgetEvent::*;
+        use Action::*;
 
+        if !self.focusable {
+            return false;
+        }
 
-    fn update(&mut self, event: WidgetEvent, _data: &mut TuiData<'a, 'int, C, I, O>, _terminal: &mut Terminal<B>) -> bool {
         match event {
-            WidgetEvent::Mouse(_) | WidgetEvent::Focus(FocusEvent::GotFocus) => self.focusable,
+            Focus(FocusEvent::GotFocus) => { self.focused = true; true },
+            Focus(FocusEvent::LostFocus) => {
+                self.focused = false;
+                self.dragging = None;
+                self.prompt = None;
+                self.error = None;
+                true
+            },
+
+            Mouse(MouseEvent::Down(MouseButton::Left, col, row, _)) => {
+                self.focused = true;
+                self.dragging = self.pin_at(col, row);
+
+                if let Some(idx) = self.dragging {
+                    self.set(idx, Self::value_at(self.sliders[idx], col), data);
+                }
+                true
+            },
+            Mouse(MouseEvent::Drag(MouseButton::Left, col, _, _)) => {
+                if let Some(idx) = self.dragging {
+                    self.set(idx, Self::value_at(self.sliders[idx], col), data);
+                }
+                true
+            },
+            Mouse(MouseEvent::Up(_, _, _, _)) => { self.dragging = None; true },
+            Mouse(MouseEvent::ScrollUp(col, row, _)) => { self.scroll(col, row, 1, data); true },
+            Mouse(MouseEvent::ScrollDown(col, row, _)) => { self.scroll(col, row, -1, data); true },
+            Mouse(_) => true,
+
+            // Typing a path:
+            Key(e) if self.prompt.is_some() => {
+                if let Outcome::Entered((), path) = prompt::key(&mut self.prompt, e) {
+                    self.load_samples(path, data);
+                }
+
+                true
+            },
+
+            Key(e) => match data.keymap.lookup(&e, Scope::Adc) {
+                Some(AdcPrev) => { self.selected = (self.selected + ADC_PINS.len() - 1) % ADC_PINS.len(); true },
+                Some(AdcNext) => { self.selected = (self.selected + 1) % ADC_PINS.len(); true },
+                Some(AdcDecrease) => { self.nudge(-1, data); true },
+                Some(AdcIncrease) => { self.nudge(1, data); true },
+                Some(AdcDecreaseFast) => { self.nudge(-(FAST_STEP as i16), data); true },
+                Some(AdcIncreaseFast) => { self.nudge(FAST_STEP as i16, data); true },
+                Some(AdcMin) => { self.set(self.selected, 0, data); true },
+                Some(AdcMax) => { self.set(self.selected, 255, data); true },
+                Some(AdcSignal) => { self.cycle_signal(data); true },
+                Some(AdcSlower) => { self.scale_period(true, data); true },
+                Some(AdcFaster) => { self.scale_period(false, data); true },
+                Some(AdcLoadSamples) => { self.prompt = Some(Prompt::new(())); self.error = None; true },
+                _ => false,
+            },
+
             _ => false,
         }
     }
+
+    fn wants_key(&self, _key: &KeyEvent, _data: &TuiData<'a, 'int, C, I, O>) -> bool {
+        self.prompt.is_some()
+    }
 }
@@ -0,0 +1,391 @@
+//! A logic analyser: every peripheral channel (GPIO levels, ADC values, PWM
+//! duty cycles, timers) as a timeline, lined up so they can be compared.
+//!
+//! The samples come from [`peripheral_trace`](crate::tui::peripheral_trace).
+//! The view follows the newest samples until it's scrolled back; the values
+//! at the cursor are shown next to the channel names.
+
+use super::widget_impl_support::*;
+use crate::keymap::{Action, Scope};
+use crate::tui::peripheral_commands::{ADC_PINS, GPIO_PINS, PWM_PINS, TIMERS};
+use crate::tui::peripheral_trace::Values;
+
+/// The zoom levels, in milliseconds per column.
+const SCALES: [u64; 9] = [10, 20, 50, 100, 200, 500, 1000, 2000, 5000];
+const DEFAULT_SCALE: usize = 3;
+/// How wide the channel names (and values) are.
+const LABEL: u16 = 15;
+/// How far apart the time axis's labels are, in columns.
+const TICK_EVERY: usize = 20;
+
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Channel {
+    Gpio(usize),
+    Adc(usize),
+    Pwm(usize),
+    Timer(usize),
+}
+
+impl Channel {
+    fn all() -> Vec<Channel> {
+        (0..GPIO_PINS.len()).map(Channel::Gpio)
+            .chain((0..ADC_PINS.len()).map(Channel::Adc))
+            .chain((0..PWM_PINS.len()).map(Channel::Pwm))
+            .chain((0..TIMERS.len()).map(Channel::Timer))
+            .collect()
+    }
+
+    fn name(self) -> String {
+        match self {
+            Channel::Gpio(idx) => format!("GPIO {}", idx),
+            Channel::Adc(idx) => format!("ADC {}", idx),
+            Channel::Pwm(idx) => format!("PWM {}", idx),
+            Channel::Timer(idx) => format!("Timer {}", idx),
+        }
+    }
+
+    fn value(self, values: &Values) -> String {
+        let value = match self {
+            Channel::Gpio(idx) => values.gpio[idx].map(|l| (l as u8).to_string()),
+            Channel::Adc(idx) => values.adc[idx].map(|v| v.to_string()),
+            Channel::Pwm(idx) => values.pwm[idx].map(|d| d.to_string()),
+            Channel::Timer(idx) => values.timers[idx].map(|p| format!("{}ms", p)),
+        };
+
+        value.unwrap_or_else(|| "off".to_string())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Analyser {
+    focused: bool,
+    scale: usize,
+    /// The time at the right edge of the timeline; `None` to follow the newest
+    /// samples.
+    end: Option<u64>,
+    /// The cursor's column in the timeline; `None` for the last column.
+    cursor: Option<usize>,
+    /// The first channel shown (when they don't all fit).
+    first_channel: usize,
+
+    // As of the last draw:
+    timeline: Rect,
+    shown_end: u64,
+    shown_channels: usize,
+}
+
+impl Default for Analyser {
+    fn default() -> Self {
+        Self {
+            focused: false,
+            scale: DEFAULT_SCALE,
+            end: None,
+            cursor: None,
+            first_channel: 0,
+            timeline: Rect::default(),
+            shown_end: 0,
+            shown_channels: 0,
+        }
+    }
+}
+
+// Seconds (with a sign) relative to the newest sample.
+fn relative(t: u64, latest: u64) -> String {
+    if t >= latest {
+        "now".to_string()
+    } else {
+        let ago = latest - t;
+        format!("-{}.{:03}s", ago / 1000, ago % 1000)
+    }
+}
+
+impl Analyser {
+    fn scale(&self) -> u64 {
+        SCALES[self.scale]
+    }
+
+    fn width(&self) -> usize {
+        self.timeline.width as usize
+    }
+
+    fn cursor(&self) -> usize {
+        self.cursor.unwrap_or(usize::MAX).min(self.width().saturating_sub(1))
+    }
+
+    // The time at the end of column `col`; `None` if that's before we
+    // started.
+    fn column_time(&self, end: u64, col: usize) -> Option<u64> {
+        end.checked_sub((self.width() - 1 - col) as u64 * self.scale())
+    }
+
+    fn scroll<'a, 'int, C, I, O>(&mut self, columns: i64, data: &TuiData<'a, 'int, C, I, O>)
+    where
+        C: Control + ?Sized + 'a,
+        I: InputSink + ?Sized + 'a,
+        O: OutputSource + ?Sized + 'a,
+    {
+        let (start, latest) = match data.trace.span() {
+            Some(span) => span,
+            None => return,
+        };
+
+        let end = self.shown_end as i64 + columns * self.scale() as i64;
+        self.end = if end >= latest as i64 {
+            None
+        } else {
+            // Don't scroll back past the first sample:
+            Some((end.max(0) as u64).max(start))
+        };
+    }
+
+    fn move_cursor<'a, 'int, C, I, O>(&mut self, by: i64, data: &TuiData<'a, 'int, C, I, O>)
+    where
+        C: Control + ?Sized + 'a,
+        I: InputSink + ?Sized + 'a,
+        O: OutputSource + ?Sized + 'a,
+    {
+        let col = self.cursor() as i64 + by;
+        let last = self.width() as i64 - 1;
+
+        // Moving past the edges scrolls:
+        if col < 0 || col > last {
+            self.scroll(if col < 0 { col } else { col - last }, data);
+        }
+
+        self.cursor = Some(col.max(0).min(last.max(0)) as usize);
+    }
+
+    fn zoom(&mut self, zoom_in: bool) {
+        self.scale = if zoom_in {
+            self.scale.saturating_sub(1)
+        } else {
+            (self.scale + 1).min(SCALES.len() - 1)
+        };
+    }
+
+    // One channel's timeline: a character (and style) per column.
+    fn cells<'a, 'int, C, I, O>(&self, channel: Channel, end: u64, data: &TuiData<'a, 'int, C, I, O>) -> Vec<(char, Style)>
+    where
+        C: Control + ?Sized + 'a,
+        I: InputSink + ?Sized + 'a,
+        O: OutputSource + ?Sized + 'a,
+    {
+        let style = |c| Style::default().fg(c);
+        let bar = |v: u8| (BARS[v as usize * (BARS.len() - 1) / 255], style(c!(Data)));
+
+        (0..self.width()).map(|col| {
+            let to = match self.column_time(end, col) {
+                Some(t) => t,
+                None => return (' ', Style::default()),
+            };
+            let from = to.saturating_sub(self.scale());
+
+            let values = match data.trace.at(to) {
+                Some(v) => v,
+                None => return (' ', Style::default()),
+            };
+
+            let mut cell = match channel {
+                Channel::Gpio(idx) => {
+                    let level = values.gpio[idx];
+                    let edge = data.trace.at(from).map(|v| v.gpio[idx] != level).unwrap_or(false)
+                        || data.trace.changes(from, to).any(|s| s.values.gpio[idx] != level);
+
+                    match (level, edge) {
+                        (None, _) => ('·', style(c!(Disabled))),
+                        (Some(_), true) => ('│', style(c!(Data))),
+                        (Some(true), false) => ('▔', style(c!(Success))),
+                        (Some(false), false) => ('▁', style(c!(Name))),
+                    }
+                },
+                Channel::Adc(idx) => values.adc[idx].map(bar).unwrap_or(('·', style(c!(Disabled)))),
+                Channel::Pwm(idx) => values.pwm[idx].map(bar).unwrap_or(('·', style(c!(Disabled)))),
+                Channel::Timer(idx) => {
+                    let fired = data.timer_history[idx].recent.iter().any(|f| f.at > from && f.at <= to);
+                    match (values.timers[idx], fired) {
+                        (None, _) => ('·', style(c!(Disabled))),
+                        (Some(_), true) => ('┃', style(c!(Success))),
+                        (Some(_), false) => ('─', style(c!(Data))),
+                    }
+                },
+            };
+
+            if col == self.cursor() {
+                cell.1 = cell.1.modifier(Modifier::REVERSED);
+            }
+
+            cell
+        }).collect()
+    }
+
+    // The time axis: a label every `TICK_EVERY` columns (counting back from
+    // the right edge), ending at the column it's for.
+    fn axis(&self, end: u64, latest: u64) -> String {
+        let width = self.width();
+        let mut axis = vec![' '; width];
+
+        for col in (0..width).rev().step_by(TICK_EVERY) {
+            let t = match self.column_time(end, col) {
+                Some(t) => t,
+                None => break,
+            };
+
+            let label: Vec<char> = format!("{}╵", relative(t, latest)).chars().collect();
+            if label.len() <= col + 1 {
+                axis[col + 1 - label.len()..=col].copy_from_slice(&label);
+            }
+        }
+
+        axis.into_iter().collect()
+    }
+}
+
+impl<'a, 'int, C, I, O, B> Widget<'a, 'int, C, I, O, B> for Analyser
+where
+    C: Control + ?Sized + 'a,
+    I: InputSink + ?Sized + 'a,
+    O: OutputSource + ?Sized + 'a,
+    B: Backend,
+{
+    fn draw(&mut self, data: &TuiData<'a, 'int, C, I, O>, area: Rect, buf: &mut Buffer) {
+        if area.height < 3 || area.width <= LABEL {
+            return;
+        }
+
+        let latest = match data.trace.span() {
+            Some((_, latest)) => latest,
+            None => {
+                let hint = TuiText::styled("No samples yet (the peripherals are sampled as the TUI updates).", Style::default().fg(c!(ConsoleHelp)));
+                return Paragraph::new([hint].iter()).wrap(true).render(area, buf);
+            },
+        };
+
+        // A line for the header and one for the time axis; channels get the
+        // rest.
+        self.timeline = Rect::new(area.x + LABEL, area.y + 1, area.width - LABEL, area.height - 2);
+        let end = self.end.unwrap_or(latest).min(latest);
+        self.shown_end = end;
+
+        let channels = Channel::all();
+        self.shown_channels = (self.timeline.height as usize).min(channels.len());
+        self.first_channel = self.first_channel.min(channels.len() - self.shown_channels);
+
+        let cursor_time = self.column_time(end, self.cursor());
+        let at_cursor = cursor_time.and_then(|t| data.trace.at(t));
+
+        let mut header = format!(
+            "cursor: {} · {}ms per column · {}",
+            cursor_time.map(|t| relative(t, latest)).unwrap_or_else(|| "-".to_string()),
+            self.scale(),
+            if self.end.is_none() { "live" } else { "scrolled back" },
+        );
+        if self.focused {
+            header.push_str(&format!(
+                " · {}/{}: zoom, {}: follow",
+                data.keymap().describe(Action::AnalyserZoomIn),
+                data.keymap().describe(Action::AnalyserZoomOut),
+                data.keymap().describe(Action::AnalyserLive),
+            ));
+        }
+        Paragraph::new([TuiText::styled(header, Style::default().fg(c!(Name)))].iter())
+            .render(Rect { height: 1, ..area }, buf);
+
+        for (row, channel) in channels.iter().skip(self.first_channel).take(self.shown_channels).enumerate() {
+            let value = at_cursor.map(|v| channel.value(v)).unwrap_or_default();
+
+            let mut text = vec![
+                TuiText::styled(format!("{:8}", channel.name()), Style::default().fg(c!(Name))),
+                TuiText::styled(format!("{:>6} ", value), Style::default().fg(c!(Data))),
+            ];
+
+            // Runs of cells with the same style go in one span:
+            let mut run = String::new();
+            let mut run_style = None;
+            for (ch, style) in self.cells(*channel, end, data) {
+                if run_style != Some(style) {
+                    if let Some(s) = run_style {
+                        text.push(TuiText::styled(std::mem::take(&mut run), s));
+                    }
+                    run_style = Some(style);
+                }
+                run.push(ch);
+            }
+            if let Some(s) = run_style {
+                text.push(TuiText::styled(run, s));
+            }
+
+            Paragraph::new(text.iter())
+                .style(Style::default().fg(Colour::White).bg(Colour::Reset))
+                .render(Rect { y: self.timeline.y + row as u16, height: 1, ..area }, buf);
+        }
+
+        let axis = TuiText::styled(self.axis(end, latest), Style::default().fg(c!(Disabled)));
+        Paragraph::new([axis].iter())
+            .render(Rect { x: self.timeline.x, y: area.y + area.height - 1, width: self.timeline.width, height: 1 }, buf);
+    }
+
+    fn update(&mut self, event: WidgetEvent, data: &mut TuiData<'a, 'int, C, I, O>, _terminal: &mut Terminal<B>) -> bool {
+        use WidgetEvent::*;
+        use Action::*;
+
+        match event {
+            Focus(FocusEvent::GotFocus) => { self.focused = true; true },
+            Focus(FocusEvent::LostFocus) => { self.focused = false; true },
+
+            Mouse(MouseEvent::Down(MouseButton::Left, col, row, _)) => {
+                self.focused = true;
+                if self.timeline.intersects(Rect::new(col, row, 1, 1)) {
+                    self.cursor = Some((col - self.timeline.x) as usize);
+                }
+                true
+            },
+            Mouse(MouseEvent::ScrollUp(..)) => { self.zoom(true); true },
+            Mouse(MouseEvent::ScrollDown(..)) => { self.zoom(false); true },
+            Mouse(_) => true,
+
+            Key(e) => {
+                let half = (self.width() / 2).max(1) as i64;
+
+                match data.keymap.lookup(&e, Scope::Analyser) {
+                    Some(AnalyserCursorLeft) => self.move_cursor(-1, data),
+                    Some(AnalyserCursorRight) => self.move_cursor(1, data),
+                    Some(AnalyserScrollBack) => self.scroll(-half, data),
+                    Some(AnalyserScrollForward) => self.scroll(half, data),
+                    Some(AnalyserZoomIn) => self.zoom(true),
+                    Some(AnalyserZoomOut) => self.zoom(false),
+                    Some(AnalyserChannelsUp) => self.first_channel = self.first_channel.saturating_sub(1),
+                    Some(AnalyserChannelsDown) => self.first_channel += 1,
+                    Some(AnalyserLive) => { self.end = None; self.cursor = None; },
+                    Some(AnalyserClear) => {
+                        data.trace.clear();
+                        self.end = None;
+                        data.log("[Analyser] Cleared the samples.\n", c!(Success));
+                    },
+                    _ => return false,
+                }
+
+                true
+            },
+
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod analyser_tests {
+    use super::*;
+    use pretty_assertions::assert_eq as eq;
+
+    #[test]
+    fn axis() {
+        let analyser = Analyser { timeline: Rect::new(0, 0, 30, 1), ..Analyser::default() };
+
+        // 100ms per column:
+        eq!(analyser.axis(10_000, 10_000), "  -2.000s╵                now╵");
+        eq!(analyser.axis(100, 10_000), "                      -9.900s╵");
+        eq!(relative(500, 1_750), "-1.250s");
+    }
+}
@@ -1,14 +1,241 @@
-//! TODO!
+//! The program's console: its output (scrollable, searchable, and selectable
+//! for copying) over a line for typing input, which goes to the program as
+//! it's typed or a line at a time (see [`TuiData::type_char`]).
+//!
+//! [`TuiData::type_char`]: crate::tui::TuiData::type_char
 
 use super::widget_impl_support::*;
+use crate::keymap::{Action, Scope};
+use crate::tui::ansi::{restyle, wrap_line};
 
-use std::ops::Deref;
+use crate::tui::InputScript;
+use crate::tui::prompt::{self, Outcome, Prompt};
+
+use std::ops::Range;
+use std::time::Duration;
+
+// How many rows one notch of the mouse wheel scrolls.
+const SCROLL_STEP: usize = 3;
+
+// A position in the console history: a line and a character in that line.
+type Position = (usize, usize);
+
+// What's being typed in when the input line is taken over by a prompt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Asking {
+    Search,
+    InputScript,
+}
+
+impl Asking {
+    fn prefix(&self) -> &'static str {
+        match self {
+            Asking::Search => "/",
+            Asking::InputScript => "input> ",
+        }
+    }
+}
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
-pub struct Console;
+pub struct Console {
+    /// How many rows back from the newest output we're scrolled; when this is
+    /// 0 we follow new output.
+    scroll: usize,
+
+    /// The prompt that's being typed into, if there is one.
+    prompt: Option<Prompt<Asking>>,
+    /// The last search that was run and the line it found.
+    last_search: String,
+    found: Option<usize>,
+    /// Shown in the divider above the input line (i.e. "not found").
+    status: Option<String>,
+
+    /// Where the selection was started and where it ends (inclusive); these
+    /// aren't necessarily in order.
+    selection: Option<(Position, Position)>,
+    selecting: bool,
+
+    /// The output area and, for every row in it, the line it shows and the
+    /// position of its first character in that line as of the last draw.
+    output: Rect,
+    rows: Vec<Position>,
+
+    /// How far the history's lines had moved up (from the oldest lines being
+    /// dropped) as of the last draw; see [`AnsiTextContainer::shifted`].
+    shifted: usize,
+}
+
+impl Console {
+    fn page(&self) -> usize {
+        (self.output.height as usize).saturating_sub(1).max(1)
+    }
+
+    fn scroll_to_bottom(&mut self) {
+        self.scroll = 0;
+        self.found = None;
+        self.status = None;
+    }
+
+    fn start_prompt(&mut self, asking: Asking) {
+        self.prompt = Some(Prompt::new(asking));
+        self.status = match asking {
+            Asking::Search => None,
+            Asking::InputScript => Some(String::from(
+                "Text to feed to the program (`\\n` for newlines, `@path` for a file, nothing to stop)"
+            )),
+        };
+    }
+
+    fn attach_input_script<'a, 'int, C, I, O>(&mut self, text: String, data: &mut TuiData<'a, 'int, C, I, O>)
+    where
+        C: Control + ?Sized + 'a,
+        I: InputSink + ?Sized + 'a,
+        O: OutputSource + ?Sized + 'a,
+    {
+        self.status = None;
+        if text.is_empty() {
+            return data.set_input_script(None);
+        }
+
+        specialize! { [var: script]
+            desktop => {
+                match text.strip_prefix('@') {
+                    Some(path) => InputScript::load(path.as_ref()).map_err(|e| format!("{:#}", e)),
+                    None => InputScript::from_escaped(&text),
+                }
+            }
+            web => { InputScript::from_escaped(&text) }
+        }
+
+        match script {
+            Ok(script) => {
+                let script = script
+                    .with_delay(Duration::from_millis(data.config.input_delay_ms))
+                    .with_refeed_on_reset(data.config.refeed_input_on_reset);
+
+                data.set_input_script(Some(script))
+            },
+            Err(err) => self.status = Some(err),
+        }
+    }
+
+    // Finds the next (older) line with the last search in it and scrolls to
+    // it.
+    fn search_next<'a, 'int, C, I, O>(&mut self, data: &TuiData<'a, 'int, C, I, O>)
+    where
+        C: Control + ?Sized + 'a,
+        I: InputSink + ?Sized + 'a,
+        O: OutputSource + ?Sized + 'a,
+    {
+        if self.last_search.is_empty() {
+            return;
+        }
+
+        let hist = data.console_hist.borrow();
+        let before = self.found.unwrap_or(hist.num_lines());
+
+        match hist.find_before(&self.last_search, before) {
+            Some(line) => {
+                self.found = Some(line);
+                self.status = None;
+
+                // Put the match in the middle of the output area:
+                let width = self.output.width as usize;
+                let rows_below: usize = (line..hist.num_lines())
+                    .map(|l| wrap_line(hist.line(l), width).len())
+                    .sum();
+
+                self.scroll = rows_below.saturating_sub(1 + self.output.height as usize / 2);
+            },
+            None => {
+                self.status = Some(if self.found.is_some() {
+                    format!("No more matches for `{}`", self.last_search)
+                } else {
+                    format!("`{}` not found", self.last_search)
+                });
+            },
+        }
+    }
+
+    // Maps a point on the screen to a position in the history; points outside
+    // of the output area are clamped to it.
+    fn position_at(&self, col: u16, row: u16) -> Option<Position> {
+        let idx = (row.saturating_sub(self.output.y) as usize).min(self.rows.len().checked_sub(1)?);
+        let (line, start) = self.rows[idx];
+
+        let col = col
+            .saturating_sub(self.output.x)
+            .min(self.output.width.saturating_sub(1));
+
+        Some((line, start + col as usize))
+    }
+
+    fn ordered_selection(&self) -> Option<(Position, Position)> {
+        self.selection.map(|(a, b)| if a <= b { (a, b) } else { (b, a) })
+    }
+
+    // The part of a row (that starts at `start` in `line`) that's selected.
+    fn selected_range(&self, line: usize, start: usize) -> Option<Range<usize>> {
+        let ((from_line, from), (to_line, to)) = self.ordered_selection()?;
+
+        if line < from_line || line > to_line {
+            return None;
+        }
+
+        let from = if line == from_line { from } else { 0 };
+        let to = if line == to_line { to + 1 } else { usize::MAX };
+
+        Some(from.saturating_sub(start)..to.saturating_sub(start))
+    }
+
+    fn selected_text(&self, hist: &AnsiTextContainer<'_>) -> Option<String> {
+        let ((from_line, from), (to_line, to)) = self.ordered_selection()?;
+
+        let lines: Vec<String> = (from_line..=to_line)
+            .map(|line| {
+                let s = hist.line_string(line);
+                let skip = if line == from_line { from } else { 0 };
+                let take = if line == to_line { (to + 1).saturating_sub(skip) } else { usize::MAX };
+
+                s.chars().skip(skip).take(take).collect()
+            })
+            .collect();
+
+        Some(lines.join("\n"))
+    }
+
+    // Typing goes to the program (and takes us back to the newest output).
+    fn input<'a, 'int, C, I, O>(&mut self, key: KeyEvent, data: &mut TuiData<'a, 'int, C, I, O>) -> bool
+    where
+        C: Control + ?Sized + 'a,
+        I: InputSink + ?Sized + 'a,
+        O: OutputSource + ?Sized + 'a,
+    {
+        const EMPTY: KeyModifiers = KeyModifiers::empty();
+        let line = data.console_input.get_mut();
 
-// TODO: scrolling!
-// TODO: copy/paste!
+        match key {
+            KeyEvent { code: KeyCode::Char(c), modifiers } if modifiers == EMPTY || modifiers == KeyModifiers::SHIFT => {
+                data.type_char(c)
+            },
+            KeyEvent { code: KeyCode::Enter, modifiers: EMPTY } => data.type_char('\n'),
+
+            // Line editing:
+            _ if !data.line_buffered => return false,
+            KeyEvent { code: KeyCode::Backspace, .. } => line.backspace(),
+            KeyEvent { code: KeyCode::Delete, .. } => line.delete(),
+            KeyEvent { code: KeyCode::Left, modifiers: EMPTY } => line.left(),
+            KeyEvent { code: KeyCode::Right, modifiers: EMPTY } => line.right(),
+            KeyEvent { code: KeyCode::Home, modifiers: EMPTY } => line.home(),
+            KeyEvent { code: KeyCode::End, modifiers: EMPTY } => line.end(),
+
+            _ => return false,
+        }
+
+        self.scroll_to_bottom();
+        true
+    }
+}
 
 impl<'a, 'int, C, I, O, B> Widget<'a, 'int, C, I, O, B> for Console
 where
@@ -26,6 +253,7 @@ where
 
         // Append any new output we have:
         if let Some(s) = data.output.and_then(|source| source.get_chars()){
+            data.transcript.borrow_mut().output(&s, data.sim.get_pc());
             data.console_hist.borrow_mut().push_string(s);
         }
 
@@ -44,31 +272,111 @@ where
             unreachable!()
         };
 
-        // TODO: this deals with lines that wrap in pretty much the worst way.
-        // We always print the last `n` lines where `n` is the height of the
-        // output area but the problem is that we count lines by newlines so
-        // any lines that get wrapped cause another line to be "pushed out" of
-        // the output area.
-        //
-        // Since we can't scroll, this ends up meaning you just can't see the
-        // lines that have been pushed out.
+        self.output = output;
+        let (width, height) = (output.width.max(1) as usize, output.height as usize);
+        data.console_hist.borrow_mut().set_screen_rows(height);
+        let hist = data.console_hist.borrow();
+
+        // Keep the search result and the selection on the same lines if older
+        // lines were dropped (and drop them if the history was cleared):
+        let shifted = hist.shifted();
+        if shifted != self.shifted {
+            let shift = shifted.checked_sub(self.shifted);
+            let fix = |(line, col): Position| Some((line.checked_sub(shift?)?, col));
+
+            self.found = self.found.and_then(|l| fix((l, 0))).map(|(l, _)| l);
+            self.selection = self.selection.and_then(|(a, b)| Some((fix(a)?, fix(b)?)));
+            self.shifted = shifted;
+        }
+
+        // Lines are wrapped here rather than by the `Paragraph` so that we
+        // know how many rows each line takes up (and so what's on screen).
         //
-        // Because of this I'm going to disable output wrapping for now, but
-        // this is not good!
+        // We go backwards from the newest line until we've got enough rows to
+        // fill the output area at our scroll position:
+        let mut rows = Vec::new();
+        for line in (0..hist.num_lines()).rev() {
+            let wrapped = wrap_line(hist.line(line), width);
+
+            for (idx, row) in wrapped.into_iter().enumerate().rev() {
+                rows.push((line, idx * width, row));
+            }
+
+            if rows.len() >= self.scroll.saturating_add(height) {
+                break;
+            }
+        }
 
-        // Finally, do the drawing:
-        // Paragraph::new(data.console_hist.borrow().get_lines(..))
-        Paragraph::new(data.console_hist.borrow().get_last_n_lines(output.height as usize))
+        // Can't scroll back past the start of the history:
+        self.scroll = self.scroll.min(rows.len().saturating_sub(height));
+
+        let mut visible: Vec<_> = rows.into_iter().skip(self.scroll).take(height).collect();
+        visible.reverse();
+
+        self.rows.clear();
+        let mut text = Vec::new();
+        for (line, start, mut row) in visible {
+            if self.found == Some(line) {
+                row = restyle(row, 0..usize::MAX, |s| s.modifier(s.modifier | Modifier::UNDERLINED | Modifier::BOLD));
+            }
+
+            if let Some(range) = self.selected_range(line, start) {
+                row = restyle(row, range, |s| s.modifier(s.modifier | Modifier::REVERSED));
+            }
+
+            self.rows.push((line, start));
+            text.extend(row);
+            text.push(TuiText::raw("\n"));
+        }
+
+        Paragraph::new(text.iter())
             .style(Style::default().fg(c!(ConsoleOut)).bg(Colour::Reset))
             .alignment(Alignment::Left)
-            // .wrap(true)
             .render(output, buf);
 
-        Paragraph::new([
-                TuiText::styled("─".repeat(input.width as usize), Style::default().fg(c!(Border))),
-                TuiText::styled("\n> ", Style::default().fg(c!(ConsolePrompt))),
-                TuiText::styled(data.console_input_string.borrow().deref(), Style::default().fg(c!(ConsoleIn))),
-            ].iter())
+        // The divider doubles as a status line:
+        let status = match (&self.status, self.scroll) {
+            (Some(s), _) => Some(s.clone()),
+            (None, 0) => data.pending_input(),
+            (None, n) => Some(format!("{} rows back ({} to return)", n, data.keymap().describe(Action::ConsoleBottom))),
+        };
+        let divider = match status {
+            Some(s) => {
+                let s = format!("── {} ", s);
+                let pad = (input.width as usize).saturating_sub(s.chars().count());
+                s + &"─".repeat(pad)
+            },
+            None => "─".repeat(input.width as usize),
+        };
+
+        let mut footer = vec![TuiText::styled(divider, Style::default().fg(c!(Border)))];
+        let style = Style::default().fg(c!(ConsoleIn));
+
+        match &self.prompt {
+            Some(Prompt { kind, text }) => {
+                footer.push(TuiText::styled(format!("\n{}", kind.prefix()), Style::default().fg(c!(ConsolePrompt))));
+                footer.push(TuiText::styled(text.clone(), style));
+            },
+            None => {
+                let editor = data.console_input.borrow();
+                footer.push(TuiText::styled("\n> ", Style::default().fg(c!(ConsolePrompt))));
+
+                if data.line_buffered {
+                    // Show the cursor (as a reversed character):
+                    let line: Vec<char> = editor.line().chars().collect();
+                    let (before, after) = line.split_at(editor.cursor());
+                    let (at, after) = after.split_first().map(|(c, a)| (*c, a)).unwrap_or((' ', &[][..]));
+
+                    footer.push(TuiText::styled(before.iter().collect::<String>(), style));
+                    footer.push(TuiText::styled(at.to_string(), style.modifier(Modifier::REVERSED)));
+                    footer.push(TuiText::styled(after.iter().collect::<String>(), style));
+                } else {
+                    footer.push(TuiText::styled(editor.line(), style));
+                }
+            },
+        }
+
+        Paragraph::new(footer.iter())
             .style(Style::default().bg(Colour::Reset))
             // .wrap(true)
             .render(input, buf);
@@ -76,29 +384,126 @@ where
 
     fn update(&mut self, event: WidgetEvent, data: &mut TuiData<'a, 'int, C, I, O>, _terminal: &mut Terminal<B>) -> bool {
         use WidgetEvent::*;
+        use Action::*;
         const EMPTY: KeyModifiers = KeyModifiers::empty();
 
-
         match event {
-            Focus(FocusEvent::GotFocus)
-            | Focus(FocusEvent::LostFocus)
-            | Mouse(MouseEvent::Up(_, _, _, _))
-            | Mouse(MouseEvent::Down(_, _, _, _)) => true,
+            Focus(FocusEvent::GotFocus) | Focus(FocusEvent::LostFocus) => true,
 
-            Key(KeyEvent { code: KeyCode::Char(c), modifiers: EMPTY }) => {
-                data.input
-                    .and_then(|sink| sink.put_char(c))
-                    // If that succeeded, add the char to the input line:
-                    .map(|()| data.console_input_string.get_mut().push(c))
-                    .is_some()
+            Mouse(MouseEvent::ScrollUp(_, _, _)) => {
+                self.scroll = self.scroll.saturating_add(SCROLL_STEP);
+                true
+            },
+            Mouse(MouseEvent::ScrollDown(_, _, _)) => {
+                self.scroll = self.scroll.saturating_sub(SCROLL_STEP);
+                true
             },
 
-            Key(KeyEvent { code: KeyCode::Enter, modifiers: EMPTY }) => {
-                data.console_input_string.get_mut().clear();
+            // Selecting text:
+            Mouse(MouseEvent::Down(MouseButton::Left, col, row, _)) => {
+                let in_output = row >= self.output.y && row < self.output.y + self.output.height;
+
+                self.selection = if in_output {
+                    self.position_at(col, row).map(|p| (p, p))
+                } else {
+                    None
+                };
+                self.selecting = self.selection.is_some();
+
                 true
             },
+            Mouse(MouseEvent::Drag(MouseButton::Left, col, row, _)) if self.selecting => {
+                // Dragging past the top or bottom scrolls:
+                if row < self.output.y {
+                    self.scroll = self.scroll.saturating_add(1);
+                } else if row >= self.output.y + self.output.height {
+                    self.scroll = self.scroll.saturating_sub(1);
+                }
+
+                if let (Some((start, _)), Some(end)) = (self.selection, self.position_at(col, row)) {
+                    self.selection = Some((start, end));
+                }
+
+                true
+            },
+            Mouse(MouseEvent::Up(MouseButton::Left, _, _, _)) if self.selecting => {
+                self.selecting = false;
+
+                match self.selection {
+                    // Just a click; nothing to copy.
+                    Some((start, end)) if start == end => self.selection = None,
+                    Some(_) => {
+                        let text = self.selected_text(&data.console_hist.borrow());
+                        if let Some(text) = text {
+                            data.copy("console selection", text);
+                        }
+                    },
+                    None => {},
+                }
+
+                true
+            },
+            Mouse(MouseEvent::Up(_, _, _, _))
+            | Mouse(MouseEvent::Down(_, _, _, _)) => true,
+
+            // Typing into a prompt:
+            Key(e) if self.prompt.is_some() => {
+                match prompt::key(&mut self.prompt, e) {
+                    Outcome::Entered(Asking::Search, text) => {
+                        self.last_search = text;
+                        self.found = None;
+                        self.search_next(data);
+                    },
+                    Outcome::Entered(Asking::InputScript, text) => self.attach_input_script(text, data),
+                    Outcome::Cancelled => self.status = None,
+                    Outcome::Typing => {},
+                }
+
+                true
+            },
+
+            Key(e) => match data.keymap.lookup(&e, Scope::Console) {
+                Some(ConsoleScrollUp) => { self.scroll = self.scroll.saturating_add(self.page()); true },
+                Some(ConsoleScrollDown) => { self.scroll = self.scroll.saturating_sub(self.page()); true },
+                Some(ConsoleTop) => { self.scroll = usize::MAX; true },
+                Some(ConsoleBottom) => { self.scroll_to_bottom(); true },
+                Some(ConsoleSearch) => { self.start_prompt(Asking::Search); true },
+                Some(ConsoleInputScript) => { self.start_prompt(Asking::InputScript); true },
+                Some(ConsoleSearchNext) => { self.search_next(data); true },
+                Some(ConsoleLineMode) => { data.toggle_line_buffered(); true },
+
+                Some(ConsoleKillLine) if data.line_buffered => { data.console_input.get_mut().kill_to_start(); true },
+                Some(ConsoleHistoryPrev) if data.line_buffered => { data.console_input.get_mut().history_prev(); true },
+                Some(ConsoleHistoryNext) if data.line_buffered => { data.console_input.get_mut().history_next(); true },
+                Some(_) => false,
+
+                // While scrolled back, there are some less-style shortcuts;
+                // otherwise keys go to the program.
+                None => match e {
+                    KeyEvent { code: KeyCode::Char('/'), modifiers: EMPTY } if self.scroll > 0 => {
+                        self.start_prompt(Asking::Search);
+                        true
+                    },
+                    KeyEvent { code: KeyCode::Char('n'), modifiers: EMPTY } if self.scroll > 0 => {
+                        self.search_next(data);
+                        true
+                    },
+                    KeyEvent { code: KeyCode::Esc, .. } => {
+                        self.selection = None;
+                        self.scroll_to_bottom();
+                        true
+                    },
+                    e => self.input(e, data),
+                },
+            },
 
              _ => false,
         }
     }
+
+    // Prompts take every key; line-buffered consoles take their bindings (i.e.
+    // `ctrl+u`) over the usual ones.
+    fn wants_key(&self, key: &KeyEvent, data: &TuiData<'a, 'int, C, I, O>) -> bool {
+        self.prompt.is_some() || (data.line_buffered && data.keymap.lookup(key, Scope::Console).is_some())
+    }
 }
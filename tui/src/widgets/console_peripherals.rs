@@ -1,185 +1,360 @@
-//! TODO!
+//! A console for poking at the peripherals: setting GPIO and ADC inputs and
+//! looking at the state of every peripheral.
+//!
+//! See [`peripheral_commands`](crate::tui::peripheral_commands) for the
+//! commands.
 
 use super::widget_impl_support::*;
+use crate::keymap::{Action, Scope};
+use crate::tui::line_editor::LineEditor;
+use crate::tui::peripheral_commands::{self, Command, Peripheral, GPIO_PINS, ADC_PINS, PWM_PINS, TIMERS};
+use crate::tui::stimulus::When;
 
-use lc3_traits::peripherals::gpio::{GpioPin, GpioState};
-use lc3_traits::peripherals::adc::{AdcPin, AdcState};
-use lc3_traits::peripherals::pwm::{PwmPin, PwmState};
-use lc3_traits::peripherals::timers::{TimerId, TimerState};
+use lc3_traits::peripherals::gpio::GpioState;
+use lc3_traits::peripherals::adc::AdcState;
+use lc3_traits::peripherals::pwm::PwmState;
+use lc3_traits::peripherals::timers::{TimerMode, TimerState};
 
-use std::sync::RwLock;
+use std::path::Path;
 
+/// How many lines of output we hold on to.
+const OUTPUT_LINES: usize = 500;
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub struct ConsolePeripherals
-{
-    history: String,
-    input: String,
+const NO_STIMULUS: &str = "There isn't a stimulus script; load one with `stimulus load <path>`";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Kind {
+    /// A command that was entered (echoed back).
+    Command,
+    Output,
+    Error,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConsolePeripherals {
+    output: Vec<(Kind, String)>,
+    input: LineEditor,
 }
 
-impl Default for ConsolePeripherals {
-    fn default() -> Self {
-        Self {
-            history: String::from(""),
-            input: String::from(""),
+impl ConsolePeripherals {
+    fn print(&mut self, kind: Kind, line: String) {
+        if self.output.len() == OUTPUT_LINES {
+            let _ = self.output.remove(0);
         }
+
+        self.output.push((kind, line));
     }
-}
 
-impl<'a, 'int, C, I, O, B> Widget<'a, 'int, C, I, O, B> for ConsolePeripherals
-where
-    C: Control + ?Sized + 'a,
-    I: InputSink + ?Sized + 'a,
-    O: OutputSource + ?Sized + 'a,
-    B: Backend,
-{
-    fn draw(&mut self, data: &TuiData<'a, 'int, C, I, O>, area: Rect, buf: &mut Buffer) {
-        let peripheral_help = format!("Hello! To write to ADC or GPIO from the peripheral console,\nyou must write a line below separated by colons (:) then press ENTER to submit!\nex. gpio:1:1 will set GPIO pin 1 to True");
-        let text_help = [TuiText::styled(peripheral_help, Style::default().fg(c!(ConsoleHelp)))];
+    fn complete(&mut self) {
+        let (line, candidates) = peripheral_commands::complete(&self.input.line());
+        self.input.set_line(&line);
 
-        let para_help = Paragraph::new(text_help.iter())
-            .style(Style::default().fg(Colour::White).bg(Colour::Reset))
-            .alignment(Alignment::Left)
-            .wrap(true);
+        // When there's more than one way to go, list them:
+        if candidates.len() > 1 {
+            self.print(Kind::Output, candidates.join("  "));
+        }
+    }
 
-        let text = [TuiText::styled(">", Style::default().fg(c!(Title)))];
+    fn submit<'a, 'int, C, I, O>(&mut self, data: &mut TuiData<'a, 'int, C, I, O>)
+    where
+        C: Control + ?Sized + 'a,
+        I: InputSink + ?Sized + 'a,
+        O: OutputSource + ?Sized + 'a,
+    {
+        let line = self.input.submit();
+        if line.trim().is_empty() {
+            return;
+        }
 
-        let mut para = Paragraph::new(text.iter())
-            .style(Style::default().fg(Colour::White).bg(Colour::Reset))
-            .alignment(Alignment::Left)
-            .wrap(true);
+        self.print(Kind::Command, format!("> {}", line));
 
+        match peripheral_commands::parse(&line).and_then(|cmd| self.run(cmd, data)) {
+            Ok(lines) => lines.into_iter().for_each(|l| self.print(Kind::Output, l)),
+            Err(err) => {
+                data.log_at(Level::Warn, format!("[Peripherals] `{}`: {}\n", line, err), c!(InvalidInput));
+                self.print(Kind::Error, err);
+            },
+        }
+    }
 
-        if area.height <= 1 {
-            para.render(area, buf);
-        } else if area.height <= 4 {
-            let area = Rect::new(area.x, area.y+area.height/2, area.width, 3);
-            para.render(area, buf);
+    fn run<'a, 'int, C, I, O>(&mut self, cmd: Command, data: &mut TuiData<'a, 'int, C, I, O>) -> Result<Vec<String>, String>
+    where
+        C: Control + ?Sized + 'a,
+        I: InputSink + ?Sized + 'a,
+        O: OutputSource + ?Sized + 'a,
+    {
+        let lines = match cmd {
+            Command::Help(None) => peripheral_commands::HELP.iter().map(|l| l.to_string()).collect(),
+            Command::Help(Some(p)) => p.help().iter().map(|l| l.to_string()).collect(),
+            Command::History => self.input.history()
+                .iter()
+                .enumerate()
+                .map(|(idx, l)| format!("{:4}  {}", idx + 1, l))
+                .collect(),
+            Command::Clear => {
+                self.output.clear();
+                Vec::new()
+            },
+
+            Command::Show(p, Some(idx)) => vec![Self::describe(p, idx, data)],
+            Command::Show(Peripheral::Clock, None) => vec![Self::describe(Peripheral::Clock, 0, data)],
+            Command::Show(p, None) => (0..p.count()).map(|idx| Self::describe(p, idx, data)).collect(),
+
+            Command::SetGpio(idx, level) => {
+                data.set_gpio_input(idx, level)?;
+                vec![format!("GPIO pin {} set to {}", idx, level as u8)]
+            },
+            Command::SetAdc(idx, value) => {
+                data.set_adc_input(idx, value)?;
+                vec![format!("ADC pin {} set to {}", idx, value)]
+            },
+            Command::SetAdcSignal(idx, signal) => {
+                data.set_adc_signal(idx, signal);
+                vec![Self::following(idx, data)]
+            },
+            Command::LoadAdcSamples(idx, path) => {
+                data.load_adc_samples(idx, Path::new(&path))?;
+                vec![Self::following(idx, data)]
+            },
+
+            Command::ShowStimulus => Self::stimulus_status(data)?,
+            Command::LoadStimulus(path) => {
+                data.load_stimulus_script(Path::new(&path))?;
+                Self::stimulus_status(data)?
+            },
+            Command::RestartStimulus => {
+                data.stimulus.as_mut().ok_or_else(|| NO_STIMULUS.to_string())?.restart();
+                vec!["Restarted the stimulus script".to_string()]
+            },
+            Command::StopStimulus => {
+                data.set_stimulus_script(None);
+                vec!["Stopped the stimulus script".to_string()]
+            },
+        };
+
+        Ok(lines)
+    }
 
-            let text = [TuiText::styled(self.input.clone(), Style::default().fg(c!(ConsoleIn)))];
-            para = Paragraph::new(text.iter())
-                .style(Style::default().fg(Colour::White).bg(Colour::Reset))
-                .alignment(Alignment::Left)
-                .wrap(true);
+    // The stimulus script's upcoming events (and the last few that fired).
+    fn stimulus_status<'a, 'int, C, I, O>(data: &TuiData<'a, 'int, C, I, O>) -> Result<Vec<String>, String>
+    where
+        C: Control + ?Sized + 'a,
+        I: InputSink + ?Sized + 'a,
+        O: OutputSource + ?Sized + 'a,
+    {
+        const SHOWN: usize = 5;
+
+        let script = data.stimulus.as_ref().ok_or_else(|| NO_STIMULUS.to_string())?;
+        let mut lines = vec![format!(
+            "`{}`: {} events, {}ms and {} steps in",
+            script.name, script.events.len(), script.millis, script.steps,
+        )];
+
+        let upcoming = script.upcoming();
+        lines.push(format!("{} upcoming:", upcoming.len()));
+        lines.extend(upcoming.iter().take(SHOWN).map(|e| {
+            let next = match e.when {
+                When::Millis(_) => When::Millis(e.next.unwrap_or_default()),
+                When::Steps(_) => When::Steps(e.next.unwrap_or_default()),
+            };
+
+            let every = if e.repeats { format!(" (every {})", e.when) } else { String::new() };
+            format!("  line {:3}: at {}{}: {}", e.line, next, every, e.action)
+        }));
+
+        lines.push("Fired (most recent first):".to_string());
+        lines.extend(script.fired.iter().rev().take(SHOWN).map(|f| {
+            let outcome = f.error.as_ref().map(|e| format!(" (failed: {})", e)).unwrap_or_default();
+            format!("  line {:3}: at {}: {}{}", f.line, f.at, f.action, outcome)
+        }));
+
+        Ok(lines)
+    }
 
-            let area = increment(1, Axis::Y, area);
-            if area.height < 2 {
-                return;
-            }
-            para.render(area,buf);
-        } else {
+    // What an ADC pin's signal generator is up to.
+    fn following<'a, 'int, C, I, O>(idx: usize, data: &TuiData<'a, 'int, C, I, O>) -> String
+    where
+        C: Control + ?Sized + 'a,
+        I: InputSink + ?Sized + 'a,
+        O: OutputSource + ?Sized + 'a,
+    {
+        let waiting = match data.sim.get_adc_states()[ADC_PINS[idx]] {
+            AdcState::Disabled => " (once the program enables it)",
+            AdcState::Enabled => "",
+        };
+
+        match data.adc_signal(idx) {
+            Some(signal) => format!("ADC pin {} follows: {}{}", idx, signal, waiting),
+            None => format!("ADC pin {} isn't following a signal", idx),
+        }
+    }
 
-            para_help.render(area, buf);
+    // One line saying what a pin (or timer, or the clock) is up to.
+    fn describe<'a, 'int, C, I, O>(p: Peripheral, idx: usize, data: &TuiData<'a, 'int, C, I, O>) -> String
+    where
+        C: Control + ?Sized + 'a,
+        I: InputSink + ?Sized + 'a,
+        O: OutputSource + ?Sized + 'a,
+    {
+        let bit = |b: bool| if b { "1" } else { "0" };
+
+        let state = match p {
+            Peripheral::Gpio => {
+                let level = data.gpio_level(idx).map(bit).unwrap_or("?");
+                match data.sim.get_gpio_states()[GPIO_PINS[idx]] {
+                    GpioState::Disabled => "disabled".to_string(),
+                    GpioState::Input => format!("input, {}", level),
+                    GpioState::Interrupt => format!("interrupt, {}", level),
+                    GpioState::Output => format!("output, {}", level),
+                }
+            },
+            Peripheral::Adc => {
+                let pin = ADC_PINS[idx];
+                let state = match data.sim.get_adc_states()[pin] {
+                    AdcState::Disabled => "disabled".to_string(),
+                    AdcState::Enabled => match data.sim.get_adc_readings()[pin] {
+                        Ok(value) => format!("enabled, {}", value),
+                        Err(_) => "enabled, ?".to_string(),
+                    },
+                };
+
+                match data.adc_signal(idx) {
+                    Some(signal) => format!("{} (following: {})", state, signal),
+                    None => state,
+                }
+            },
+            Peripheral::Pwm => {
+                let pin = PWM_PINS[idx];
+                match data.sim.get_pwm_states()[pin] {
+                    PwmState::Disabled => "disabled".to_string(),
+                    PwmState::Enabled(period) => {
+                        format!("period {}ms, duty cycle {}/255", period, data.sim.get_pwm_config()[pin])
+                    },
+                }
+            },
+            Peripheral::Timer => {
+                let id = TIMERS[idx];
+                match data.sim.get_timer_states()[id] {
+                    TimerState::Disabled => "disabled".to_string(),
+                    TimerState::WithPeriod(period) => match data.sim.get_timer_modes()[id] {
+                        TimerMode::Repeated => format!("repeating every {}ms", period),
+                        TimerMode::SingleShot => format!("single shot after {}ms", period),
+                    },
+                }
+            },
+            Peripheral::Clock => format!("{}ms", data.sim.get_clock()),
+        };
+
+        match p {
+            Peripheral::Clock => format!("Clock: {}", state),
+            p => {
+                let label = p.label();
+                format!("{}{} {}: {}", label[..1].to_uppercase(), &label[1..], idx, state)
+            },
+        }
+    }
+}
 
-            let area = Rect::new(area.x, area.y+area.height-3, area.width, 3);
-            para.render(area, buf);
+impl<'a, 'int, C, I, O, B> Widget<'a, 'int, C, I, O, B> for ConsolePeripherals
+where
+    C: Control + ?Sized + 'a,
+    I: InputSink + ?Sized + 'a,
+    O: OutputSource + ?Sized + 'a,
+    B: Backend,
+{
+    fn draw(&mut self, _data: &TuiData<'a, 'int, C, I, O>, area: Rect, buf: &mut Buffer) {
+        if area.height == 0 {
+            return;
+        }
 
-            let text = [TuiText::styled(self.input.clone(), Style::default().fg(c!(ConsoleIn)))];
-            para = Paragraph::new(text.iter())
+        // The newest output that fits above the input line:
+        let rows = (area.height - 1) as usize;
+        let mut text: Vec<TuiText<'_>> = if self.output.is_empty() {
+            let hint = "Type `help` for a list of commands (i.e. `gpio 1 1` sets GPIO pin 1 high).\n";
+            vec![TuiText::styled(hint, Style::default().fg(c!(ConsoleHelp)))]
+        } else {
+            self.output[self.output.len().saturating_sub(rows)..]
+                .iter()
+                .map(|(kind, line)| {
+                    let colour = match kind {
+                        Kind::Command => c!(ConsoleIn),
+                        Kind::Output => c!(Data),
+                        Kind::Error => c!(Error),
+                    };
+
+                    TuiText::styled(format!("{}\n", line), Style::default().fg(colour))
+                })
+                .collect()
+        };
+
+        if rows > 0 {
+            Paragraph::new(text.iter())
                 .style(Style::default().fg(Colour::White).bg(Colour::Reset))
                 .alignment(Alignment::Left)
-                .wrap(true);
-
-            let area = increment(1, Axis::Y, area);
-            if area.height < 2 {
-                return;
-            }
-            para.render(area,buf);
+                .render(Rect { height: area.height - 1, ..area }, buf);
         }
 
+        // The input line, with the cursor shown as a reversed character:
+        let style = Style::default().fg(c!(ConsoleIn));
+        let line: Vec<char> = self.input.line().chars().collect();
+        let (before, after) = line.split_at(self.input.cursor());
+        let (at, after) = after.split_first().map(|(c, a)| (*c, a)).unwrap_or((' ', &[][..]));
+
+        text = vec![
+            TuiText::styled("> ", Style::default().fg(c!(ConsolePrompt))),
+            TuiText::styled(before.iter().collect::<String>(), style),
+            TuiText::styled(at.to_string(), style.modifier(Modifier::REVERSED)),
+            TuiText::styled(after.iter().collect::<String>(), style),
+        ];
+
+        Paragraph::new(text.iter())
+            .style(Style::default().bg(Colour::Reset))
+            .render(Rect { y: area.y + area.height - 1, height: 1, ..area }, buf);
     }
 
-    fn update(&mut self, event: WidgetEvent, _data: &mut TuiData<'a, 'int, C, I, O>, _terminal: &mut Terminal<B>) -> bool {
+    fn update(&mut self, event: WidgetEvent, data: &mut TuiData<'a, 'int, C, I, O>, _terminal: &mut Terminal<B>) -> bool {
         use WidgetEvent::*;
+        use Action::*;
         const EMPTY: KeyModifiers = KeyModifiers::empty();
-        match &_data.shims{
-            Some(shim) => {
-                match event {
-                    Focus(FocusEvent::GotFocus) => true,
-                    Focus(FocusEvent::LostFocus) => true,
-                    Mouse(MouseEvent::Up(_, _, _, _)) => true,
-                    Mouse(MouseEvent::Down(_, _, _, _)) => true,
-
-
-                    Key(KeyEvent { code: KeyCode::Backspace, modifiers: EMPTY }) => {
-                        if self.input.len() > 0 {
-                            self.input.remove(self.input.len()-1);
-                        }
-
-                        true
-                    }
-
-
-                    Key(KeyEvent { code: KeyCode::Char(c), modifiers: _ }) => {
-                        let x = format!("{}", c);
-                        self.input.push_str(&x);
-                        true
-                    }
-
-                    Key(KeyEvent { code: KeyCode::Enter, modifiers: EMPTY }) => {
-                        let x = self.input.clone();
-                        let vec: Vec<&str> = x.split(":").collect();
-
-                        self.input = String::from("");
-                        let adc_pins = [AdcPin::A0, AdcPin::A1, AdcPin::A2, AdcPin::A3];
-                        let gpio_pins = [GpioPin::G0, GpioPin::G1, GpioPin::G2, GpioPin::G3, GpioPin::G4, GpioPin::G5, GpioPin::G6, GpioPin::G7];
-                        if vec.len() > 2 {
-                            match vec[0] {
-                                "adc" => {
-                                    let adc_states = _data.sim.get_adc_states();
-                                    let lock = RwLock::write(&shim.adc);
-                                    let adc_pin = adc_pins[vec[1].parse::<u8>().unwrap() as usize];
-                                         match adc_states[adc_pin] {
-                                                AdcState::Enabled => {
-                                                    let value = vec[2].parse::<u8>().unwrap();
-                                                    lock.unwrap().set_value(adc_pin, value);
-                                                },
-                                                AdcState::Disabled => {
-
-                                                }
-
-                                            }
-                                    },
-
-                                "gpio" => {
-                                    let gpio_states = _data.sim.get_gpio_states();
-                                    let lock = RwLock::write(&shim.gpio);
-
-                                    let gpio_pin = gpio_pins[vec[1].parse::<u8>().unwrap() as usize];
-                                    match gpio_states[gpio_pin] {
-
-                                                GpioState::Input | GpioState::Interrupt => {
-                                                    match vec[2] {
-                                                        "0" => {
-                                                            lock.unwrap().set_pin(gpio_pin, false);
-                                                        },
-                                                        "1" => {
-                                                            lock.unwrap().set_pin(gpio_pin, true);
-                                                        },
-                                                        _ => {}
-                                                    }
-                                                },
-                                                _ => {}
-                                            }
-
-                                     },
-                                        _ => {}
-
-                                }
-
-
-                            }
-
-                        true
-                    }
-                     _ => false,
+
+        match event {
+            Focus(FocusEvent::GotFocus) | Focus(FocusEvent::LostFocus) => true,
+            Mouse(MouseEvent::Up(_, _, _, _)) => true,
+            Mouse(MouseEvent::Down(_, _, _, _)) => true,
+
+            Key(e) => {
+                match data.keymap.lookup(&e, Scope::Peripherals) {
+                    Some(PeripheralsComplete) => self.complete(),
+                    Some(PeripheralsHistoryPrev) => self.input.history_prev(),
+                    Some(PeripheralsHistoryNext) => self.input.history_next(),
+                    _ => match e {
+                        // Leave chords with `ctrl` or `alt` for other key bindings.
+                        KeyEvent { code: KeyCode::Char(c), modifiers } if modifiers == EMPTY || modifiers == KeyModifiers::SHIFT => {
+                            self.input.insert(c)
+                        },
+                        KeyEvent { code: KeyCode::Enter, modifiers: EMPTY } => self.submit(data),
+                        KeyEvent { code: KeyCode::Esc, .. } => self.input.clear(),
+
+                        KeyEvent { code: KeyCode::Backspace, .. } => self.input.backspace(),
+                        KeyEvent { code: KeyCode::Delete, .. } => self.input.delete(),
+                        KeyEvent { code: KeyCode::Left, modifiers: EMPTY } => self.input.left(),
+                        KeyEvent { code: KeyCode::Right, modifiers: EMPTY } => self.input.right(),
+                        KeyEvent { code: KeyCode::Home, modifiers: EMPTY } => self.input.home(),
+                        KeyEvent { code: KeyCode::End, modifiers: EMPTY } => self.input.end(),
+
+                        _ => return false,
+                    },
                 }
 
-            }
+                true
+            },
 
             _ => false,
-
         }
+    }
 
+    // Our bindings (i.e. `tab` to complete) beat the usual ones.
+    fn wants_key(&self, key: &KeyEvent, data: &TuiData<'a, 'int, C, I, O>) -> bool {
+        data.keymap.lookup(key, Scope::Peripherals).is_some()
     }
 }
@@ -0,0 +1,276 @@
+//! Typed views of memory pinned to a panel: strings, arrays, linked lists and
+//! binary trees, redrawn from memory every update with changed words
+//! highlighted.
+//!
+//! See [`data_views`](crate::tui::data_views) for what views look like.
+
+use super::widget_impl_support::*;
+use crate::keymap::{Action, Scope};
+use crate::markers;
+use crate::tui::data_views::{DataView, Line};
+use crate::tui::prompt::{self, Outcome, Prompt};
+
+use lc3_isa::{Addr, Word};
+
+use std::collections::HashMap;
+
+const EXAMPLES: &[&str] = &[
+    "string MSG                       a null-terminated string",
+    "array x4000 10 [hex|dec|char]    10 words",
+    "list *HEAD [next=1] [value=0]    follows the pointer at HEAD",
+    "tree R0 [left=1] [right=2] [value=0]",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataViews {
+    focused: bool,
+    views: Vec<DataView>,
+    /// The view keys act on.
+    selected: usize,
+    /// The first view that's shown.
+    first: usize,
+
+    /// A view that's being typed in.
+    prompt: Option<Prompt>,
+    /// Whether the view being typed in replaces the selected one.
+    editing: bool,
+    /// Why the last thing asked of us didn't happen.
+    error: Option<String>,
+
+    /// The words the views showed as of the previous PC and as of now (for
+    /// highlighting changes, like the register view does).
+    previous: HashMap<Addr, Word>,
+    current: HashMap<Addr, Word>,
+    pc: Option<Addr>,
+}
+
+impl Default for DataViews {
+    fn default() -> Self {
+        Self {
+            focused: false,
+            views: Vec::new(),
+            selected: 0,
+            first: 0,
+            prompt: None,
+            editing: false,
+            error: None,
+            previous: HashMap::new(),
+            current: HashMap::new(),
+            pc: None,
+        }
+    }
+}
+
+impl DataViews {
+    // A title line for each view and then its lines (with how many lines
+    // that is).
+    fn render<'a, 'int, C, I, O>(&mut self, data: &TuiData<'a, 'int, C, I, O>) -> Vec<(usize, Vec<TuiText<'static>>)>
+    where
+        C: Control + ?Sized + 'a,
+        I: InputSink + ?Sized + 'a,
+        O: OutputSource + ?Sized + 'a,
+    {
+        let (regs, _, pc) = data.sim.get_registers_psr_and_pc();
+        if self.pc != Some(pc) {
+            self.previous = std::mem::take(&mut self.current);
+            self.pc = Some(pc);
+        }
+
+        let read = |addr: Addr| data.sim.read_word(addr);
+        let views: Vec<(String, Result<Vec<Line>, String>)> = self.views.iter().map(|view| {
+            match view.resolve(&data.symbols, &regs, pc, read) {
+                Ok(start) => (format!("{} @ x{:04X}", view, start), Ok(view.render(start, read))),
+                Err(err) => (view.to_string(), Err(err)),
+            }
+        }).collect();
+
+        let mut blocks = Vec::new();
+        for (idx, (title, lines)) in views.into_iter().enumerate() {
+            let selected = idx == self.selected;
+            let mut title_style = Style::default().fg(c!(Name));
+            if selected && self.focused {
+                title_style = title_style.modifier(Modifier::REVERSED);
+            }
+
+            let mut text = vec![TuiText::styled(format!("{} {}\n", if selected { "▸" } else { " " }, title), title_style)];
+            let height = 1 + lines.as_ref().map(|l| l.len()).unwrap_or(1);
+            match lines {
+                Ok(lines) => for line in lines {
+                    text.push(TuiText::raw("  "));
+                    for span in line {
+                        let addr = match span.addr {
+                            Some(addr) => addr,
+                            None => {
+                                text.push(TuiText::styled(span.text, Style::default().fg(c!(Disabled))));
+                                continue;
+                            },
+                        };
+
+                        let word = read(addr);
+                        let changed = self.previous.get(&addr).filter(|old| **old != word).is_some();
+                        let _ = self.current.insert(addr, word);
+
+                        if changed {
+                            let marker = if markers::enabled() { markers::CHANGED } else { "" };
+                            let style = Style::default().fg(c!(RegHighlight)).modifier(Modifier::BOLD);
+                            text.push(TuiText::styled(format!("{}{}", span.text, marker), style));
+                        } else {
+                            text.push(TuiText::styled(span.text, Style::default().fg(c!(Data))));
+                        }
+                    }
+                    text.push(TuiText::raw("\n"));
+                },
+                Err(err) => text.push(TuiText::styled(format!("  {}\n", err), Style::default().fg(c!(Error)))),
+            }
+
+            blocks.push((height, text));
+        }
+
+        blocks
+    }
+
+    fn select(&mut self, by: isize) {
+        if !self.views.is_empty() {
+            let len = self.views.len() as isize;
+            self.selected = (self.selected as isize + by).rem_euclid(len) as usize;
+        }
+    }
+
+    fn start_prompt(&mut self, editing: bool) {
+        self.editing = editing && !self.views.is_empty();
+        self.prompt = Some(Prompt::with_text((), if self.editing { self.views[self.selected].to_string() } else { String::new() }));
+        self.error = None;
+    }
+
+    fn finish_prompt(&mut self, src: String) {
+        match DataView::parse(&src) {
+            Ok(view) if self.editing => self.views[self.selected] = view,
+            Ok(view) => {
+                self.views.push(view);
+                self.selected = self.views.len() - 1;
+            },
+            Err(err) => {
+                // Let them fix it:
+                self.prompt = Some(Prompt::with_text((), src));
+                self.error = Some(err);
+            },
+        }
+    }
+}
+
+impl<'a, 'int, C, I, O, B> Widget<'a, 'int, C, I, O, B> for DataViews
+where
+    C: Control + ?Sized + 'a,
+    I: InputSink + ?Sized + 'a,
+    O: OutputSource + ?Sized + 'a,
+    B: Backend,
+{
+    fn draw(&mut self, data: &TuiData<'a, 'int, C, I, O>, area: Rect, buf: &mut Buffer) {
+        if area.height < 2 {
+            return;
+        }
+
+        let mut header = prompt::status_lines(
+            self.prompt.as_ref().map(|p| ((if self.editing { "Change view" } else { "New view" }).to_string(), p.text.as_str())),
+            self.error.as_deref().map(|err| (err, true)),
+        );
+        if self.prompt.is_none() {
+            header.insert(0, TuiText::styled(
+                format!(
+                    "{}: add · {}: change · {}: remove{}\n",
+                    data.keymap().describe(Action::DataViewsAdd),
+                    data.keymap().describe(Action::DataViewsEdit),
+                    data.keymap().describe(Action::DataViewsRemove),
+                    if self.focused { "" } else { " (focus first)" },
+                ),
+                Style::default().fg(c!(ConsoleHelp)),
+            ));
+        }
+
+        let header_height = (header.len() as u16 + 1).min(area.height);
+        Paragraph::new(header.iter())
+            .wrap(true)
+            .render(Rect { height: header_height, ..area }, buf);
+
+        let body = Rect { y: area.y + header_height, height: area.height - header_height, ..area };
+        if self.views.is_empty() {
+            let mut text = vec![TuiText::styled("No views yet; views look like:\n", Style::default().fg(c!(Disabled)))];
+            for example in EXAMPLES {
+                text.push(TuiText::styled(format!("  {}\n", example), Style::default().fg(c!(Disabled))));
+            }
+            text.push(TuiText::styled(
+                "\nViews start at an address, a label or a register (`*` follows the pointer there instead).\n",
+                Style::default().fg(c!(Disabled)),
+            ));
+
+            return Paragraph::new(text.iter()).wrap(true).render(body, buf);
+        }
+
+        let blocks = self.render(data);
+
+        // Scroll so the selected view is on screen (as much of it as fits):
+        self.first = self.first.min(self.selected);
+        while self.first < self.selected && blocks[self.first..=self.selected].iter().map(|(h, _)| h).sum::<usize>() > body.height as usize {
+            self.first += 1;
+        }
+
+        let text: Vec<_> = blocks.into_iter().skip(self.first).flat_map(|(_, t)| t).collect();
+        Paragraph::new(text.iter())
+            .style(Style::default().fg(Colour::White).bg(Colour::Reset))
+            .render(body, buf);
+    }
+
+    fn update(&mut self, event: WidgetEvent, data: &mut TuiData<'a, 'int, C, I, O>, _terminal: &mut Terminal<B>) -> bool {
+        use WidgetEvent::*;
+        use Action::*;
+
+        match event {
+            Focus(FocusEvent::GotFocus) => { self.focused = true; true },
+            Focus(FocusEvent::LostFocus) => {
+                self.focused = false;
+                self.prompt = None;
+                self.error = None;
+                true
+            },
+
+            Mouse(MouseEvent::ScrollUp(..)) => { self.select(-1); true },
+            Mouse(MouseEvent::ScrollDown(..)) => { self.select(1); true },
+            Mouse(_) => true,
+
+            // Typing a view:
+            Key(e) if self.prompt.is_some() => {
+                match prompt::key(&mut self.prompt, e) {
+                    Outcome::Entered((), src) => {
+                        self.error = None;
+                        self.finish_prompt(src);
+                    },
+                    Outcome::Cancelled => self.error = None,
+                    Outcome::Typing => {},
+                }
+
+                true
+            },
+
+            Key(e) => match data.keymap.lookup(&e, Scope::DataViews) {
+                Some(DataViewsPrev) => { self.select(-1); true },
+                Some(DataViewsNext) => { self.select(1); true },
+                Some(DataViewsAdd) => { self.start_prompt(false); true },
+                Some(DataViewsEdit) => { self.start_prompt(true); true },
+                Some(DataViewsRemove) => {
+                    if self.selected < self.views.len() {
+                        let _ = self.views.remove(self.selected);
+                        self.selected = self.selected.min(self.views.len().saturating_sub(1));
+                    }
+                    true
+                },
+                _ => false,
+            },
+
+            _ => false,
+        }
+    }
+
+    fn wants_key(&self, _key: &KeyEvent, _data: &TuiData<'a, 'int, C, I, O>) -> bool {
+        self.prompt.is_some()
+    }
+}
@@ -1,20 +1,34 @@
-//! TODO!
+//! The GPIO pins, drawn as LEDs; input pins can be toggled and pulsed (with
+//! keys or by clicking on them).
 
 use super::widget_impl_support::*;
+use crate::keymap::{Action, Scope};
+use crate::tui::peripheral_commands::GPIO_PINS;
 
-use lc3_traits::peripherals::gpio::{GpioPin, GpioState};
+use lc3_traits::peripherals::gpio::GpioState;
 
-use std::sync::RwLock;
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Gpio {
+    /// Whether the pins can be driven from here (the widget is display-only
+    /// otherwise).
     pub focusable: bool,
+    focused: bool,
+    /// The pin keys act on.
+    selected: usize,
+    /// Where each pin was last drawn, for clicks.
+    rows: [Rect; 8],
+    /// Why the last thing asked of us didn't happen.
+    error: Option<String>,
 }
 
 impl Default for Gpio {
     fn default() -> Self {
         Self {
             focusable: false,
+            focused: false,
+            selected: 0,
+            rows: [Rect::default(); 8],
+            error: None,
         }
     }
 }
@@ -24,6 +38,50 @@ impl Gpio {
         self.focusable = focusable;
         self
     }
+
+    fn toggle<'a, 'int, C, I, O>(&mut self, idx: usize, data: &mut TuiData<'a, 'int, C, I, O>)
+    where
+        C: Control + ?Sized + 'a,
+        I: InputSink + ?Sized + 'a,
+        O: OutputSource + ?Sized + 'a,
+    {
+        self.selected = idx;
+
+        let level = data.gpio_level(idx).unwrap_or(false);
+        let res = data.set_gpio_input(idx, !level);
+        self.report(res, data);
+    }
+
+    fn pulse<'a, 'int, C, I, O>(&mut self, idx: usize, data: &mut TuiData<'a, 'int, C, I, O>)
+    where
+        C: Control + ?Sized + 'a,
+        I: InputSink + ?Sized + 'a,
+        O: OutputSource + ?Sized + 'a,
+    {
+        self.selected = idx;
+
+        let res = data.pulse_gpio_input(idx);
+        self.report(res, data);
+    }
+
+    fn report<'a, 'int, C, I, O>(&mut self, res: Result<(), String>, data: &mut TuiData<'a, 'int, C, I, O>)
+    where
+        C: Control + ?Sized + 'a,
+        I: InputSink + ?Sized + 'a,
+        O: OutputSource + ?Sized + 'a,
+    {
+        self.error = match res {
+            Ok(()) => None,
+            Err(err) => {
+                data.log_at(Level::Warn, format!("[GPIO] {}\n", err), c!(InvalidInput));
+                Some(err)
+            },
+        };
+    }
+
+    fn pin_at(&self, col: u16, row: u16) -> Option<usize> {
+        self.rows.iter().position(|r| r.intersects(Rect::new(col, row, 1, 1)))
+    }
 }
 
 impl<'a, 'int, C, I, O, B> Widget<'a, 'int, C, I, O, B> for Gpio
@@ -33,178 +91,122 @@ where
     O: OutputSource + ?Sized + 'a,
     B: Backend,
 {
-
     fn draw(&mut self, data: &TuiData<'a, 'int, C, I, O>, area: Rect, buf: &mut Buffer) {
-        let gpio_states = data.sim.get_gpio_states();
-        let gpioin = data.sim.get_gpio_readings();
-
-
-        let text = [
-            TuiText::styled("GPIO 0: \nGPIO 1: \nGPIO 2: \nGPIO 3: \n", Style::default().fg(c!(Name))),
-        ];
-
-        let mut para = Paragraph::new(text.iter())
-            .style(Style::default().fg(Colour::White).bg(Colour::Reset))
-            .alignment(Alignment::Left)
-            .wrap(true);
-
-        para.render(area, buf);
-
-
-        let mut s1 = String::from("");
-
-        let gpio_pins_1 = [GpioPin::G0, GpioPin::G1, GpioPin::G2, GpioPin::G3];
-        let gpio_pins_2 = [GpioPin::G4, GpioPin::G5, GpioPin::G6, GpioPin::G7];
-        //let gpio_pins_2 = [GpioPin::G0, GpioPin::G1, GpioPin::G2, GpioPin::G3, GpioPin::G4, GpioPin::G5, GpioPin::G6, GpioPin::G7];
-        for i in 0..4 {
-            match gpio_states[gpio_pins_1[i]]{
-                GpioState::Disabled => {
-                    let disabled_string = "Disabled";
-                    s1.push_str(&format!("{}\n",
-                    disabled_string, ));
-                }
-                GpioState::Output => {
-                    let mut output_string = String::from("Output");
-
-                    if let Some(shims) = &data.shims {
-                        match RwLock::read(&shims.gpio).unwrap().get_pin(gpio_pins_1[i]).unwrap() {
-                            true => output_string.push_str(": 1"),
-                            false => output_string.push_str(": 0"),
-                        }
-                    }
-
-                    s1.push_str(&format!("{}\n",
-                    output_string, ));
-                },
-
-
-                GpioState::Input => {
-                    match gpioin[gpio_pins_1[i]] {
-                        Ok(val) => {
-                                s1.push_str(&format!(
-                                "Input: {}\n",
-                                val,
-
-                                ));
-                            }
-                        _ => {
-                            let err_string = "-";
-                            s1.push_str(&format!(
-                                "Input: {}\n",
-                                err_string,
-
-                                ));
-                        }
-                        }
-                },
-
-                GpioState::Interrupt => {
-                    match gpioin[gpio_pins_1[i]] {
-                        Ok(val) => {
-                                s1.push_str(&format!(
-                                "Interrupt: {}\n",
-                                val,
-
-                                ));
-                            }
-                        _ => {
-                            let err_string = "-";
-                            s1.push_str(&format!(
-                                "Interrupt: {}\n",
-                                err_string,
-
-                                ));
-                        }
-                        }
-                }
+        let states = data.sim.get_gpio_states();
+
+        // One column of pins if they fit, two otherwise; the line at the
+        // bottom (if there's room for it) is for hints and errors.
+        let columns = if area.height as usize > GPIO_PINS.len() { 1 } else { 2 };
+        let per_column = GPIO_PINS.len() / columns;
+        let width = area.width / columns as u16;
+
+        for (idx, pin) in GPIO_PINS.iter().enumerate() {
+            let (column, row) = (idx / per_column, idx % per_column);
+            if row as u16 >= area.height {
+                self.rows[idx] = Rect::default();
+                continue;
             }
-        }
-
-        let text = [TuiText::styled(s1, Style::default().fg(c!(Data)))];
-        para = Paragraph::new(text.iter())
-            .style(Style::default().fg(Colour::White).bg(Colour::Reset))
-            .alignment(Alignment::Left)
-            .wrap(true);
-        let area = increment(10, Axis::X, area);
-        para.render(area, buf);
-
 
+            let rect = Rect::new(area.x + column as u16 * width, area.y + row as u16, width, 1);
+            self.rows[idx] = rect;
+
+            let (mode, input) = match states[*pin] {
+                GpioState::Disabled => ("disabled", false),
+                GpioState::Output => ("output", false),
+                GpioState::Input => ("input", true),
+                GpioState::Interrupt => ("interrupt", true),
+            };
+
+            let disabled = matches!(states[*pin], GpioState::Disabled);
+            let (led, led_colour, level) = match (disabled, data.gpio_level(idx)) {
+                (true, _) => ("·", c!(Disabled), "-"),
+                (false, Some(true)) => ("●", c!(Success), "1"),
+                (false, Some(false)) => ("○", c!(Name), "0"),
+                (false, None) => ("?", c!(Error), "?"),
+            };
+
+            let mut label = Style::default().fg(c!(Name));
+            if self.focused && idx == self.selected {
+                label = label.modifier(Modifier::REVERSED);
+            }
 
-        let text = [
-            TuiText::styled("GPIO 4: \nGPIO 5: \nGPIO 6: \nGPIO 7: \n", Style::default().fg(c!(Name))),
+            let text = [
+                TuiText::styled(format!("{} ", led), Style::default().fg(led_colour)),
+                TuiText::styled(format!("GPIO {}", idx), label),
+                TuiText::styled(format!(" {:9} ", mode), Style::default().fg(if input { c!(Data) } else { c!(Name) })),
+                TuiText::styled(level, Style::default().fg(c!(Data))),
             ];
 
-        let mut para = Paragraph::new(text.iter())
-        .style(Style::default().fg(Colour::White).bg(Colour::Reset))
-        .alignment(Alignment::Left)
-        .wrap(true);
-        let area = increment(40, Axis::X, area);
-        para.render(area, buf);
-
-        let mut s2 = String::from("");
-
-
+            Paragraph::new(text.iter())
+                .style(Style::default().fg(Colour::White).bg(Colour::Reset))
+                .render(rect, buf);
+        }
 
-            for i in 0..4 {
-                match gpio_states[gpio_pins_2[i]]{
-                    GpioState::Disabled => {
-                        let disabled_string = "Disabled";
-                        s2.push_str(&format!("{}\n",
-                        disabled_string, ));
-                    },
-                    GpioState::Output => {
-                        let mut output_string = String::from("Output");
-
-                        if let Some(shims) = &data.shims {
-                            match RwLock::read(&shims.gpio).unwrap().get_pin(gpio_pins_2[i]).unwrap() {
-                                true => output_string.push_str(": 1"),
-                                false => output_string.push_str(": 0"),
-                            }
-                        }
-
-                        s2.push_str(&format!("{}\n",
-                        output_string, ));
-                    },
-                    _ => {
-                        match gpioin[gpio_pins_2[i]] {
-                            Ok(val) => {
-                                    s2.push_str(&format!(
-                                    "{}\n",
-                                    val,
-
-                                    ));
-                                }
-                            _ => {
-                                let err_string = "-";
-                                s2.push_str(&format!(
-                                    "{}\n",
-                                    err_string,
-
-                                    ));
-                            }
-                            }
-                    }
-                }
-            }
+        let pin_rows = per_column as u16;
+        if !self.focusable || area.height <= pin_rows {
+            return;
+        }
 
-            let text = [TuiText::styled(s2, Style::default().fg(c!(Data)))];
-            para = Paragraph::new(text.iter())
-                .style(Style::default().fg(Colour::White).bg(Colour::Reset))
-                .alignment(Alignment::Left)
-                .wrap(true);
+        let hint = match &self.error {
+            Some(err) => TuiText::styled(err.clone(), Style::default().fg(c!(Error))),
+            None => TuiText::styled(
+                format!(
+                    "0-7 or {}: toggle · {}: pulse · click to toggle, right click to pulse",
+                    data.keymap().describe(Action::GpioToggle),
+                    data.keymap().describe(Action::GpioPulse),
+                ),
+                Style::default().fg(c!(ConsoleHelp)),
+            ),
+        };
+
+        Paragraph::new([hint].iter())
+            .style(Style::default().bg(Colour::Reset))
+            .wrap(true)
+            .render(Rect { y: area.y + pin_rows, height: area.height - pin_rows, ..area }, buf);
+    }
 
-            let area = increment(10, Axis::X, area);
-            para.render(area, buf);
+    fn update(&mut self, event: WidgetEvent, data: &mut TuiData<'a, 'int, C, I, O>, _terminal: &mut Terminal<B>) -> bool {
+        use WidgetEvent::*;
+        use Action::*;
+        const EMPTY: KeyModifiers = KeyModifiers::empty();
 
+        if !self.focusable {
+            return false;
+        }
 
-    }
+        match event {
+            Focus(FocusEvent::GotFocus) => { self.focused = true; true },
+            Focus(FocusEvent::LostFocus) => { self.focused = false; self.error = None; true },
 
+            Mouse(MouseEvent::Down(button, col, row, _)) => {
+                self.focused = true;
 
+                match (button, self.pin_at(col, row)) {
+                    (MouseButton::Left, Some(idx)) => self.toggle(idx, data),
+                    (MouseButton::Right, Some(idx)) => self.pulse(idx, data),
+                    _ => {},
+                }
 
+                true
+            },
+            Mouse(_) => true,
+
+            Key(e) => match data.keymap.lookup(&e, Scope::Gpio) {
+                Some(GpioPrev) => { self.selected = (self.selected + GPIO_PINS.len() - 1) % GPIO_PINS.len(); true },
+                Some(GpioNext) => { self.selected = (self.selected + 1) % GPIO_PINS.len(); true },
+                Some(GpioToggle) => { self.toggle(self.selected, data); true },
+                Some(GpioPulse) => { self.pulse(self.selected, data); true },
+                Some(_) => false,
+
+                None => match e {
+                    KeyEvent { code: KeyCode::Char(c @ '0'..='7'), modifiers: EMPTY } => {
+                        self.toggle(c as usize - '0' as usize, data);
+                        true
+                    },
+                    _ => false,
+                },
+            },
 
-    fn update(&mut self, event: WidgetEvent, _data: &mut TuiData<'a, 'int, C, I, O>, _terminal: &mut Terminal<B>) -> bool {
-        match event {
-            WidgetEvent::Mouse(_) | WidgetEvent::Focus(FocusEvent::GotFocus) => self.focusable,
             _ => false,
         }
     }
@@ -1,21 +1,57 @@
-//! TODO!
+//! Every key binding, by scope, generated from the current keymap (so that it
+//! follows users' rebindings).
 
 use super::widget_impl_support::*;
+use crate::keymap::{Action, Scope};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Help
 {
-    pub focusable: bool
+    pub focusable: bool,
+    offset: u16,
+    /// How tall we were as of the last draw (for paging).
+    height: u16,
 }
 
 impl Default for Help {
     fn default() -> Self {
         Self {
             focusable: true,
+            offset: 0,
+            height: 0,
         }
     }
 }
 
+impl Help {
+    pub fn focusable(mut self, focusable: bool) -> Self {
+        self.focusable = focusable;
+        self
+    }
+}
+
+// How many rows `text` takes up when wrapped to `width`.
+fn rows(text: &[TuiText<'_>], width: u16) -> u16 {
+    let width = width.max(1) as usize;
+    let (mut rows, mut line) = (0, 0);
+
+    for span in text {
+        let s = match span {
+            TuiText::Raw(s) | TuiText::Styled(s, _) => s,
+        };
+
+        for c in s.chars() {
+            if c == '\n' {
+                rows += 1 + line.saturating_sub(1) / width;
+                line = 0;
+            } else {
+                line += 1;
+            }
+        }
+    }
+
+    rows as u16
+}
 
 impl<'a, 'int, C, I, O, B> Widget<'a, 'int, C, I, O, B> for Help
 where
@@ -25,33 +61,70 @@ where
     B: Backend,
 {
     fn draw(&mut self, data: &TuiData<'a, 'int, C, I, O>, area: Rect, buf: &mut Buffer) {
-        let text = [
-            TuiText::styled("To control the TUI, you can click on any of the interactable interfaces.\n
-                Most will respond to mouse events, some will respond to sroll.\n
-                You can also use keyboard events to navigate.\n
-                Tab and Ctrl tab swaps back and forth between tabs.\n
-                FN + a number jumps to the tab of that number directly.\n
-                Ctrl + a diretional arrow also moves between widgets.\n
-                A lot of widgets have their own keybinds. See the full documentation for assistance.\n
-                Finally, there are universal keybinds:\n
-                Ctrl: + l to load, + r to run, + p to pause, + s to step\n
-                Ctrl: + t twice to reset.\n
-                Ctrl + u for Step Over, Ctrl + i for Step In (== Step), Ctrl + o for Step Out (these binds also work with Alt — i.e. Alt + u — for machines with issues with Ctrl)", Style::default().fg(c!(Help))),
+        let mut text = vec![
+            TuiText::styled("Most widgets can be clicked on to focus them and respond to the mouse (and scrolling).\n\
+                These are the key bindings (change them in the `[keys]` table of the config file):\n", Style::default().fg(c!(Help))),
         ];
 
+        let keymap = data.keymap();
+        for scope in Scope::ALL {
+            text.push(TuiText::styled(format!("\n{}:\n", scope.title()), Style::default().fg(c!(Title))));
+
+            for action in Action::ALL.iter().filter(|a| a.scope() == *scope) {
+                text.push(TuiText::styled(format!("  {:<24}", keymap.describe(*action)), Style::default().fg(c!(Name))));
+                text.push(TuiText::styled(format!("{}\n", action.description()), Style::default().fg(c!(Help))));
+            }
+        }
+
+        // Don't scroll past the end:
+        self.height = area.height;
+        self.offset = self.offset.min(rows(&text, area.width).saturating_sub(area.height));
+
         // TODO: allow parameterization of this in the usual way.
         let mut para = Paragraph::new(text.iter())
             .style(Style::default().fg(Colour::White).bg(Colour::Reset))
             .alignment(Alignment::Left)
+            .scroll(self.offset)
             .wrap(true);
 
         para.render(area, buf)
     }
 
-    fn update(&mut self, event: WidgetEvent, _data: &mut TuiData<'a, 'int, C, I, O>, _terminal: &mut Terminal<B>) -> bool {
+    fn update(&mut self, event: WidgetEvent, data: &mut TuiData<'a, 'int, C, I, O>, _terminal: &mut Terminal<B>) -> bool {
+        use WidgetEvent::*;
+        use Action::*;
+
+        // `draw` keeps `offset` from going past the end.
         match event {
-            WidgetEvent::Mouse(_) | WidgetEvent::Focus(FocusEvent::GotFocus) => self.focusable,
+            Mouse(MouseEvent::ScrollUp(_, _, _)) if self.focusable => { self.offset = self.offset.saturating_sub(1); true },
+            Mouse(MouseEvent::ScrollDown(_, _, _)) if self.focusable => { self.offset = self.offset.saturating_add(1); true },
+
+            Key(e) if self.focusable => match data.keymap.lookup(&e, Scope::Help) {
+                Some(HelpScrollUp) => { self.offset = self.offset.saturating_sub(1); true },
+                Some(HelpScrollDown) => { self.offset = self.offset.saturating_add(1); true },
+                Some(HelpPageUp) => { self.offset = self.offset.saturating_sub(self.height.max(1)); true },
+                Some(HelpPageDown) => { self.offset = self.offset.saturating_add(self.height.max(1)); true },
+                Some(HelpTop) => { self.offset = 0; true },
+                _ => false,
+            },
+
+            Mouse(_) | Focus(FocusEvent::GotFocus) => self.focusable,
             _ => false,
         }
     }
 }
+
+#[cfg(test)]
+mod help_tests {
+    use super::*;
+    use pretty_assertions::assert_eq as eq;
+
+    #[test]
+    fn wrapped_rows() {
+        let text = [TuiText::raw("abcdef\n\n"), TuiText::raw("ab"), TuiText::raw("c\n")];
+
+        eq!(rows(&text, 80), 3);
+        eq!(rows(&text, 3), 4);
+        eq!(rows(&text, 2), 6);
+    }
+}
@@ -0,0 +1,301 @@
+//! Why the processor is (or was) in supervisor mode: the trap and interrupt
+//! vector tables, which peripherals can raise interrupts (and which have one
+//! waiting), and the traps and interrupts that have been taken.
+//!
+//! See [`interrupts`](crate::tui::interrupts) for how taken traps and
+//! interrupts are spotted.
+
+use super::widget_impl_support::*;
+use crate::keymap::{Action, Scope};
+use crate::tui::interrupts::{interrupt_name, Cause, INTERRUPT_VECTOR_TABLE, TRAP_VECTOR_TABLE, VECTOR_TABLE_LEN};
+use crate::tui::symbols::trap_name;
+use crate::tui::time::now;
+
+use lc3_isa::{Addr, Bits, Word};
+
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Row {
+    Heading(&'static str),
+    Entry { addr: Addr, name: Option<&'static str>, handler: Word, unused: bool },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Interrupts {
+    focused: bool,
+    /// The first row of the vector tables that's shown.
+    first: usize,
+    hide_unused: bool,
+
+    /// Where the vector tables were last drawn.
+    tables: Rect,
+}
+
+impl Default for Interrupts {
+    fn default() -> Self {
+        Self {
+            focused: false,
+            first: 0,
+            hide_unused: false,
+            tables: Rect::default(),
+        }
+    }
+}
+
+// The entries of the table at `start`; the ones sharing the most common
+// handler (when more than half of them do) are the unused ones.
+fn table<'a, 'int, C, I, O>(data: &TuiData<'a, 'int, C, I, O>, start: Addr, name: fn(u8) -> Option<&'static str>) -> Vec<Row>
+where
+    C: Control + ?Sized + 'a,
+    I: InputSink + ?Sized + 'a,
+    O: OutputSource + ?Sized + 'a,
+{
+    let handlers: Vec<Word> = (0..VECTOR_TABLE_LEN).map(|v| data.sim.read_word(start + v)).collect();
+
+    let mut counts = HashMap::new();
+    for handler in handlers.iter() {
+        *counts.entry(*handler).or_insert(0) += 1;
+    }
+    let unused = counts.into_iter()
+        .max_by_key(|(_, count)| *count)
+        .filter(|(_, count)| *count > handlers.len() / 2)
+        .map(|(handler, _)| handler);
+
+    handlers.into_iter().enumerate().map(|(v, handler)| Row::Entry {
+        addr: start + v as Addr,
+        name: name(v as u8),
+        handler,
+        unused: Some(handler) == unused,
+    }).collect()
+}
+
+impl Interrupts {
+    fn rows<'a, 'int, C, I, O>(&self, data: &TuiData<'a, 'int, C, I, O>) -> Vec<Row>
+    where
+        C: Control + ?Sized + 'a,
+        I: InputSink + ?Sized + 'a,
+        O: OutputSource + ?Sized + 'a,
+    {
+        let mut rows = vec![Row::Heading("Trap vector table")];
+        rows.extend(table(data, TRAP_VECTOR_TABLE, trap_name));
+        rows.push(Row::Heading("Interrupt vector table"));
+        rows.extend(table(data, INTERRUPT_VECTOR_TABLE, interrupt_name));
+
+        if self.hide_unused {
+            rows.retain(|r| !matches!(r, Row::Entry { unused: true, .. }));
+        }
+
+        rows
+    }
+
+    fn scroll(&mut self, by: isize) {
+        self.first = (self.first as isize + by).max(0) as usize;
+    }
+
+    // Scrolls so the heading for the table starting at `start` is at the top.
+    fn jump_to<'a, 'int, C, I, O>(&mut self, start: Addr, data: &TuiData<'a, 'int, C, I, O>)
+    where
+        C: Control + ?Sized + 'a,
+        I: InputSink + ?Sized + 'a,
+        O: OutputSource + ?Sized + 'a,
+    {
+        let heading = if start == TRAP_VECTOR_TABLE { "Trap vector table" } else { "Interrupt vector table" };
+        self.first = self.rows(data).iter().position(|r| *r == Row::Heading(heading)).unwrap_or(0);
+    }
+
+    fn draw_tables<'a, 'int, C, I, O>(&mut self, data: &TuiData<'a, 'int, C, I, O>, area: Rect, buf: &mut Buffer)
+    where
+        C: Control + ?Sized + 'a,
+        I: InputSink + ?Sized + 'a,
+        O: OutputSource + ?Sized + 'a,
+    {
+        let rows = self.rows(data);
+        self.tables = area;
+        self.first = self.first.min(rows.len().saturating_sub(area.height as usize));
+
+        // The entry the last trap or interrupt we know the cause of came
+        // through:
+        let last = data.interrupts.taken.iter().rev().find_map(|t| match t.cause {
+            Cause::Trap(v) => Some(TRAP_VECTOR_TABLE + v as Addr),
+            Cause::Interrupt(v) => Some(INTERRUPT_VECTOR_TABLE + v as Addr),
+            Cause::Unknown => None,
+        });
+
+        let mut text = Vec::new();
+        for row in rows.iter().skip(self.first).take(area.height as usize) {
+            match *row {
+                Row::Heading(title) => text.push(TuiText::styled(format!("{}\n", title), Style::default().fg(c!(Name)).modifier(Modifier::BOLD))),
+                Row::Entry { addr, name, handler, unused } => {
+                    let mut style = Style::default().fg(if unused { c!(Disabled) } else { c!(Data) });
+                    if Some(addr) == last {
+                        style = style.modifier(Modifier::REVERSED);
+                    }
+
+                    text.push(TuiText::styled(format!("x{:04X} ", addr), Style::default().fg(c!(Addr))));
+                    text.push(TuiText::styled(format!("{:<26}", name.unwrap_or("")), style));
+                    text.push(TuiText::styled(format!("→ x{:04X}", handler), style));
+                    text.push(TuiText::styled(
+                        format!(" {}\n", data.symbols.name(handler).unwrap_or("")),
+                        Style::default().fg(c!(Name)),
+                    ));
+                },
+            }
+        }
+
+        Paragraph::new(text.iter())
+            .style(Style::default().fg(Colour::White).bg(Colour::Reset))
+            .render(area, buf);
+    }
+
+    fn draw_status<'a, 'int, C, I, O>(&self, data: &TuiData<'a, 'int, C, I, O>, area: Rect, buf: &mut Buffer)
+    where
+        C: Control + ?Sized + 'a,
+        I: InputSink + ?Sized + 'a,
+        O: OutputSource + ?Sized + 'a,
+    {
+        let name = Style::default().fg(c!(Name));
+        let data_style = Style::default().fg(c!(Data));
+
+        let (_, psr, _) = data.sim.get_registers_psr_and_pc();
+        let mode = if psr.bit(15) { "User" } else { "Supervisor" };
+        let priority = psr.bits(8..10);
+
+        let mut text = vec![
+            TuiText::styled("Mode: ", name),
+            TuiText::styled(mode, data_style),
+            TuiText::styled("  Priority: ", name),
+            TuiText::styled(priority.to_string(), data_style),
+            TuiText::styled(format!(" (only interrupts above {} are taken)\n", priority), Style::default().fg(c!(Disabled))),
+        ];
+
+        let sources = data.interrupt_sources();
+        let list = |names: Vec<&str>| if names.is_empty() { "none".to_string() } else { names.join(", ") };
+
+        text.push(TuiText::styled("Enabled: ", name));
+        text.push(TuiText::styled(
+            format!("{}\n", list(sources.iter().filter(|s| s.enabled).map(|s| s.name.as_str()).collect())),
+            data_style,
+        ));
+
+        text.push(TuiText::styled("Pending: ", name));
+        if sources.iter().any(|s| s.pending.is_none()) {
+            text.push(TuiText::styled("unknown (the simulator's peripherals aren't available)\n", Style::default().fg(c!(Disabled))));
+        } else {
+            let pending = list(sources.iter().filter(|s| s.pending == Some(true)).map(|s| s.name.as_str()).collect());
+            text.push(TuiText::styled(format!("{}\n", pending), Style::default().fg(c!(Pause))));
+        }
+
+        let history = &data.interrupts;
+        text.push(TuiText::styled(format!("\nTaken ({} in all, newest first):\n", history.count), name));
+        if history.taken.is_empty() {
+            text.push(TuiText::styled("None yet.\n", Style::default().fg(c!(ConsoleHelp))));
+        }
+        text.push(TuiText::styled(
+            "While running, ones that start and finish between updates and most taken from supervisor mode are missed; step to see them all.\n",
+            Style::default().fg(c!(Disabled)),
+        ));
+
+        let now = now();
+        for taken in history.taken.iter().rev() {
+            let ago = now.saturating_sub(taken.at);
+            let colour = if taken.cause == Cause::Unknown { c!(Disabled) } else { c!(Success) };
+
+            text.push(TuiText::styled(format!("{:>4}.{:03}s ago  ", ago / 1000, ago % 1000), data_style));
+            text.push(TuiText::styled(taken.cause.to_string(), Style::default().fg(colour)));
+
+            match taken.saved {
+                Some((pc, saved_psr)) => {
+                    let label = data.symbols.describe(pc, 16).map(|l| format!(" {}", l)).unwrap_or_default();
+                    text.push(TuiText::styled(format!("  from x{:04X}{}", pc, label), Style::default().fg(c!(Addr))));
+                    text.push(TuiText::styled(format!("  PSR x{:04X}\n", saved_psr), Style::default().fg(c!(Disabled))));
+                },
+                None => text.push(TuiText::styled(format!("  (caught mid-handler at x{:04X})\n", taken.pc), Style::default().fg(c!(Disabled)))),
+            }
+        }
+
+        Paragraph::new(text.iter())
+            .style(Style::default().fg(Colour::White).bg(Colour::Reset))
+            .wrap(true)
+            .render(area, buf);
+    }
+}
+
+impl<'a, 'int, C, I, O, B> Widget<'a, 'int, C, I, O, B> for Interrupts
+where
+    C: Control + ?Sized + 'a,
+    I: InputSink + ?Sized + 'a,
+    O: OutputSource + ?Sized + 'a,
+    B: Backend,
+{
+    fn draw(&mut self, data: &TuiData<'a, 'int, C, I, O>, area: Rect, buf: &mut Buffer) {
+        if area.height < 2 {
+            return;
+        }
+
+        let mut header = format!(
+            "{} entries{}",
+            if self.hide_unused { "Used" } else { "All" },
+            if self.focused { "" } else { " (focus to scroll)" },
+        );
+        if self.focused {
+            header.push_str(&format!(
+                " · {}: traps, {}: interrupts, {}: hide/show unused",
+                data.keymap().describe(Action::InterruptsTraps),
+                data.keymap().describe(Action::InterruptsVectors),
+                data.keymap().describe(Action::InterruptsHideUnused),
+            ));
+        }
+        Paragraph::new([TuiText::styled(header, Style::default().fg(c!(Disabled)))].iter())
+            .render(Rect { height: 1, ..area }, buf);
+
+        let body = Rect { y: area.y + 1, height: area.height - 1, ..area };
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Length(48), Constraint::Min(0)].as_ref())
+            .split(body);
+
+        self.draw_tables(data, chunks[0], buf);
+        self.draw_status(data, chunks[1], buf);
+    }
+
+    fn update(&mut self, event: WidgetEvent, data: &mut TuiData<'a, 'int, C, I, O>, _terminal: &mut Terminal<B>) -> bool {
+        use WidgetEvent::*;
+        use Action::*;
+
+        let page = self.tables.height.max(1) as isize;
+
+        match event {
+            Focus(FocusEvent::GotFocus) => { self.focused = true; true },
+            Focus(FocusEvent::LostFocus) => { self.focused = false; true },
+
+            Mouse(MouseEvent::ScrollUp(..)) => { self.scroll(-3); true },
+            Mouse(MouseEvent::ScrollDown(..)) => { self.scroll(3); true },
+            Mouse(_) => true,
+
+            Key(e) => {
+                match data.keymap.lookup(&e, Scope::Interrupts) {
+                    Some(InterruptsUp) => self.scroll(-1),
+                    Some(InterruptsDown) => self.scroll(1),
+                    Some(InterruptsPageUp) => self.scroll(-page),
+                    Some(InterruptsPageDown) => self.scroll(page),
+                    Some(InterruptsTraps) => self.jump_to(TRAP_VECTOR_TABLE, data),
+                    Some(InterruptsVectors) => self.jump_to(INTERRUPT_VECTOR_TABLE, data),
+                    Some(InterruptsHideUnused) => {
+                        self.hide_unused = !self.hide_unused;
+                        self.first = 0;
+                    },
+                    Some(InterruptsClear) => {
+                        data.interrupts.clear();
+                        data.log("[Interrupts] Cleared the taken traps and interrupts.\n", c!(Success));
+                    },
+                    _ => return false,
+                }
+
+                true
+            },
+
+            _ => false,
+        }
+    }
+}
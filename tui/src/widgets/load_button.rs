@@ -5,6 +5,7 @@ use crate::tui::program_source::{
     file_requires_assembly,
     assemble_mem_dump,
 };
+use crate::tui::symbols::Symbols;
 use super::widget_impl_support::*;
 
 use lc3_traits::control::load::{load_whole_memory_dump, Progress, LoadMemoryProgress};
@@ -445,12 +446,15 @@ where
                     Some(ref p) => {
                         match self.load(data.sim, terminal, p, data.use_os) {
                             Ok(msg) => {
+                                data.symbols = p.assembly().map(|s| Symbols::from_source(&s)).unwrap_or_default();
                                 self.attempt = Attempt::succeeded();
+                                data.diagnostics = None;
                                 data.log(format!("[Load] {}\n", msg), c!(Success))
                             },
                             Err(msg) => {
                                 self.attempt = Attempt::failed();
-                                data.log(format!("[Load] {}\n", msg), c!(Error))
+                                data.diagnostics = Some(msg.to_string());
+                                data.log_at(Level::Error, format!("[Load] {}\n", msg), c!(Error))
                             },
                         }
 
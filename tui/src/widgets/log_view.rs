@@ -0,0 +1,242 @@
+//! The program log, with filters (by level, by source, and by text), scrolling,
+//! and exporting.
+
+use super::widget_impl_support::*;
+use crate::keymap::{Action, Scope};
+use crate::tui::program_log::LogEntry;
+use crate::tui::prompt::{self, Outcome, Prompt};
+
+// How many rows one notch of the mouse wheel scrolls.
+const SCROLL_STEP: usize = 3;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LogView {
+    /// How many rows back from the newest entry we're scrolled; when this is
+    /// 0 we follow new entries.
+    scroll: usize,
+
+    /// Entries below this level are hidden.
+    min_level: Level,
+    /// When set, only entries from this source are shown.
+    source: Option<String>,
+    /// When set, only entries containing this (lowercase) text are shown.
+    search: Option<String>,
+    /// The search that's being typed in, if there is one.
+    prompt: Option<Prompt>,
+
+    /// How many rows of entries fit, as of the last draw.
+    height: usize,
+}
+
+impl Default for LogView {
+    fn default() -> Self {
+        Self {
+            scroll: 0,
+            min_level: Level::Info,
+            source: None,
+            search: None,
+            prompt: None,
+            height: 0,
+        }
+    }
+}
+
+impl LogView {
+    fn shows(&self, entry: &LogEntry<'_>) -> bool {
+        // Always show the marker for dropped entries:
+        if entry.at.is_none() {
+            return true;
+        }
+
+        entry.level >= self.min_level
+            && (self.source.is_none() || entry.source == self.source)
+            && self.search.as_ref().map(|s| entry.contains(s)).unwrap_or(true)
+    }
+
+    fn page(&self) -> usize {
+        self.height.saturating_sub(1).max(1)
+    }
+
+    // Moves on to the next source (in the order they first showed up in the
+    // log), going back to showing every source after the last one.
+    fn cycle_source(&mut self, entries: &[LogEntry<'_>]) {
+        let mut sources: Vec<&String> = Vec::new();
+        for source in entries.iter().filter_map(|e| e.source.as_ref()) {
+            if !sources.contains(&source) {
+                sources.push(source);
+            }
+        }
+
+        let next = match &self.source {
+            None => 0,
+            Some(current) => sources.iter().position(|s| *s == current).map(|i| i + 1).unwrap_or(0),
+        };
+
+        self.source = sources.get(next).map(|s| s.to_string());
+        self.scroll = 0;
+    }
+
+    fn level_style(level: Level) -> Style {
+        match level {
+            Level::Debug => Style::default().fg(c!(Border)).modifier(Modifier::DIM),
+            Level::Info => Style::default().fg(c!(Border)),
+            Level::Warn => Style::default().fg(c!(Pause)),
+            Level::Error => Style::default().fg(c!(Error)).modifier(Modifier::BOLD),
+        }
+    }
+}
+
+impl<'a, 'int, C, I, O, B> Widget<'a, 'int, C, I, O, B> for LogView
+where
+    C: Control + ?Sized + 'a,
+    I: InputSink + ?Sized + 'a,
+    O: OutputSource + ?Sized + 'a,
+    B: Backend,
+{
+    fn draw(&mut self, data: &TuiData<'a, 'int, C, I, O>, area: Rect, buf: &mut Buffer) {
+        if area.height == 0 {
+            return;
+        }
+
+        let header = Rect { height: 1, ..area };
+        let body = Rect { y: area.y + 1, height: area.height - 1, ..area };
+        self.height = body.height as usize;
+
+        // We go backwards from the newest entry until we've got enough rows to
+        // fill the body at our scroll position:
+        let mut shown = 0;
+        let mut rows: Vec<Vec<TuiText<'_>>> = Vec::new();
+        for entry in data.log.iter().rev().filter(|e| self.shows(e)) {
+            shown += 1;
+
+            if rows.len() >= self.scroll.saturating_add(self.height) {
+                continue;
+            }
+
+            let prefix = entry.prefix();
+            let indent = " ".repeat(prefix.chars().count());
+            let style = match &entry.text {
+                TuiText::Raw(_) => Style::default(),
+                TuiText::Styled(_, style) => *style,
+            };
+
+            let lines: Vec<&str> = entry.message().split('\n').collect();
+            for (idx, line) in lines.into_iter().enumerate().rev() {
+                let lead = if idx == 0 {
+                    TuiText::styled(prefix.clone(), Self::level_style(entry.level))
+                } else {
+                    TuiText::raw(indent.clone())
+                };
+
+                rows.push(vec![lead, TuiText::styled(line.to_string(), style), TuiText::raw("\n")]);
+            }
+        }
+
+        // Can't scroll back past the oldest entry:
+        self.scroll = self.scroll.min(rows.len().saturating_sub(self.height));
+
+        let visible: Vec<_> = rows.into_iter().skip(self.scroll).take(self.height).collect();
+        let text: Vec<TuiText<'_>> = visible.into_iter().rev().flatten().collect();
+
+        Paragraph::new(text.iter())
+            .style(Style::default().fg(Colour::White).bg(Colour::Reset))
+            .alignment(Alignment::Left)
+            .render(body, buf);
+
+        // The header shows the filters (or the search being typed):
+        let line = match &self.prompt {
+            Some(p) => format!("/{}", p.text),
+            None => {
+                let source = self.source.as_deref().unwrap_or("all");
+                let mut line = format!(
+                    "level ≥ {} ({}) · source: {} ({}) · {} of {} entries",
+                    self.min_level, data.keymap().describe(Action::LogLevel),
+                    source, data.keymap().describe(Action::LogSource),
+                    shown, data.log.len(),
+                );
+
+                if let Some(s) = &self.search {
+                    line.push_str(&format!(" · matching `{}`", s));
+                }
+                if self.scroll > 0 {
+                    line.push_str(&format!(" · {} rows back", self.scroll));
+                }
+
+                line
+            },
+        };
+
+        Paragraph::new([TuiText::styled(line, Style::default().fg(c!(Border)))].iter())
+            .style(Style::default().bg(Colour::Reset))
+            .render(header, buf);
+    }
+
+    fn update(&mut self, event: WidgetEvent, data: &mut TuiData<'a, 'int, C, I, O>, _terminal: &mut Terminal<B>) -> bool {
+        use WidgetEvent::*;
+        use Action::*;
+
+        match event {
+            Focus(FocusEvent::GotFocus) => true,
+            Focus(FocusEvent::LostFocus) => true,
+            Mouse(MouseEvent::Up(_, _, _, _)) => true,
+            Mouse(MouseEvent::Down(_, _, _, _)) => true,
+
+            Mouse(MouseEvent::ScrollUp(_, _, _)) => {
+                self.scroll = self.scroll.saturating_add(SCROLL_STEP);
+                true
+            },
+            Mouse(MouseEvent::ScrollDown(_, _, _)) => {
+                self.scroll = self.scroll.saturating_sub(SCROLL_STEP);
+                true
+            },
+
+            // Typing a search:
+            Key(e) if self.prompt.is_some() => {
+                if let Outcome::Entered((), text) = prompt::key(&mut self.prompt, e) {
+                    let text = text.to_lowercase();
+                    self.search = if text.is_empty() { None } else { Some(text) };
+                    self.scroll = 0;
+                }
+
+                true
+            },
+
+            Key(e) => match data.keymap.lookup(&e, Scope::Log) {
+                Some(LogScrollUp) => { self.scroll = self.scroll.saturating_add(1); true },
+                Some(LogScrollDown) => { self.scroll = self.scroll.saturating_sub(1); true },
+                Some(LogPageUp) => { self.scroll = self.scroll.saturating_add(self.page()); true },
+                Some(LogPageDown) => { self.scroll = self.scroll.saturating_sub(self.page()); true },
+                Some(LogTop) => { self.scroll = usize::MAX; true },
+                Some(LogBottom) => { self.scroll = 0; true },
+
+                Some(LogLevel) => { self.min_level = self.min_level.next(); self.scroll = 0; true },
+                Some(LogSource) => { self.cycle_source(&data.log); true },
+                Some(LogSearch) => { self.prompt = Some(Prompt::with_text((), self.search.clone().unwrap_or_default())); true },
+                Some(LogResetFilters) => {
+                    self.source = None;
+                    self.search = None;
+                    self.scroll = 0;
+                    true
+                },
+
+                Some(LogExport) => {
+                    let text = data.log.iter()
+                        .filter(|e| self.shows(e))
+                        .map(|e| format!("{}\n", e))
+                        .collect();
+
+                    data.export_log(text);
+                    true
+                },
+
+                _ => false,
+            },
+
+            _ => false,
+        }
+    }
+
+    fn wants_key(&self, _key: &KeyEvent, _data: &TuiData<'a, 'int, C, I, O>) -> bool {
+        self.prompt.is_some()
+    }
+}
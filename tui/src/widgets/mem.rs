@@ -1,12 +1,29 @@
 //! TODO!
 
 use super::widget_impl_support::*;
+use crate::keymap::{Action, Scope};
+use crate::markers;
+use crate::tui::mem_search::Query;
+use crate::tui::prompt::{self, Outcome, Prompt};
+use crate::tui::program_source::assemble_instruction;
+use crate::tui::word_input::parse_word;
 
 use std::convert::TryInto;
 
 use lc3_isa::{Addr, Instruction, Reg, Word};
+use lc3_traits::control::State;
 use lc3_traits::control::control::Event;
 
+/// Where the peripherals' registers start.
+const MEM_MAPPED_START: Addr = 0xFE00;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Asking {
+    GoTo,
+    Search,
+    /// A new value for the word at this address.
+    Edit(Addr),
+}
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Mem
@@ -17,7 +34,17 @@ pub struct Mem
     reset_flag: u8,
     addr: Addr,
     follow: bool,
-    debug: (bool, u8)
+    debug: (bool, u8),
+    /// A go-to target, search or new value that's being typed in.
+    prompt: Option<Prompt<Asking>>,
+    /// The last search (as typed in, and parsed); see
+    /// [`mem_search`](crate::tui::mem_search).
+    search: Option<(String, Query)>,
+    /// All of memory as of the last search, and the write, reset and load
+    /// counts it was read at; see [`Mem::refresh_memory`].
+    memory: Option<((u64, u8, u8), Vec<Word>)>,
+    /// How the last go-to, search or change went (and whether it failed).
+    message: Option<(String, bool)>,
 }
 
 impl Mem {
@@ -34,6 +61,10 @@ impl Mem {
             addr: 0x200,
             follow: true,
             debug: (toggle, 0),
+            prompt: None,
+            search: None,
+            memory: None,
+            message: None,
         }
     }
 
@@ -56,6 +87,140 @@ impl Mem {
         self.focus = 0;
         self.follow = true;
     }
+
+    /// Selects `addr` (in the same row as the word that's selected now).
+    pub fn jump_to(&mut self, addr: Addr) {
+        self.addr = addr;
+        self.follow = false;
+    }
+
+    fn selected(&self, pc: Addr) -> Addr {
+        if self.follow { pc } else { self.addr }
+    }
+
+    fn go_to<'a, 'int, C, I, O>(&mut self, target: String, data: &TuiData<'a, 'int, C, I, O>)
+    where
+        C: Control + ?Sized + 'a,
+        I: InputSink + ?Sized + 'a,
+        O: OutputSource + ?Sized + 'a,
+    {
+        let (regs, _, pc) = data.sim.get_registers_psr_and_pc();
+        match data.symbols.resolve(&target, &regs, pc, |addr| data.sim.read_word(addr)) {
+            Ok(addr) => self.jump_to(addr),
+            Err(err) => {
+                // Let them fix it:
+                self.prompt = Some(Prompt::with_text(Asking::GoTo, target));
+                self.message = Some((err, true));
+            },
+        }
+    }
+
+    fn search<'a, 'int, C, I, O>(&mut self, src: String, data: &TuiData<'a, 'int, C, I, O>)
+    where
+        C: Control + ?Sized + 'a,
+        I: InputSink + ?Sized + 'a,
+        O: OutputSource + ?Sized + 'a,
+    {
+        match Query::parse(&src) {
+            Ok(query) => {
+                self.search = Some((src, query));
+                self.memory = None;
+                self.find(true, data);
+            },
+            Err(err) => {
+                self.prompt = Some(Prompt::with_text(Asking::Search, src));
+                self.message = Some((err, true));
+            },
+        }
+    }
+
+    fn edit<'a, 'int, C, I, O>(&mut self, addr: Addr, src: String, data: &mut TuiData<'a, 'int, C, I, O>)
+    where
+        C: Control + ?Sized + 'a,
+        I: InputSink + ?Sized + 'a,
+        O: OutputSource + ?Sized + 'a,
+    {
+        match parse_word(&src, addr, &data.symbols, assemble_instruction) {
+            Ok(word) => {
+                data.sim.write_word(addr, word);
+                data.writes += 1;
+                self.message = Some((format!("x{:04X} is now x{:04X}", addr, word), false));
+            },
+            Err(err) => {
+                self.prompt = Some(Prompt::with_text(Asking::Edit(addr), src));
+                self.message = Some((err, true));
+            },
+        }
+    }
+
+    // Reads all of memory for searches to go through, unless it's been read
+    // since anything last changed it. With a board on the other end every
+    // read is a round trip so this can't happen on every keypress.
+    //
+    // The program changes memory as it runs and the peripherals change their
+    // registers whenever, so neither of those are ever reused.
+    fn refresh_memory<'a, 'int, C, I, O>(&mut self, data: &TuiData<'a, 'int, C, I, O>)
+    where
+        C: Control + ?Sized + 'a,
+        I: InputSink + ?Sized + 'a,
+        O: OutputSource + ?Sized + 'a,
+    {
+        let key = (data.writes, data.reset_flag, data.load_flag);
+        let running = data.sim.get_state() == State::RunningUntilEvent;
+
+        match &mut self.memory {
+            Some((at, mem)) if *at == key && !running => {
+                for addr in MEM_MAPPED_START..=0xFFFF {
+                    mem[addr as usize] = data.sim.read_word(addr);
+                }
+            },
+            _ => self.memory = Some((key, (0..=0xFFFF).map(|addr| data.sim.read_word(addr)).collect())),
+        }
+    }
+
+    // Jumps to the next (or previous) match for the last search, wrapping
+    // around the ends of memory.
+    fn find<'a, 'int, C, I, O>(&mut self, forward: bool, data: &TuiData<'a, 'int, C, I, O>)
+    where
+        C: Control + ?Sized + 'a,
+        I: InputSink + ?Sized + 'a,
+        O: OutputSource + ?Sized + 'a,
+    {
+        if self.search.is_none() {
+            let msg = format!("Nothing to find yet; press {} to search", data.keymap().describe(Action::MemSearch));
+            self.message = Some((msg, true));
+            return;
+        }
+
+        self.refresh_memory(data);
+        let (src, query) = self.search.as_ref().unwrap();
+        let matches = match &self.memory {
+            Some((_, mem)) => query.matches(mem),
+            None => unreachable!("memory was just read"),
+        };
+
+        let from = self.selected(data.sim.get_pc());
+        let idx = if forward {
+            matches.iter().position(|m| *m > from).unwrap_or(0)
+        } else {
+            matches.iter().rposition(|m| *m < from).unwrap_or_else(|| matches.len().wrapping_sub(1))
+        };
+
+        self.message = Some(match matches.get(idx) {
+            Some(addr) => {
+                let wrapped = if forward { *addr <= from } else { *addr >= from };
+                let msg = format!(
+                    "Match {} of {} for `{}` at x{:04X}{}",
+                    idx + 1, matches.len(), src, addr,
+                    if wrapped { " (wrapped around)" } else { "" },
+                );
+
+                self.jump_to(*addr);
+                (msg, false)
+            },
+            None => (format!("No matches for `{}`", src), true),
+        });
+    }
 }
 
 impl<'a, 'int, C, I, O, B> Widget<'a, 'int, C, I, O, B> for Mem
@@ -71,6 +236,20 @@ where
             self.reset_flag = data.reset_flag;
         }
 
+        // The prompt (or how the last go-to, search or change went) goes at
+        // the bottom:
+        let asking = self.prompt.as_ref().map(|p| (match p.kind {
+            Asking::GoTo => "Go to (address, register or label)".to_string(),
+            Asking::Search => "Search (words, \"text\", bytes …, or an instruction)".to_string(),
+            Asking::Edit(addr) => format!("New value for x{:04X} (number, 'c', label or instruction)", addr),
+        }, p.text.as_str()));
+        let area = prompt::draw_status(
+            asking,
+            self.message.as_ref().map(|(msg, error)| (msg.as_str(), *error)),
+            area,
+            buf,
+        );
+
         self.position = area;
 
         if self.offset > area.height.saturating_sub(1) {
@@ -126,10 +305,10 @@ where
 
             let cur_addr = pc.wrapping_sub(self.offset).wrapping_add(x).wrapping_sub(self.focus);
             if x == self.offset.wrapping_add(self.focus) {
-                let x = String::from("-->\n");
+                let x = format!("{}\n", if markers::enabled() { markers::PC } else { markers::SELECTED });
                 arrow_v.push(TuiText::styled(x,Style::default().fg(c!(Pc))));
             } else if x == self.offset {
-                let x = String::from("-->\n");
+                let x = format!("{}\n", markers::SELECTED);
                 arrow_v.push(TuiText::styled(x,Style::default().fg(c!(Highlight))));
             } else {
                 arrow_v.push(TuiText::raw("\n"));
@@ -141,6 +320,9 @@ where
             let mut data_colour = c!(Data);
             let mut inst_colour = c!(Inst);
 
+            // Only shown when markers are on; see `crate::markers`.
+            let mut row_marker = None;
+
             if x == self.offset {
                 bp_colour = c!(Highlight);
                 wp_colour = c!(Highlight);
@@ -167,6 +349,7 @@ where
                                 addr_colour = c!(Breakpoint);
                                 data_colour = c!(Breakpoint);
                                 inst_colour = c!(Breakpoint);
+                                row_marker = Some(markers::event(event));
                             }
                         }
                         Event::MemoryWatch {addr, data} => {
@@ -176,6 +359,7 @@ where
                                 addr_colour = c!(Highlight);
                                 data_colour = c!(Highlight);
                                 inst_colour = c!(Highlight);
+                                row_marker = Some(markers::event(event));
                             }
                         }
                         Event::DepthReached {current_depth } => {     // TODO: maybe pick some other color for this?
@@ -185,6 +369,7 @@ where
                                 addr_colour = c!(Depth);
                                 data_colour = c!(Depth);
                                 inst_colour = c!(Depth);
+                                row_marker = Some(markers::event(event));
                             }
                         }
                         Event::Error {err} => {
@@ -194,6 +379,7 @@ where
                                 addr_colour = c!(Error);
                                 data_colour = c!(Error);
                                 inst_colour = c!(Error);
+                                row_marker = Some(markers::event(event));
                             }
                         },
                         Event::Interrupted => {
@@ -203,6 +389,7 @@ where
                                 addr_colour = c!(Pause);
                                 data_colour = c!(Pause);
                                 inst_colour = c!(Pause);
+                                row_marker = Some(markers::event(event));
                             }
                         }
                         Event::Halted => {
@@ -212,6 +399,7 @@ where
                                 addr_colour = c!(Halted);
                                 data_colour = c!(Halted);
                                 inst_colour = c!(Halted);
+                                row_marker = Some(markers::event(event));
                             }
                         }
                         _ => {}
@@ -227,6 +415,7 @@ where
                     addr_colour = c!(MemRegHighlight);
                     data_colour = c!(MemRegHighlight);
                     inst_colour = c!(MemRegHighlight);
+                    row_marker = Some("[M]");
                 }
             }
 
@@ -254,11 +443,16 @@ where
             let s = format!("{:#05}\n", mem[x as usize]);
             dec_v.push(TuiText::styled(s, Style::default().fg(data_colour)));
 
+            let row_marker = match row_marker {
+                Some(m) if markers::enabled() => m,
+                _ => "",
+            };
+
             if inst_f {
-                let s = format!("{}\n", inst);
+                let s = format!("{} {}\n", inst, row_marker);
                 insts_v.push(TuiText::styled(s, Style::default().fg(inst_colour)));
             } else {
-                insts_v.push(TuiText::raw("\n"))
+                insts_v.push(TuiText::styled(format!("{}\n", row_marker), Style::default().fg(inst_colour)))
             }
             x = x + 1;
         }
@@ -382,11 +576,13 @@ where
                 }},
             };
         }
-        const EMPTY: KeyModifiers = KeyModifiers::empty();
 
         match event {
             Focus(FocusEvent::GotFocus) => true,
-            Focus(FocusEvent::LostFocus) => true,
+            Focus(FocusEvent::LostFocus) => {
+                self.prompt = None;
+                true
+            }
             Mouse(MouseEvent::Up(button, x, y, _)) => {
                 true
             }
@@ -431,65 +627,88 @@ where
                 true
             }
 
-            Key(KeyEvent { code: KeyCode::Up, modifiers: EMPTY }) => {
-                self.scroll_up(1);
-                true
-            }
-            Key(KeyEvent { code: KeyCode::Down, modifiers: EMPTY }) => {
-                self.scroll_down(1);
-                true
-            }
+            // Typing a go-to target or a search:
+            Key(e) if self.prompt.is_some() => {
+                match prompt::key(&mut self.prompt, e) {
+                    Outcome::Entered(asking, src) => {
+                        self.message = None;
+                        match asking {
+                            Asking::GoTo => self.go_to(src, data),
+                            Asking::Search => self.search(src, data),
+                            Asking::Edit(addr) => self.edit(addr, src, data),
+                        }
+                    },
+                    Outcome::Cancelled => self.message = None,
+                    Outcome::Typing => {},
+                }
 
-            Key(KeyEvent { code: KeyCode::Up, modifiers: KeyModifiers::SHIFT }) => {
-                self.scroll_up(10);
-                true
-            }
-            Key(KeyEvent { code: KeyCode::Down, modifiers: KeyModifiers::SHIFT }) => {
-                self.scroll_down(10);
                 true
             }
 
-            Key(KeyEvent { code: KeyCode::PageUp, modifiers: EMPTY }) => {
-                self.focus = self.focus.wrapping_add(self.position.height).wrapping_sub(1);
-                self.follow = false;
-                self.addr = self.addr.wrapping_add(self.position.height).wrapping_sub(1);
-                true
-            }
-            Key(KeyEvent { code: KeyCode::PageDown, modifiers: EMPTY }) => {
-                self.focus = self.focus.wrapping_sub(self.position.height).wrapping_add(1);
-                self.follow = false;
-                self.addr = self.addr.wrapping_sub(self.position.height).wrapping_add(1);
-                true
-            }
+            Key(e) => {
+                // Messages last until the next key:
+                self.message = None;
 
-            Key(KeyEvent { code: KeyCode::Home, modifiers: EMPTY }) => {
-                self.home();
-                true
-            }
+                match data.keymap.lookup(&e, Scope::Memory) {
+                    Some(Action::MemUp) => { self.scroll_up(1); true }
+                    Some(Action::MemDown) => { self.scroll_down(1); true }
+                    Some(Action::MemUpFast) => { self.scroll_up(10); true }
+                    Some(Action::MemDownFast) => { self.scroll_down(10); true }
 
-            Key(KeyEvent { code: KeyCode::Char(c), modifiers: EMPTY }) => {
-                match c {
-                    'w' => {
-                        set_wp(self.focus, data);
+                    Some(Action::MemPageUp) => {
+                        self.focus = self.focus.wrapping_add(self.position.height).wrapping_sub(1);
+                        self.follow = false;
+                        self.addr = self.addr.wrapping_add(self.position.height).wrapping_sub(1);
                         true
                     }
+                    Some(Action::MemPageDown) => {
+                        self.focus = self.focus.wrapping_sub(self.position.height).wrapping_add(1);
+                        self.follow = false;
+                        self.addr = self.addr.wrapping_sub(self.position.height).wrapping_add(1);
+                        true
+                    }
+
+                    Some(Action::MemHome) => { self.home(); true }
 
-                    'b' => {
-                        set_bp(self.focus, data);
+                    Some(Action::MemGoTo) => { self.prompt = Some(Prompt::new(Asking::GoTo)); true }
+                    Some(Action::MemSearch) => {
+                        // Start from the last search, so it can be tweaked:
+                        let last = self.search.as_ref().map(|(src, _)| src.clone()).unwrap_or_default();
+                        self.prompt = Some(Prompt::with_text(Asking::Search, last));
+                        true
+                    }
+                    Some(Action::MemEdit) => {
+                        let addr = self.selected(data.sim.get_pc());
+                        self.prompt = Some(Prompt::new(Asking::Edit(addr)));
                         true
                     }
+                    Some(Action::MemSearchNext) => { self.find(true, data); true }
+                    Some(Action::MemSearchPrev) => { self.find(false, data); true }
+
+                    Some(Action::ToggleWatchpoint) => { set_wp(self.focus, data); true }
+                    Some(Action::ToggleBreakpoint) => { set_bp(self.focus, data); true }
+
+                    Some(Action::CopyMemoryRow) => {
+                        let addr = data.sim.get_pc().wrapping_sub(self.focus);
+                        let word = data.sim.read_word(addr);
+
+                        let inst: Result<Instruction, _> = word.try_into();
+                        let inst = inst.map(|i| i.to_string()).unwrap_or_default();
 
-                    'h' => {
-                        self.home();
+                        let row = format!("{:#06x}: {:#018b} {:#06x} {:#05} {}", addr, word, word, word, inst);
+                        data.copy("memory row", row.trim_end().to_string());
                         true
                     }
 
-                    _ => false
+                    _ => false,
                 }
-
             }
 
             _ => false,
         }
     }
+
+    fn wants_key(&self, _key: &KeyEvent, _data: &TuiData<'a, 'int, C, I, O>) -> bool {
+        self.prompt.is_some()
+    }
 }
@@ -156,7 +156,7 @@ where
                             $on_success;
                         }
                         Err(_e) => {
-                            data.log(format!("[Addr] Invalid hex value: {}\n", self.input), c!(InvalidInput));
+                            data.log_at(Level::Warn, format!("[Addr] Invalid hex value: {}\n", self.input), c!(InvalidInput));
                         }
                     }
                 } else if self.input.starts_with("x") {
@@ -166,7 +166,7 @@ where
                             $on_success;
                         }
                         Err(_e) => {
-                            data.log(format!("[Addr] Invalid hex value: {}\n", self.input), c!(InvalidInput));
+                            data.log_at(Level::Warn, format!("[Addr] Invalid hex value: {}\n", self.input), c!(InvalidInput));
                         }
                     }
                 } else if self.input.starts_with("0b") {
@@ -176,7 +176,7 @@ where
                             $on_success;
                         }
                         Err(_e) => {
-                            data.log(format!("[Addr] Invalid binary value: {}\n", self.input), c!(InvalidInput));
+                            data.log_at(Level::Warn, format!("[Addr] Invalid binary value: {}\n", self.input), c!(InvalidInput));
                         }
                     }
                 } else {
@@ -185,7 +185,7 @@ where
                             $value = word;
                             $on_success;
                         }
-                        Err(e) => {data.log(format!("[Addr] Invalid value: {}\n", self.input), c!(InvalidInput))}
+                        Err(e) => {data.log_at(Level::Warn, format!("[Addr] Invalid value: {}\n", self.input), c!(InvalidInput))}
                     }
                 }
             }
@@ -259,12 +259,12 @@ where
                                             data.mem_reg_inter = (2, value.into());
                                         },
                                         Err(e) => {
-                                            data.log(format!("[Reg] Invalid register: {}\n", self.input), c!(InvalidInput));
+                                            data.log_at(Level::Warn, format!("[Reg] Invalid register: {}\n", self.input), c!(InvalidInput));
                                         }
                                     };
                                 },
                                 Err(e) => {
-                                    data.log(format!("[Reg] Invalid register: {}\n", self.input), c!(InvalidInput));
+                                    data.log_at(Level::Warn, format!("[Reg] Invalid register: {}\n", self.input), c!(InvalidInput));
                                 }
                             }
                         } else if self.input == String::from("pc") {
@@ -288,7 +288,7 @@ where
                         modify_addr!(
                             addr,
                             word,
-                            {data.sim.write_word(addr, word);}
+                            {data.sim.write_word(addr, word); data.writes += 1;}
                         );
                     },
                     RegisterMod => {
@@ -297,7 +297,7 @@ where
                         modify_addr!(
                             addr_from_reg,
                             word,
-                            {data.sim.set_register(self.reg_num, word);}
+                            {data.sim.set_register(self.reg_num, word); data.writes += 1;}
                         );
                     },
                     PcMod => {
@@ -306,7 +306,7 @@ where
                         modify_addr!(
                             addr_from_pc,
                             word,
-                            {data.sim.set_pc(word);}
+                            {data.sim.set_pc(word); data.writes += 1;}
                         );
                     },
                 };
@@ -8,6 +8,7 @@ pub(in self) mod widget_impl_support {
     pub use crate::tui::widget::util::ConditionalSendBound;
     pub use crate::tui::ansi::AnsiTextContainer;
     pub use crate::tui::TuiData;
+    pub use crate::tui::program_log::Level;
     pub use crate::tui::events::{WidgetEvent, FocusEvent};
 
     pub use crate::debug::{in_debug_mode, run_if_debugging};
@@ -51,6 +52,9 @@ pub use regs::*;
 mod console;
 pub use console::*;
 
+mod log_view;
+pub use log_view::*;
+
 mod gpio;
 pub use gpio::*;
 
@@ -75,6 +79,21 @@ pub use load_button::*;
 mod console_peripherals;
 pub use console_peripherals::*;
 
+mod stimulus_window;
+pub use stimulus_window::*;
+
+mod analyser;
+pub use analyser::*;
+
+mod interrupts;
+pub use interrupts::*;
+
+mod stack_inspector;
+pub use stack_inspector::*;
+
+mod data_views;
+pub use data_views::*;
+
 mod watch_window;
 pub use watch_window::*;
 
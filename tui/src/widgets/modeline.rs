@@ -1,6 +1,8 @@
 //! TODO!
 
 use super::widget_impl_support::*;
+use crate::keymap::{Action, Scope};
+use crate::markers;
 use ModelineFocus::*;
 
 use core::future::Future;
@@ -98,6 +100,14 @@ where
 
     fn step(&mut self, data: &mut TuiData<'a, 'int, C, I, O>) {
         data.current_event = data.sim.step();
+        data.writes += 1;
+
+        if let Some(script) = data.stimulus.as_mut() {
+            script.pending_steps += 1;
+        }
+
+        data.track_interrupts();
+        data.track_stack();
     }
 
     fn step_in(&mut self, data: &mut TuiData<'a, 'int, C, I, O>) {
@@ -138,6 +148,7 @@ where
             }
 
             self.event_fut = Some(data.sim.run_until_event());
+            data.writes += 1;
         } else {
             // Just to make sure!
 
@@ -209,8 +220,13 @@ where
     fn reset(&mut self, data: &mut TuiData<'a, 'int, C, I, O>) {
         data.log("[modeline] Resetting Sim\n", c!(Pause));
         data.sim.reset();
-        data.console_input_string.get_mut().clear();
+        data.console_input.get_mut().clear();
+        data.input_queue.clear();
+        if let Some(script) = data.input_script.as_mut().filter(|s| s.refeed_on_reset) {
+            script.rewind();
+        }
         data.console_hist.get_mut().clear();
+        data.transcript.get_mut().clear();
         data.mem_reg_inter = (0,0);
         data.reset_flag = data.reset_flag.wrapping_add(1);
 
@@ -225,6 +241,13 @@ where
 
         self.skip_os(data);
 
+        // Stimulus scripts start over once we're past the OS:
+        if let Some(script) = data.stimulus.as_mut() {
+            script.restart();
+        }
+        data.interrupts.clear();
+        data.stack.clear();
+
         data.log("[modeline] Reset Complete\n", c!(Success));
         drop(data.current_event.take())
     }
@@ -242,6 +265,16 @@ fn create_rect(index: u16, size: u16, area:Rect) -> Rect {
         area.height)
 }
 
+// With markers on, the focused button is also marked with arrows (instead of
+// just being coloured differently).
+fn button_label(label: &str, focused: bool) -> String {
+    if focused && markers::enabled() {
+        format!("> {} <", label)
+    } else {
+        label.to_string()
+    }
+}
+
 impl<'a, 'int, C, I, O, B> Widget<'a, 'int, C, I, O, B> for Modeline<'a, 'int, C, I, O, B>
 where
     C: Control + ?Sized + 'a,
@@ -346,6 +379,10 @@ where
             },
             None => format!(""),
         };
+        let event = match data.get_current_event() {
+            Some(e) if markers::enabled() && !event.is_empty() => format!("{} {}", markers::event(e), event),
+            _ => event,
+        };
         let event_text = [TuiText::styled(event, Style::default().fg(box_colour))];
         let mut para = Paragraph::new(event_text.iter())
             .style(Style::default())
@@ -357,7 +394,7 @@ where
             .wrap(true);
         para.render(cur_event_block, buf);
 
-        let text = [TuiText::styled("Step Over", Style::default().fg(step_over_colour))];
+        let text = [TuiText::styled(button_label("Step Over", self.focus == StepOver), Style::default().fg(step_over_colour))];
         para = Paragraph::new(text.iter())
             .style(Style::default())
             .block(Block::default()
@@ -368,7 +405,7 @@ where
             .wrap(true);
         para.render(self.step_over_button,buf);
 
-        let text = [TuiText::styled("Step In", Style::default().fg(step_in_colour))];
+        let text = [TuiText::styled(button_label("Step In", self.focus == StepIn), Style::default().fg(step_in_colour))];
         para = Paragraph::new(text.iter())
             .style(Style::default())
             .block(Block::default()
@@ -379,7 +416,7 @@ where
             .wrap(true);
         para.render(self.step_in_button,buf);
 
-        let text = [TuiText::styled("Step Out", Style::default().fg(step_out_colour))];
+        let text = [TuiText::styled(button_label("Step Out", self.focus == StepOut), Style::default().fg(step_out_colour))];
         para = Paragraph::new(text.iter())
             .style(Style::default())
             .block(Block::default()
@@ -392,9 +429,9 @@ where
 
         let mut vec = Vec::new();
         if running {
-            vec.push(TuiText::styled("Pause", Style::default().fg(c!(Pause))));
+            vec.push(TuiText::styled(button_label("Pause", self.focus == ExecutionControl), Style::default().fg(c!(Pause))));
         } else {
-            vec.push(TuiText::styled("Run", Style::default().fg(c!(Run))));
+            vec.push(TuiText::styled(button_label("Run", self.focus == ExecutionControl), Style::default().fg(c!(Run))));
         }
         let mut para = Paragraph::new(vec.iter())
             .style(Style::default())
@@ -410,7 +447,7 @@ where
         if self.reset_flag {
             vec.push(TuiText::styled(s!(ResetConfirmationMsg), Style::default().fg(c!(Reset))));
         } else {
-            vec.push(TuiText::styled("Reset", Style::default().fg(c!(Pause))));
+            vec.push(TuiText::styled(button_label("Reset", self.focus == Reset), Style::default().fg(c!(Pause))));
         }
         para = Paragraph::new(vec.iter())
             .style(Style::default())
@@ -453,7 +490,7 @@ where
                     Event::Halted => c!(Halted),
                     _ => c!(mDefault),
                 };
-                data.log(format!("[modeline] Got an event! {:?}\n", event), event_colour);
+                data.log_at(Level::Debug, format!("[modeline] Got an event! {:?}\n", event), event_colour);
                 data.current_event = Some(event);
             }
         }
@@ -506,53 +543,27 @@ where
                 true
             }
 
-            Key(e) => match e {
-                KeyEvent { code: KeyCode::Char('u'), modifiers: KeyModifiers::ALT } |
-                KeyEvent { code: KeyCode::Char('u'), modifiers: KeyModifiers::CONTROL } => {
-                    if !running { self.step_over(data); }
-                    true
-                }
-
-                KeyEvent { code: KeyCode::Char('i'), modifiers: KeyModifiers::ALT } |
-                KeyEvent { code: KeyCode::Char('i'), modifiers: KeyModifiers::CONTROL } => {
-                    if !running { self.step(data); }
-                    true
-                }
-
-                KeyEvent { code: KeyCode::Char('o'), modifiers: KeyModifiers::ALT } |
-                KeyEvent { code: KeyCode::Char('o'), modifiers: KeyModifiers::CONTROL } => {
-                    if !running { self.step_out(data); }
-                    true
-                }
-
-                KeyEvent { code: KeyCode::Char('s'), modifiers: KeyModifiers::CONTROL } => {
-                    if !running {
-                        self.step(data);
-                    }
-                    true
-                }
-                KeyEvent { code: KeyCode::Char('p'), modifiers: KeyModifiers::CONTROL } => {
-                    self.pause(data);
-                    true
-                }
-                KeyEvent { code: KeyCode::Char('r'), modifiers: KeyModifiers::CONTROL } => {
-                    self.run(data);
-                    true
-                }
-                KeyEvent { code: KeyCode::Char('t'), modifiers: KeyModifiers::CONTROL } => {
-                    self.focus = Reset;
-                    if self.reset_flag{
-                        self.reset(data);
-                        self.reset_flag = false;
-                    } else {
-                        self.reset_flag = true;
+            Key(e) => if let Some(action) = data.keymap.lookup(&e, Scope::Modeline) {
+                match action {
+                    Action::StepOver => if !running { self.step_over(data); },
+                    Action::StepIn | Action::Step => if !running { self.step(data); },
+                    Action::StepOut => if !running { self.step_out(data); },
+                    Action::Pause => self.pause(data),
+                    Action::Run => self.run(data),
+                    Action::Reset => {
+                        self.focus = Reset;
+                        if self.reset_flag{
+                            self.reset(data);
+                            self.reset_flag = false;
+                        } else {
+                            self.reset_flag = true;
+                        }
                     }
-                    true
-                }
-                KeyEvent { code: KeyCode::Char('l'), modifiers: KeyModifiers::CONTROL } => {
-                    self.load(event, data, terminal);
-                    true
+                    Action::Load => self.load(event, data, terminal),
+                    _ => unreachable!(),
                 }
+                true
+            } else { match e {
                 KeyEvent { code: KeyCode::Enter, modifiers: EMPTY } => {
                     match self.focus {
                         ExecutionControl => {
@@ -584,7 +595,7 @@ where
                     }
                     true
                 }
-                KeyEvent { code: KeyCode::Right, modifiers: KeyModifiers::CONTROL } => {
+                e if data.keymap.is(Action::FocusRight, &e) => {
                     self.focus = match self.focus {
                         StepOver => StepIn,
                         StepIn => StepOut,
@@ -596,7 +607,7 @@ where
                     };
                     true
                 }
-                KeyEvent { code: KeyCode::Left, modifiers: KeyModifiers::CONTROL } => {
+                e if data.keymap.is(Action::FocusLeft, &e) => {
                     self.focus = match self.focus {
                         StepOver => StepOver,
                         StepIn => StepOver,
@@ -612,7 +623,7 @@ where
                     true
                 }
                 _ => false,
-            }
+            }}
             _ => false,
         }
     }
@@ -1,18 +1,37 @@
-//! TODO!
+//! The PWM channels: a duty cycle gauge, the waveform the channel is putting
+//! out and a plot of how the duty cycle has changed.
 
 use super::widget_impl_support::*;
+use crate::tui::peripheral_commands::PWM_PINS;
+use crate::tui::time::now;
 
-use lc3_traits::peripherals::pwm::{PwmPin, PwmState};
+use lc3_traits::peripherals::pwm::PwmState;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+use std::collections::VecDeque;
+
+/// How far apart the duty cycle history's samples are, in milliseconds.
+const HISTORY_INTERVAL: u64 = 250;
+/// How many samples of history we keep (per channel).
+const HISTORY_LEN: usize = 240;
+/// How many columns one period of the waveform takes up.
+const PERIOD_WIDTH: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Pwm {
     pub focusable: bool,
+    /// Each channel's duty cycle (`None` when it's disabled) once every
+    /// `HISTORY_INTERVAL`, as `(now() / HISTORY_INTERVAL, duty)`.
+    ///
+    /// This is recorded when we're drawn so there are gaps for when we
+    /// weren't on screen.
+    history: [VecDeque<(u64, Option<u8>)>; 2],
 }
 
 impl Default for Pwm {
     fn default() -> Self {
         Self {
             focusable: false,
+            history: Default::default(),
         }
     }
 }
@@ -22,102 +41,140 @@ impl Pwm {
         self.focusable = focusable;
         self
     }
-}
-
-impl<'a, 'int, C, I, O, B> Widget<'a, 'int, C, I, O, B> for Pwm
-where
-    C: Control + ?Sized + 'a,
-    I: InputSink + ?Sized + 'a,
-    O: OutputSource + ?Sized + 'a,
-    B: Backend,
-{
-
-    fn draw(&mut self, data: &TuiData<'a, 'int, C, I, O>, area: Rect, buf: &mut Buffer) {
-        let pwm_state = data.sim.get_pwm_states();
-        let pwm_configs = data.sim.get_pwm_config();
-
-
-        let text = [
-            TuiText::styled("PWM 0: \n", Style::default().fg(c!(Name))),
-        ];
 
-        let mut para = Paragraph::new(text.iter())
-            .style(Style::default().fg(Colour::White).bg(Colour::Reset))
-            .alignment(Alignment::Left)
-            .wrap(true);
-
-        para.render(area, buf);
+    fn record(&mut self, slot: u64, channel: usize, duty: Option<u8>) {
+        let history = &mut self.history[channel];
+        if history.back().map(|(s, _)| *s == slot).unwrap_or(false) {
+            return;
+        }
 
-        let mut s0 = String::from("");
+        if history.len() == HISTORY_LEN {
+            let _ = history.pop_front();
+        }
+        history.push_back((slot, duty));
+    }
+}
 
-        let p0 = match pwm_state[PwmPin::P0]{
-            PwmState::Disabled => {
-                let disabled_string = "Disabled";
-                s0.push_str(&format!("{}\n", disabled_string));
-            },
-            PwmState::Enabled(time) => {
-                s0.push_str(&format!(
-                    "{:#018b} {:#06x} {:#05}\n", pwm_configs[PwmPin::P0], pwm_configs[PwmPin::P0], pwm_configs[PwmPin::P0]
-                ));
+fn percent(duty: u8) -> u32 {
+    (duty as u32 * 100 + 127) / 255
+}
 
-            }
+// A `width` wide bar that's `duty / 255` full.
+fn gauge(duty: u8, width: usize) -> (String, String) {
+    let filled = (duty as usize * width + 127) / 255;
+    ("█".repeat(filled), "░".repeat(width - filled))
+}
 
+// A square wave with the given duty cycle, as two rows of box drawing
+// characters (the top one has the highs).
+fn waveform(duty: u8, width: usize, period: usize) -> (String, String) {
+    let high_for = (duty as usize * period + 127) / 255;
+    let high = |x: usize| x % period < high_for;
+
+    let (mut top, mut bottom) = (String::new(), String::new());
+    for x in 0..width {
+        let edge = x > 0 && high(x) != high(x - 1);
+
+        let (t, b) = match (high(x), edge) {
+            (true, false) => ('─', ' '),
+            (true, true) => ('┌', '┘'),
+            (false, false) => (' ', '─'),
+            (false, true) => ('┐', '└'),
         };
 
+        top.push(t);
+        bottom.push(b);
+    }
 
-        let text = [TuiText::styled(s0, Style::default().fg(c!(Data)))];
-        para = Paragraph::new(text.iter())
-            .style(Style::default().fg(Colour::White).bg(Colour::Reset))
-            .alignment(Alignment::Left)
-            .wrap(true);
-        let area = increment(10, Axis::X, area);
-        para.render(area, buf);
-
-
-
-
-        let text = [
-            TuiText::styled("PWM 1: \n", Style::default().fg(c!(Name))),
-        ];
+    (top, bottom)
+}
 
-        let mut para = Paragraph::new(text.iter())
-            .style(Style::default().fg(Colour::White).bg(Colour::Reset))
-            .alignment(Alignment::Left)
-            .wrap(true);
-        let area1 = increment(40, Axis::X, area);
-        para.render(area1, buf);
+// One column per history slot (ending at `slot`), with gaps (and disabled
+// slots) left blank.
+fn sparkline(history: &VecDeque<(u64, Option<u8>)>, slot: u64, width: usize) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
 
+    let mut line = vec![' '; width];
+    for (s, duty) in history.iter() {
+        let age = (slot - s) as usize;
+        if age >= width {
+            continue;
+        }
 
+        if let Some(duty) = duty {
+            line[width - 1 - age] = BARS[*duty as usize * (BARS.len() - 1) / 255];
+        }
+    }
 
-        let mut s1 = String::from("");
-        let p1 = match pwm_state[PwmPin::P1]{
-            PwmState::Disabled => {
-                let disabled_string = "Disabled";
-                s1.push_str(&format!("{}\n", disabled_string));
-            },
-            PwmState::Enabled(time) => {
-                s1.push_str(&format!(
-                    "{:#018b} {:#06x} {:#05}\n", pwm_configs[PwmPin::P1], pwm_configs[PwmPin::P1], pwm_configs[PwmPin::P1]
-                ));
+    line.into_iter().collect()
+}
 
+impl<'a, 'int, C, I, O, B> Widget<'a, 'int, C, I, O, B> for Pwm
+where
+    C: Control + ?Sized + 'a,
+    I: InputSink + ?Sized + 'a,
+    O: OutputSource + ?Sized + 'a,
+    B: Backend,
+{
+    fn draw(&mut self, data: &TuiData<'a, 'int, C, I, O>, area: Rect, buf: &mut Buffer) {
+        const LABEL: usize = 8; // "PWM 0   "
+
+        let states = data.sim.get_pwm_states();
+        let configs = data.sim.get_pwm_config();
+        let slot = now() / HISTORY_INTERVAL;
+
+        // Each channel gets a line for its gauge, then (if there's room) two
+        // for its waveform and one for its history:
+        let rows = (area.height / PWM_PINS.len() as u16) as usize;
+        let width = (area.width as usize).saturating_sub(LABEL);
+
+        for (idx, pin) in PWM_PINS.iter().enumerate() {
+            let (duty, period) = match states[*pin] {
+                PwmState::Disabled => (None, None),
+                PwmState::Enabled(period) => (Some(configs[*pin]), Some(format!("{}", period))),
+            };
+            self.record(slot, idx, duty);
+
+            if rows == 0 {
+                continue;
             }
 
-        };
-
-        let text = [TuiText::styled(s1, Style::default().fg(c!(Data)))];
-        para = Paragraph::new(text.iter())
-            .style(Style::default().fg(Colour::White).bg(Colour::Reset))
-            .alignment(Alignment::Left)
-            .wrap(true);
-        let area2 = increment(10, Axis::X, area1);
-        para.render(area2, buf);
+            let colour = if duty.is_some() { c!(Data) } else { c!(Disabled) };
+            let label = |s: &str| TuiText::styled(format!("{:1$}", s, LABEL), Style::default().fg(c!(Name)));
+            let line = |s: String| TuiText::styled(format!("{}\n", s), Style::default().fg(colour));
+
+            let mut text = vec![label(&format!("PWM {}", idx))];
+            match (duty, period) {
+                (Some(duty), Some(period)) => {
+                    let (filled, empty) = gauge(duty, width.saturating_sub(36).min(32).max(4));
+                    text.push(TuiText::styled(filled, Style::default().fg(c!(Data))));
+                    text.push(TuiText::styled(empty, Style::default().fg(c!(Disabled))));
+                    text.push(line(format!(" {:3}% ({:3}/255), period {}ms", percent(duty), duty, period)));
+                },
+                _ => text.push(line("disabled".to_string())),
+            }
 
+            if rows >= 3 {
+                let (top, bottom) = waveform(duty.unwrap_or(0), width, PERIOD_WIDTH.min(width.max(1)));
+                text.push(label(""));
+                text.push(line(top));
+                text.push(label(""));
+                text.push(line(bottom));
+            }
 
+            if rows >= 4 {
+                text.push(label("history"));
+                text.push(line(sparkline(&self.history[idx], slot, width)));
+            }
 
+            let y = area.y + (idx * rows) as u16;
+            Paragraph::new(text.iter())
+                .style(Style::default().fg(Colour::White).bg(Colour::Reset))
+                .alignment(Alignment::Left)
+                .render(Rect { y, height: rows as u16, ..area }, buf);
+        }
     }
 
-
-
     fn update(&mut self, event: WidgetEvent, _data: &mut TuiData<'a, 'int, C, I, O>, _terminal: &mut Terminal<B>) -> bool {
         match event {
             WidgetEvent::Mouse(_) | WidgetEvent::Focus(FocusEvent::GotFocus) => self.focusable,
@@ -125,3 +182,35 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod pwm_tests {
+    use super::*;
+    use pretty_assertions::assert_eq as eq;
+
+    fn wave(duty: u8, width: usize, period: usize) -> [String; 2] {
+        let (top, bottom) = waveform(duty, width, period);
+        [top, bottom]
+    }
+
+    #[test]
+    fn waveforms() {
+        eq!(wave(128, 12, 4), ["──┐ ┌─┐ ┌─┐ ".to_string(), "  └─┘ └─┘ └─".to_string()]);
+        eq!(wave(0, 4, 4), ["    ".to_string(), "────".to_string()]);
+        eq!(wave(255, 4, 4), ["────".to_string(), "    ".to_string()]);
+    }
+
+    #[test]
+    fn sparklines() {
+        let history = vec![(3, Some(0)), (4, None), (6, Some(255))].into_iter().collect();
+        eq!(sparkline(&history, 6, 5), " ▁  █");
+        eq!(sparkline(&history, 8, 2), "  ");
+    }
+
+    #[test]
+    fn gauges() {
+        eq!(gauge(128, 4), ("██".to_string(), "░░".to_string()));
+        eq!(percent(128), 50);
+        eq!(percent(255), 100);
+    }
+}
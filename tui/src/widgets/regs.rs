@@ -1,9 +1,27 @@
 //! TODO!
 
 use super::widget_impl_support::*;
+use crate::keymap::{Action, Scope};
+use crate::markers;
+use crate::tui::program_source::assemble_instruction;
+use crate::tui::prompt::{self, Outcome, Prompt};
+use crate::tui::word_input::parse_word;
 
 use lc3_isa::{Addr, Instruction, Reg, Word, Bits};
 
+use std::convert::TryFrom;
+
+/// The field for the PC (fields 0 to 7 are R0 to R7).
+const PC_FIELD: usize = 8;
+
+fn field_name(field: usize) -> String {
+    if field == PC_FIELD {
+        "PC".to_string()
+    } else {
+        format!("R{}", field)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 struct RegDiff {
     old: ([Word; Reg::NUM_REGS], Word, Word),
@@ -26,44 +44,68 @@ impl RegDiff {
         }
     }
 
-    fn diff(&self) -> ([Colour; Reg::NUM_REGS], [Colour; 5], Colour) {
-        let mut colours = ([c!(Data); Reg::NUM_REGS], [c!(Privilege), c!(Priority), c!(n_bit), c!(z_bit), c!(p_bit)], c!(Data));
+    // Which registers and which parts of the PSR (privilege, priority, n, z, p)
+    // changed in the last step.
+    fn changed(&self) -> ([bool; Reg::NUM_REGS], [bool; 5]) {
+        let mut regs = [false; Reg::NUM_REGS];
         for i in 0..Reg::NUM_REGS {
-            if self.old.0[i] != self.new.0[i] {
-                colours.0[i] = c!(RegHighlight);
-            }
+            regs[i] = self.old.0[i] != self.new.0[i];
         }
 
-        if self.old.1.bit(15) != self.new.1.bit(15){
-            colours.1[0] = c!(RegHighlight);
-        }
+        let (old, new) = (self.old.1, self.new.1);
+        let psr = [
+            old.bit(15) != new.bit(15),
+            old.bits(8..10) != new.bits(8..10),
+            old.bit(2) != new.bit(2),
+            old.bit(1) != new.bit(1),
+            old.bit(0) != new.bit(0),
+        ];
 
-        if self.old.1.bits(8..10) != self.new.1.bits(8..10){
-            colours.1[1] = c!(RegHighlight);
-        }
+        (regs, psr)
+    }
 
-        if self.old.1.bit(2) != self.new.1.bit(2){
-            colours.1[2] = c!(RegHighlight);
-        }
+    fn diff(&self) -> ([Colour; Reg::NUM_REGS], [Colour; 5], Colour) {
+        let mut colours = ([c!(Data); Reg::NUM_REGS], [c!(Privilege), c!(Priority), c!(n_bit), c!(z_bit), c!(p_bit)], c!(Data));
+        let (regs, psr) = self.changed();
 
-        if self.old.1.bit(1) != self.new.1.bit(1){
-            colours.1[3] = c!(RegHighlight);
+        for i in 0..Reg::NUM_REGS {
+            if regs[i] {
+                colours.0[i] = c!(RegHighlight);
+            }
         }
 
-        if self.old.1.bit(0) != self.new.1.bit(0){
-            colours.1[4] = c!(RegHighlight);
+        for i in 0..5 {
+            if psr[i] {
+                colours.1[i] = c!(RegHighlight);
+            }
         }
 
         colours
     }
 }
 
+// With markers on, changed things get a `*` (in addition to being highlighted).
+fn changed_marker(changed: bool) -> &'static str {
+    if changed && markers::enabled() {
+        markers::CHANGED
+    } else {
+        ""
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Regs
 {
     state: RegDiff,
     debug: bool,
     reset_flag: u8,
+    focused: bool,
+    /// The field keys act on (see `PC_FIELD`).
+    selected: usize,
+    /// A new value for it that's being typed in.
+    prompt: Option<Prompt>,
+    /// Why the last value typed in wasn't taken.
+    error: Option<String>,
 }
 
 impl Regs {
@@ -76,6 +118,43 @@ impl Regs {
             state: RegDiff::default(),
             debug,
             reset_flag: 0,
+            focused: false,
+            selected: 0,
+            prompt: None,
+            error: None,
+        }
+    }
+
+    // R0 to R3 are on the left; R4 to R7 and then the PC are on the right.
+    fn select(&mut self, action: Action) {
+        let f = self.selected;
+        self.selected = match action {
+            Action::RegsUp if f == PC_FIELD => 7,
+            Action::RegsUp if f % 4 != 0 => f - 1,
+            Action::RegsDown if f == 7 => PC_FIELD,
+            Action::RegsDown if f != PC_FIELD && f % 4 != 3 => f + 1,
+            Action::RegsLeft if (4..PC_FIELD).contains(&f) => f - 4,
+            Action::RegsRight if f < 4 => f + 4,
+            _ => f,
+        };
+    }
+
+    fn edit<'a, 'int, C, I, O>(&mut self, src: String, data: &mut TuiData<'a, 'int, C, I, O>)
+    where
+        C: Control + ?Sized + 'a,
+        I: InputSink + ?Sized + 'a,
+        O: OutputSource + ?Sized + 'a,
+    {
+        // Instructions are assembled as if they were at the PC:
+        let pc = data.sim.get_pc();
+        match parse_word(&src, pc, &data.symbols, assemble_instruction) {
+            Ok(word) if self.selected == PC_FIELD => { data.sim.set_pc(word); data.writes += 1; },
+            Ok(word) => { data.sim.set_register(Reg::try_from(self.selected as u8).unwrap(), word); data.writes += 1; },
+            Err(err) => {
+                // Let them fix it:
+                self.prompt = Some(Prompt::with_text((), src));
+                self.error = Some(err);
+            },
         }
     }
 }
@@ -108,6 +187,20 @@ where
         self.state.push(regs_psr_pc);
 
         let mut colours = self.state.diff();
+        let changed = self.state.changed();
+
+        // The prompt (and what was wrong with the last value) goes at the
+        // bottom:
+        let area = prompt::draw_status(
+            self.prompt.as_ref().map(|p| (format!("New value for {} (number, 'c', label or instruction)", field_name(self.selected)), p.text.as_str())),
+            self.error.as_deref().map(|err| (err, true)),
+            area,
+            buf,
+        );
+
+        let highlight = |field: usize, style: Style| {
+            if self.focused && self.selected == field { style.modifier(Modifier::REVERSED) } else { style }
+        };
 
         if self.debug && data.mem_reg_inter.0 == 2 {
             let reg = data.mem_reg_inter.1;
@@ -133,10 +226,10 @@ where
         let mut reg_v = Vec::new();
         for i in 0..4 {
             let s = format!(
-                "{:#018b} {:#06x} {:#05}\n",
-                regs[i], regs[i], regs[i]
+                "{:#018b} {:#06x} {:#05} {}\n",
+                regs[i], regs[i], regs[i], changed_marker(changed.0[i])
             );
-            reg_v.push(TuiText::styled(s, Style::default().fg(colours.0[i])));
+            reg_v.push(TuiText::styled(s, highlight(i, Style::default().fg(colours.0[i]))));
         }
 
         reg_v.push(TuiText::styled("0b", Style::default().fg(c!(Data))));
@@ -150,17 +243,20 @@ where
         reg_v.push(TuiText::styled(s, Style::default().fg(colours.1[2])));
         let s = format!("{}", z);
         reg_v.push(TuiText::styled(s, Style::default().fg(colours.1[3])));
-        let s = format!("{}\n", p);
+        let s = format!("{}", p);
         reg_v.push(TuiText::styled(s, Style::default().fg(colours.1[4])));
+        let s = format!(" {}\n", changed_marker(changed.1.iter().any(|c| *c)));
+        reg_v.push(TuiText::styled(s, Style::default().fg(c!(RegHighlight))));
 
         let s = match privilege {
-            0 => "Supervisor\n",
-            1 => "User\n",
+            0 => "Supervisor",
+            1 => "User",
             _ => unreachable!(),
         };
+        let s = format!("{} {}\n", s, changed_marker(changed.1[0]));
         reg_v.push(TuiText::styled(s, Style::default().fg(colours.1[0])));
 
-        let s = format!("{}\n", priority);
+        let s = format!("{} {}\n", priority, changed_marker(changed.1[1]));
         reg_v.push(TuiText::styled(s, Style::default().fg(colours.1[1])));
 
         para = Paragraph::new(reg_v.iter())
@@ -187,21 +283,21 @@ where
         reg_v.clear();
         for i in 4..8 {
             let s = format!(
-                "{:#018b} {:#06x} {:#05}\n",
-                regs[i], regs[i], regs[i]
+                "{:#018b} {:#06x} {:#05} {}\n",
+                regs[i], regs[i], regs[i], changed_marker(changed.0[i])
             );
-            reg_v.push(TuiText::styled(s, Style::default().fg(colours.0[i])));
+            reg_v.push(TuiText::styled(s, highlight(i, Style::default().fg(colours.0[i]))));
         }
         let s = format!("{:#018b} {:#06x} {:#05}\n", pc, pc, pc);
-        reg_v.push(TuiText::styled(s, Style::default().fg(colours.2)));
+        reg_v.push(TuiText::styled(s, highlight(PC_FIELD, Style::default().fg(colours.2))));
         reg_v.push(TuiText::styled(format!("n: "), Style::default().fg(c!(Pc))));
-        let s = format!("{}  ", n);
+        let s = format!("{:<3}", format!("{}{}", n, changed_marker(changed.1[2])));
         reg_v.push(TuiText::styled(s, Style::default().fg(colours.1[2])));
         reg_v.push(TuiText::styled(format!("z: "), Style::default().fg(c!(Pc))));
-        let s = format!("{}  ", z);
+        let s = format!("{:<3}", format!("{}{}", z, changed_marker(changed.1[3])));
         reg_v.push(TuiText::styled(s, Style::default().fg(colours.1[3])));
         reg_v.push(TuiText::styled(format!("p: "), Style::default().fg(c!(Pc))));
-        let s = format!("{}  ", p);
+        let s = format!("{:<3}", format!("{}{}", p, changed_marker(changed.1[4])));
         reg_v.push(TuiText::styled(s, Style::default().fg(colours.1[4])));
 
         para = Paragraph::new(reg_v.iter())
@@ -214,16 +310,46 @@ where
 
     }
 
-    fn update(&mut self, event: WidgetEvent, _data: &mut TuiData<'a, 'int, C, I, O>, _terminal: &mut Terminal<B>) -> bool {
+    fn update(&mut self, event: WidgetEvent, data: &mut TuiData<'a, 'int, C, I, O>, _terminal: &mut Terminal<B>) -> bool {
         use WidgetEvent::*;
-        const EMPTY: KeyModifiers = KeyModifiers::empty();
+        use Action::*;
 
         match event {
-            Focus(FocusEvent::GotFocus) => false,
-            Focus(FocusEvent::LostFocus) => false,
+            Focus(FocusEvent::GotFocus) => { self.focused = true; true },
+            Focus(FocusEvent::LostFocus) => {
+                self.focused = false;
+                self.prompt = None;
+                self.error = None;
+                true
+            },
             Mouse(MouseEvent::Up(_, _, _, _)) => false,
             Mouse(MouseEvent::Down(_, _, _, _)) => false,
+
+            // Typing a new value:
+            Key(e) if self.prompt.is_some() => {
+                match prompt::key(&mut self.prompt, e) {
+                    Outcome::Entered((), src) => {
+                        self.error = None;
+                        self.edit(src, data);
+                    },
+                    Outcome::Cancelled => self.error = None,
+                    Outcome::Typing => {},
+                }
+
+                true
+            },
+
+            Key(e) => match data.keymap.lookup(&e, Scope::Registers) {
+                Some(action @ (RegsUp | RegsDown | RegsLeft | RegsRight)) => { self.select(action); true },
+                Some(RegsEdit) => { self.prompt = Some(Prompt::new(())); self.error = None; true },
+                _ => false,
+            },
+
             _ => false,
         }
     }
+
+    fn wants_key(&self, _key: &KeyEvent, _data: &TuiData<'a, 'int, C, I, O>) -> bool {
+        self.prompt.is_some()
+    }
 }
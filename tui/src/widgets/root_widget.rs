@@ -10,6 +10,7 @@
 // there's a use case for it.
 
 use super::widget_impl_support::*;
+use crate::keymap::{Action, Scope};
 
 pub use tui::widgets::Tabs as TabsBar;
 
@@ -116,88 +117,62 @@ where
 
     fn update(&mut self, event: WidgetEvent, data: &mut TuiData<'a, 'int, C, I, O>, terminal: &mut Terminal<B>) -> bool {
         use WidgetEvent::*;
-        const EMPTY: KeyModifiers = KeyModifiers::empty();
 
         match event {
-            Key(e) => match e {
-                KeyEvent { code: KeyCode::Char(n @ '1'..='9'), modifiers: KeyModifiers::CONTROL } |
-                KeyEvent { code: KeyCode::Char(n @ '1'..='9'), modifiers: KeyModifiers::ALT } => {
-                    // Switch to 0 indexing:
-                    self.drop_extra_focus(0, data, terminal);
-                    self.propagate_to_main(event, data, terminal)
-                },
-                KeyEvent { code: KeyCode::F(n), modifiers: EMPTY } => {
-                    // Switch to 0 indexing:
-                    self.drop_extra_focus(0, data, terminal);
-                    self.propagate_to_main(event, data, terminal)
-                }
-
-                // Crossterm seems to drop `ctrl` so we'll compromise with this for now (TODO):
-                KeyEvent { code: KeyCode::BackTab, modifiers: EMPTY } |
-                KeyEvent { code: KeyCode::BackTab, modifiers: KeyModifiers::CONTROL } => {
-                    self.drop_extra_focus(0, data, terminal);
-                    self.propagate_to_main(event, data, terminal)
-                }
-
-                KeyEvent { code: KeyCode::Char('i'), modifiers: KeyModifiers::ALT } |
-                KeyEvent { code: KeyCode::Char('i'), modifiers: KeyModifiers::CONTROL } |
-                KeyEvent { code: KeyCode::Char('o'), modifiers: KeyModifiers::ALT } |
-                KeyEvent { code: KeyCode::Char('o'), modifiers: KeyModifiers::CONTROL } |
-                KeyEvent { code: KeyCode::Char('u'), modifiers: KeyModifiers::ALT } |
-                KeyEvent { code: KeyCode::Char('u'), modifiers: KeyModifiers::CONTROL } |
-                KeyEvent { code: KeyCode::Char('s'), modifiers: KeyModifiers::CONTROL } |
-                KeyEvent { code: KeyCode::Char('p'), modifiers: KeyModifiers::CONTROL } |
-                KeyEvent { code: KeyCode::Char('r'), modifiers: KeyModifiers::CONTROL } => {
-                    self.propagate_to_footer(event, data, terminal)
-                }
-                KeyEvent { code: KeyCode::Char('t'), modifiers: KeyModifiers::CONTROL } => {
+            // The focused widget gets first dibs (i.e. a line-buffered
+            // console's `ctrl+u`, or anything while a prompt is open):
+            Key(e) if self.wants_key(&e, data) => self.propagate(event, data, terminal),
+
+            Key(e) => if let Some(_) = data.keymap.lookup(&e, Scope::Tabs) {
+                self.drop_extra_focus(0, data, terminal);
+                self.propagate_to_main(event, data, terminal)
+            } else if let Some(action) = data.keymap.lookup(&e, Scope::Modeline) {
+                if action == Action::Reset {
                     self.drop_extra_focus(0, data, terminal);
                     self.cur_focus = 1;
                     self.give_focus(1, data, terminal);
-                    self.propagate_to_footer(event, data, terminal)
-                }
-                KeyEvent { code: KeyCode::Char('l'), modifiers: KeyModifiers::CONTROL } => {
-                    self.propagate_to_footer(event, data, terminal)
                 }
 
-                KeyEvent { code: KeyCode::Down, modifiers: KeyModifiers::CONTROL } => {
-                    if self.cur_focus == 0 {
-                        let edge = self.propagate(event, data, terminal);
-                        if edge == false {
-                            self.drop_extra_focus(0, data, terminal);
-                            self.cur_focus = 1;
-                            self.give_focus(1, data, terminal);
+                self.propagate_to_footer(event, data, terminal)
+            } else {
+                match data.keymap.lookup(&e, Scope::Focus) {
+                    Some(Action::FocusDown) => {
+                        if self.cur_focus == 0 {
+                            let edge = self.propagate(event, data, terminal);
+                            if edge == false {
+                                self.drop_extra_focus(0, data, terminal);
+                                self.cur_focus = 1;
+                                self.give_focus(1, data, terminal);
+                            }
+                            true
+                        } else {
+                            false
                         }
-                        true
-                    } else {
-                        false
                     }
-                }
 
-                KeyEvent { code: KeyCode::Up, modifiers: KeyModifiers::CONTROL } => {
-                    if self.cur_focus == 1 {
-                        self.drop_extra_focus(1, data, terminal);
-                        self.cur_focus = 0;
-                        self.give_focus(0, data, terminal);
-                        true
-                    } else {
-                        self.propagate(event, data, terminal)
+                    Some(Action::FocusUp) => {
+                        if self.cur_focus == 1 {
+                            self.drop_extra_focus(1, data, terminal);
+                            self.cur_focus = 0;
+                            self.give_focus(0, data, terminal);
+                            true
+                        } else {
+                            self.propagate(event, data, terminal)
+                        }
                     }
-                }
 
-                // Crossterm seems to drop `ctrl` so we'll compromise with this for now (TODO):
-                KeyEvent { code: KeyCode::Tab, modifiers: EMPTY } |
-                KeyEvent { code: KeyCode::Tab, modifiers: KeyModifiers::CONTROL } => {
-                    self.drop_extra_focus(0, data, terminal);
-                    self.propagate_to_main(event, data, terminal)
+                    _ => self.propagate(event, data, terminal),
                 }
-
-                _ => self.propagate(event, data, terminal),
             }
 
             Mouse(e) => {
                 use MouseEvent::*;
                 match e {
+                    // Widgets are being resized; the main area gets the release
+                    // even if it happens over the footer.
+                    Up(_, _, _, _) if data.dragging_split => {
+                        self.propagate_to_main(event, data, terminal)
+                    },
                     Down(_, col, row, _) | Up(_, col, row, _) => {
                         if row >= self.footer_cutoff {
                             self.drop_extra_focus(0, data, terminal);
@@ -228,4 +203,8 @@ where
             _ => self.propagate(event, data, terminal)
         }
     }
+
+    fn wants_key(&self, key: &KeyEvent, data: &TuiData<'a, 'int, C, I, O>) -> bool {
+        self.components[self.cur_focus].wants_key(key, data)
+    }
 }
@@ -0,0 +1,147 @@
+//! The stacks: the words around R6 (and around the other mode's saved stack
+//! pointer), with what the last step pushed and popped.
+//!
+//! See [`stack`](crate::tui::stack) for where the saved stack pointer comes
+//! from.
+
+use super::widget_impl_support::*;
+use crate::markers;
+use crate::tui::stack::Change;
+
+use lc3_isa::{Addr, Bits, Word};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct StackInspector;
+
+// The offset from the stack pointer, as `R6+1` or `SSP-2`.
+fn offset(pointer: &str, off: i32) -> String {
+    if off < 0 {
+        format!("{}{}", pointer, off)
+    } else {
+        format!("{}+{}", pointer, off)
+    }
+}
+
+// One of the two stacks.
+struct Stack {
+    title: String,
+    colour: Colour,
+    /// What offsets are from (`R6`, or the saved stack pointer's name).
+    pointer: &'static str,
+    sp: Option<Word>,
+    /// What the last step did to it.
+    change: Option<Change>,
+}
+
+impl Stack {
+    // A title line and then the words around `sp`: a few below it (where
+    // pushes go) and the rest from it on up.
+    fn draw<'a, 'int, C, I, O>(&self, data: &TuiData<'a, 'int, C, I, O>, area: Rect, buf: &mut Buffer)
+    where
+        C: Control + ?Sized + 'a,
+        I: InputSink + ?Sized + 'a,
+        O: OutputSource + ?Sized + 'a,
+    {
+        let mut text = vec![TuiText::styled(format!("{}\n", self.title), Style::default().fg(self.colour).modifier(Modifier::BOLD))];
+
+        let sp = match self.sp {
+            Some(sp) => sp,
+            None => {
+                text.push(TuiText::styled("Not seen yet (the processor hasn't been in this mode).\n", Style::default().fg(c!(Disabled))));
+                return Paragraph::new(text.iter()).wrap(true).render(area, buf);
+            },
+        };
+
+        let rows = area.height.saturating_sub(1) as i32;
+        let below = rows / 4;
+
+        for off in -below..(rows - below) {
+            let addr = (sp as i32 + off) as Addr;
+            let word = data.sim.read_word(addr);
+            let changed = data.stack.changed(addr, word);
+
+            let (tag, style) = match (&self.change, changed) {
+                (Some(c @ Change::Pushed(_)), _) if c.contains(addr) => ("pushed".to_string(), Style::default().fg(c!(Success)).modifier(Modifier::BOLD)),
+                (Some(c @ Change::Popped(_)), _) if c.contains(addr) => ("popped".to_string(), Style::default().fg(c!(Disabled))),
+                (_, Some(old)) => (format!("was x{:04X}", old), Style::default().fg(c!(RegHighlight))),
+                (_, None) if off < 0 => (String::new(), Style::default().fg(c!(Disabled))),
+                (_, None) => (String::new(), Style::default().fg(c!(Data))),
+            };
+            let marker = if changed.is_some() && markers::enabled() { markers::CHANGED } else { " " };
+
+            let label = Style::default().fg(if off == 0 { self.colour } else { c!(Name) });
+            text.push(TuiText::styled(format!("{:>7} ", offset(self.pointer, off)), label));
+            text.push(TuiText::styled(format!("x{:04X}  ", addr), Style::default().fg(c!(Addr))));
+            text.push(TuiText::styled(format!("x{:04X} {:>6}{} ", word, word as i16, marker), style));
+            text.push(TuiText::styled(format!("{}\n", tag), style));
+        }
+
+        Paragraph::new(text.iter())
+            .style(Style::default().fg(Colour::White).bg(Colour::Reset))
+            .render(area, buf);
+    }
+}
+
+impl<'a, 'int, C, I, O, B> Widget<'a, 'int, C, I, O, B> for StackInspector
+where
+    C: Control + ?Sized + 'a,
+    I: InputSink + ?Sized + 'a,
+    O: OutputSource + ?Sized + 'a,
+    B: Backend,
+{
+    fn draw(&mut self, data: &TuiData<'a, 'int, C, I, O>, area: Rect, buf: &mut Buffer) {
+        if area.height < 4 {
+            return;
+        }
+
+        let (regs, psr, _) = data.sim.get_registers_psr_and_pc();
+        let (sp, supervisor) = (regs[6], !psr.bit(15));
+        let region = data.config.stack_region.unwrap_or_default();
+
+        // The region is for the program's stack (see `track_stack`):
+        let checked = !supervisor || !data.use_os;
+        let header = if checked && !region.contains(sp) {
+            TuiText::styled(
+                format!("R6 (x{:04X}) is outside the stack region (x{:04X} to x{:04X})!", sp, region.low, region.high),
+                Style::default().fg(c!(Error)).modifier(Modifier::BOLD),
+            )
+        } else {
+            TuiText::styled(format!("Stack region: x{:04X} to x{:04X}", region.low, region.high), Style::default().fg(c!(Disabled)))
+        };
+        Paragraph::new([header].iter())
+            .render(Rect { height: 1, ..area }, buf);
+
+        // The stack in use gets two thirds of the space:
+        let body = Rect { y: area.y + 2, height: area.height - 2, ..area };
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(66), Constraint::Percentage(34)].as_ref())
+            .split(body);
+
+        let user = ("User stack", "USP", c!(CallStackUserMode), data.stack.user_sp);
+        let supervisor_stack = ("Supervisor stack", "SSP", c!(CallStackSupervisorMode), data.stack.supervisor_sp);
+        let (active, saved) = if supervisor { (supervisor_stack, user) } else { (user, supervisor_stack) };
+
+        let active = Stack {
+            title: format!("{} (R6)", active.0),
+            colour: active.2,
+            pointer: "R6",
+            sp: Some(sp),
+            change: data.stack.change(),
+        };
+        let saved = Stack {
+            title: format!("{} (saved {}, as last seen)", saved.0, saved.1),
+            colour: saved.2,
+            pointer: saved.1,
+            sp: saved.3,
+            change: None,
+        };
+
+        active.draw(data, chunks[0], buf);
+        saved.draw(data, chunks[1], buf);
+    }
+
+    fn update(&mut self, _event: WidgetEvent, _data: &mut TuiData<'a, 'int, C, I, O>, _terminal: &mut Terminal<B>) -> bool {
+        false
+    }
+}
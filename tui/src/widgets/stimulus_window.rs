@@ -0,0 +1,79 @@
+//! The stimulus script's upcoming events and the ones that have fired (see
+//! [`stimulus`](crate::tui::stimulus)).
+
+use super::widget_impl_support::*;
+use crate::tui::stimulus::When;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct StimulusWindow;
+
+impl<'a, 'int, C, I, O, B> Widget<'a, 'int, C, I, O, B> for StimulusWindow
+where
+    C: Control + ?Sized + 'a,
+    I: InputSink + ?Sized + 'a,
+    O: OutputSource + ?Sized + 'a,
+    B: Backend,
+{
+    fn draw(&mut self, data: &TuiData<'a, 'int, C, I, O>, area: Rect, buf: &mut Buffer) {
+        let script = match data.stimulus.as_ref() {
+            Some(s) => s,
+            None => {
+                let hint = TuiText::styled(
+                    "No stimulus script. Load one with `stimulus load <path>` in the peripheral console (or `--stimulus`).",
+                    Style::default().fg(c!(ConsoleHelp)),
+                );
+
+                return Paragraph::new([hint].iter())
+                    .style(Style::default().bg(Colour::Reset))
+                    .wrap(true)
+                    .render(area, buf);
+            },
+        };
+
+        let name = Style::default().fg(c!(Name));
+        let data_style = Style::default().fg(c!(Data));
+
+        let mut text = vec![
+            TuiText::styled(script.name.clone(), name),
+            TuiText::styled(format!(": {}.{:03}s, {} steps in\n", script.millis / 1000, script.millis % 1000, script.steps), data_style),
+        ];
+
+        // Upcoming events get (up to) half of what's left, fired events the
+        // rest:
+        let rows = area.height.saturating_sub(3) as usize;
+        let upcoming = script.upcoming();
+        let shown = upcoming.len().min((rows + 1) / 2);
+
+        text.push(TuiText::styled(format!("Next ({}):\n", upcoming.len()), name));
+        for event in upcoming.iter().take(shown) {
+            let next = match event.when {
+                When::Millis(_) => When::Millis(event.next.unwrap_or_default()),
+                When::Steps(_) => When::Steps(event.next.unwrap_or_default()),
+            };
+            let every = if event.repeats { format!(", every {}", event.when) } else { String::new() };
+
+            text.push(TuiText::styled(format!("  {:>9}", next.to_string()), data_style));
+            text.push(TuiText::styled(format!("  {}", event.action), Style::default().fg(c!(Success))));
+            text.push(TuiText::styled(format!("  (line {}{})\n", event.line, every), Style::default().fg(c!(Disabled))));
+        }
+
+        text.push(TuiText::styled("Fired:\n", name));
+        for fired in script.fired.iter().rev().take(rows.saturating_sub(shown)) {
+            text.push(TuiText::styled(format!("  {:>9}", fired.at.to_string()), data_style));
+            match &fired.error {
+                None => text.push(TuiText::styled(format!("  {}", fired.action), Style::default().fg(c!(Success)))),
+                Some(err) => text.push(TuiText::styled(format!("  {}: {}", fired.action, err), Style::default().fg(c!(Error)))),
+            }
+            text.push(TuiText::styled(format!("  (line {})\n", fired.line), Style::default().fg(c!(Disabled))));
+        }
+
+        Paragraph::new(text.iter())
+            .style(Style::default().fg(Colour::White).bg(Colour::Reset))
+            .alignment(Alignment::Left)
+            .render(area, buf);
+    }
+
+    fn update(&mut self, _event: WidgetEvent, _data: &mut TuiData<'a, 'int, C, I, O>, _terminal: &mut Terminal<B>) -> bool {
+        false
+    }
+}
@@ -10,6 +10,7 @@
 // there's a use case for it.
 
 use super::widget_impl_support::*;
+use crate::keymap::{Action, Scope};
 
 pub use tui::widgets::Tabs as TabsBar;
 
@@ -134,31 +135,26 @@ where
 
     fn update(&mut self, event: WidgetEvent, data: &mut TuiData<'a, 'int, C, I, O>, terminal: &mut Terminal<B>) -> bool {
         use WidgetEvent::*;
-        const EMPTY: KeyModifiers = KeyModifiers::empty();
+        use Action::*;
 
         match event {
-            Key(e) => match e {
-                KeyEvent { code: KeyCode::Char(n @ '1'..='9'), modifiers: KeyModifiers::CONTROL } |
-                KeyEvent { code: KeyCode::Char(n @ '1'..='9'), modifiers: KeyModifiers::ALT } => {
-                    // Switch to 0 indexing:
-                    self.switch_to_tab(data, terminal, n as usize - '1' as usize)
-                },
-                KeyEvent { code: KeyCode::F(n), modifiers: EMPTY } => {
-                    // Switch to 0 indexing:
-                    self.switch_to_tab(data, terminal, n as usize - 1)
+            Key(e) => match data.keymap.lookup(&e, Scope::Tabs).filter(|_| !self.wants_key(&e, data)) {
+                Some(NextTab) => {
+                    self.switch_to_tab(data, terminal, self.current_tab.checked_add(1).filter(|i| *i < self.tabs.len()).unwrap_or(0))
                 }
-
-                // Crossterm seems to drop `ctrl` so we'll compromise with this for now (TODO):
-                KeyEvent { code: KeyCode::BackTab, modifiers: EMPTY } |
-                KeyEvent { code: KeyCode::BackTab, modifiers: KeyModifiers::CONTROL } => {
+                Some(PrevTab) => {
                     self.switch_to_tab(data, terminal, self.current_tab.checked_sub(1).unwrap_or(self.tabs.len() - 1))
                 }
 
-                // Crossterm seems to drop `ctrl` so we'll compromise with this for now (TODO):
-                KeyEvent { code: KeyCode::Tab, modifiers: EMPTY } |
-                KeyEvent { code: KeyCode::Tab, modifiers: KeyModifiers::CONTROL } => {
-                    self.switch_to_tab(data, terminal, self.current_tab.checked_add(1).filter(|i| *i < self.tabs.len()).unwrap_or(0))
-                }
+                Some(Tab1) => self.switch_to_tab(data, terminal, 0),
+                Some(Tab2) => self.switch_to_tab(data, terminal, 1),
+                Some(Tab3) => self.switch_to_tab(data, terminal, 2),
+                Some(Tab4) => self.switch_to_tab(data, terminal, 3),
+                Some(Tab5) => self.switch_to_tab(data, terminal, 4),
+                Some(Tab6) => self.switch_to_tab(data, terminal, 5),
+                Some(Tab7) => self.switch_to_tab(data, terminal, 6),
+                Some(Tab8) => self.switch_to_tab(data, terminal, 7),
+                Some(Tab9) => self.switch_to_tab(data, terminal, 8),
 
                 _ => self.propagate(event, data, terminal),
             }
@@ -172,4 +168,8 @@ where
             _ => self.propagate(event, data, terminal)
         }
     }
+
+    fn wants_key(&self, key: &KeyEvent, data: &TuiData<'a, 'int, C, I, O>) -> bool {
+        self.tabs[self.current_tab].wants_key(key, data)
+    }
 }
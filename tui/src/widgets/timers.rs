@@ -1,8 +1,19 @@
-//! TODO!
+//! The timers: how long until each one next fires, how many times it has
+//! fired and when it last did (and roughly where the program was: see
+//! [`Fire::pc`]).
+//!
+//! See [`timer_history`](crate::tui::timer_history) for how fires are worked
+//! out.
 
 use super::widget_impl_support::*;
+use crate::tui::peripheral_commands::TIMERS;
+use crate::tui::time::now;
+use crate::tui::timer_history::Fire;
 
-use lc3_traits::peripherals::timers::{TimerId, TimerState, TimerMode};
+use std::collections::VecDeque;
+
+/// How much time the timeline covers, in milliseconds.
+const TIMELINE_SPAN: u64 = 10_000;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Timers {
@@ -24,6 +35,24 @@ impl Timers {
     }
 }
 
+fn seconds(ms: u64) -> String {
+    format!("{}.{:03}s", ms / 1000, ms % 1000)
+}
+
+// The last `TIMELINE_SPAN` (ending now, on the right) with a mark for every
+// fire.
+fn timeline(fires: &VecDeque<Fire>, now: u64, width: usize) -> String {
+    let mut line = vec!['─'; width];
+    for fire in fires {
+        let age = now.saturating_sub(fire.at);
+        if age < TIMELINE_SPAN && width > 0 {
+            line[width - 1 - (age as usize * width / TIMELINE_SPAN as usize)] = '┃';
+        }
+    }
+
+    line.into_iter().collect()
+}
+
 impl<'a, 'int, C, I, O, B> Widget<'a, 'int, C, I, O, B> for Timers
 where
     C: Control + ?Sized + 'a,
@@ -31,124 +60,76 @@ where
     O: OutputSource + ?Sized + 'a,
     B: Backend,
 {
-
     fn draw(&mut self, data: &TuiData<'a, 'int, C, I, O>, area: Rect, buf: &mut Buffer) {
-        let timer_state = data.sim.get_timer_states();
-        let timer_modes = data.sim.get_timer_modes();
-
-
-        let text = [
-            TuiText::styled("Timer 0: \n", Style::default().fg(c!(Name))),
-        ];
-
-        let mut para = Paragraph::new(text.iter())
-            .style(Style::default().fg(Colour::White).bg(Colour::Reset))
-            .alignment(Alignment::Left)
-            .wrap(true);
+        const LABEL: usize = 9; // "Timer 0  "
 
-        para.render(area, buf);
+        let now = now();
 
+        // Each timer gets its half: a line saying what it's up to, then (if
+        // there's room) a countdown, the timeline and then as many of the
+        // most recent fires as fit.
+        let rows = (area.height / TIMERS.len() as u16) as usize;
+        let width = (area.width as usize).saturating_sub(LABEL);
 
-        let mut s0 = String::from("");
-
-        let t0 = match timer_state[TimerId::T0] {
-            TimerState::Disabled => {
-                let disabled_string = "Disabled";
-                s0.push_str(&format!("{}\n", disabled_string))
+        for idx in 0..TIMERS.len() {
+            if rows == 0 {
+                break;
             }
-            TimerState::WithPeriod(period) => {
-                match timer_modes[TimerId::T0]{
-                        TimerMode::Repeated => {
-                            s0.push_str(&format!(
-                                "Repeated: {:#018b} {:#06x} {:#05}\n",
-                                period,
-                                period,
-                                period,
-                            ))
+
+            let history = &data.timer_history[idx];
+            let config = history.config();
+
+            let colour = if config.is_some() { c!(Data) } else { c!(Disabled) };
+            let label = |s: &str| TuiText::styled(format!("{:1$}", s, LABEL), Style::default().fg(c!(Name)));
+            let line = |s: String| TuiText::styled(format!("{}\n", s), Style::default().fg(colour));
+
+            let fired = match history.fire_count {
+                1 => ", fired once".to_string(),
+                n => format!(", fired {} times", n),
+            };
+
+            let mut text = vec![label(&format!("Timer {}", idx))];
+            text.push(line(match config {
+                None => format!("disabled{}", fired),
+                Some((period, true)) => format!("repeating every {}ms{}", period, fired),
+                Some((period, false)) => format!("single shot after {}ms{}", period, fired),
+            }));
+
+            if rows >= 2 {
+                text.push(label(""));
+                match (config, history.remaining(now)) {
+                    (Some((period, _)), Some(remaining)) => {
+                        let bar = width.saturating_sub(16).min(40);
+                        let filled = ((period - remaining) as usize * bar / period as usize).min(bar);
+
+                        text.push(TuiText::styled("█".repeat(filled), Style::default().fg(c!(Data))));
+                        text.push(TuiText::styled("░".repeat(bar - filled), Style::default().fg(c!(Disabled))));
+                        text.push(line(format!(" {} left", seconds(remaining))));
                     },
-                        TimerMode::SingleShot => {
-                            s0.push_str(&format!(
-                                "Single: {:#018b} {:#06x} {:#05}\n",
-                                period,
-                                period,
-                                period,
-                            ))
-                        }
-
-                };
-
-
-            },
-
-        };
-
-        let text = [TuiText::styled(s0, Style::default().fg(c!(Data)))];
-        para = Paragraph::new(text.iter())
-            .style(Style::default().fg(Colour::White).bg(Colour::Reset))
-            .alignment(Alignment::Left)
-            .wrap(true);
-        let area = increment(10, Axis::X, area);
-        para.render(area, buf);
-
-        let text = [
-            TuiText::styled("Timer 1: \n", Style::default().fg(c!(Name))),
-        ];
-
-        let mut para = Paragraph::new(text.iter())
-            .style(Style::default().fg(Colour::White).bg(Colour::Reset))
-            .alignment(Alignment::Left)
-            .wrap(true);
-        let area = increment(40, Axis::X, area);
-        para.render(area, buf);
-
-        let mut s1 = String::from("");
-
-        let t1 = match timer_state[TimerId::T1] {
-            TimerState::Disabled => {
-                let disabled_string = "Disabled";
-                s1.push_str(&format!("{}\n", disabled_string))
+                    (Some(_), None) => text.push(line("fired; set it up again to restart it".to_string())),
+                    (None, _) => text.push(line(String::new())),
+                }
             }
-            TimerState::WithPeriod(period) => {
-                match timer_modes[TimerId::T0]{
-                        TimerMode::Repeated => {
-                            s1.push_str(&format!(
-                                "Repeated: {:#018b} {:#06x} {:#05}\n",
-                                period,
-                                period,
-                                period,
-                            ))
-                    },
-                        TimerMode::SingleShot => {
-                            s1.push_str(&format!(
-                                "Single: {:#018b} {:#06x} {:#05}\n",
-                                period,
-                                period,
-                                period,
-                            ))
-                        }
-
-                };
-
-            },
-
-        };
-
-
-
-        let text = [TuiText::styled(s1, Style::default().fg(c!(Data)))];
-        para = Paragraph::new(text.iter())
-            .style(Style::default().fg(Colour::White).bg(Colour::Reset))
-            .alignment(Alignment::Left)
-            .wrap(true);
-        let area = increment(10, Axis::X, area);
-        para.render(area, buf);
 
+            if rows >= 3 {
+                text.push(label(&format!("last {}s", TIMELINE_SPAN / 1000)));
+                text.push(line(timeline(&history.recent, now, width)));
+            }
 
+            for fire in history.recent.iter().rev().take(rows.saturating_sub(3)) {
+                text.push(label(""));
+                text.push(TuiText::styled(format!("{} ago", seconds(now.saturating_sub(fire.at))), Style::default().fg(c!(Data))));
+                text.push(TuiText::styled(format!(" (estimated PC x{:04X}; the Interrupts widget has the saved one)\n", fire.pc), Style::default().fg(c!(Name))));
+            }
 
+            let y = area.y + (idx * rows) as u16;
+            Paragraph::new(text.iter())
+                .style(Style::default().fg(Colour::White).bg(Colour::Reset))
+                .alignment(Alignment::Left)
+                .render(Rect { y, height: rows as u16, ..area }, buf);
+        }
     }
 
-
-
     fn update(&mut self, event: WidgetEvent, _data: &mut TuiData<'a, 'int, C, I, O>, _terminal: &mut Terminal<B>) -> bool {
         match event {
             WidgetEvent::Mouse(_) | WidgetEvent::Focus(FocusEvent::GotFocus) => self.focusable,
@@ -156,3 +137,17 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod timers_tests {
+    use super::*;
+    use pretty_assertions::assert_eq as eq;
+
+    #[test]
+    fn timelines() {
+        let fires = [0, 5_000, 9_000, 10_000].iter().map(|at| Fire { at: *at, pc: 0x3000 }).collect();
+        eq!(timeline(&fires, 10_000, 10), "────┃───┃┃");
+        eq!(timeline(&VecDeque::new(), 10_000, 3), "───");
+        eq!(seconds(1_234), "1.234s");
+    }
+}